@@ -2,7 +2,9 @@ use anchor_lang::prelude::*;
 
 // Program IDs for token programs
 pub const SPL_TOKEN_PROGRAM_ID: Pubkey = spl_token::ID;
-pub const SPL_TOKEN_2022_PROGRAM_ID: Pubkey = spl_token_2022::ID;
+// Spelled out so builds without the `token-2022` feature need no spl_token_2022
+pub const SPL_TOKEN_2022_PROGRAM_ID: Pubkey =
+    anchor_lang::solana_program::pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
 
 // Seeds for PDA generation
 pub const POOL_SEED: &[u8] = b"pool";
@@ -11,11 +13,27 @@ pub const VAULT_SEED: &[u8] = b"vault";
 
 // Pool configuration
 pub const MAX_WHITELISTED_HOOKS: usize = 10;
-pub const MIN_LIQUIDITY: u64 = 1000; // Minimum liquidity to prevent division by zero
+pub const MIN_LIQUIDITY: u64 = 1000; // Default LP locked for good by the first deposit
+pub const MAX_LIQUIDITY_LOCK_BPS: u64 = 1000; // 10%, upper bound of the lock relative to the first deposit's LP
 pub const FEE_DENOMINATOR: u64 = 10000; // For percentage calculations (0.01% = 1/10000)
 
 // Default fees (in basis points)
 pub const DEFAULT_TRADE_FEE: u64 = 30; // 0.3%
 pub const DEFAULT_OWNER_FEE: u64 = 5; // 0.05%
+pub const MAX_FEE_BPS: u64 = 1000; // 10%, upper bound for owner-set fees
+
+// Swap curves
+pub const CURVE_TYPE_CONSTANT_PRODUCT: u8 = 0;
+pub const CURVE_TYPE_STABLE: u8 = 1;
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 10_000; // Amplification coefficient bounds for stable pools
+
+// Price oracle
+pub const MAX_ORACLE_AGE_SLOTS: u64 = 25; // ~10s, older feeds are too stale to check swaps against
+
+// Pool status
+pub const POOL_STATUS_ACTIVE: u64 = 1;
+pub const POOL_STATUS_PAUSED: u64 = 2;
+pub const MAX_PAUSE_DURATION: i64 = 7 * 24 * 60 * 60; // A pause lapses after 7 days unless renewed, on auto-unpause pools
 
 pub const ANCHOR_DISCRIMINATOR: usize = 8;
@@ -0,0 +1,1231 @@
+//! Constant-product and StableSwap curve math shared by the swap and deposit
+//! handlers and off-chain tooling.
+
+use crate::constants::{
+    CURVE_TYPE_CONSTANT_PRODUCT, CURVE_TYPE_STABLE, FEE_DENOMINATOR, MAX_AMP, MIN_AMP,
+};
+use crate::error::TradiumError;
+use crate::state::{sys_decimals_from_value, Fees};
+use anchor_lang::prelude::*;
+
+/// Input amount left after the swap fee is taken.
+pub fn amount_in_after_fee(
+    amount_in: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    let amount_in_after_fee = (amount_in as u128)
+        .checked_mul(
+            fee_denominator
+                .checked_sub(fee_numerator)
+                .ok_or(TradiumError::MathOverflow)? as u128,
+        )
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(fee_denominator as u128)
+        .ok_or(TradiumError::MathOverflow)?;
+    // At most `amount_in`, so this always narrows back
+    u64::try_from(amount_in_after_fee).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Output of a constant-product swap after fees:
+/// `amount_out = (amount_in_after_fee * reserve_out) / (reserve_in + amount_in_after_fee)`
+pub fn get_amount_out(
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    let amount_in_after_fee = amount_in_after_fee(amount_in, fee_numerator, fee_denominator)?;
+    constant_product_amount_out(amount_in_after_fee, reserve_in, reserve_out)
+}
+
+/// The `x * y = k` output for an input already net of fees:
+/// `amount_out = amount_in_net * reserve_out / (reserve_in + amount_in_net)`.
+/// Rounds down, so the reserves after the swap never hold a smaller product
+/// than before: `(reserve_in + amount_in_net) * (reserve_out - amount_out)
+/// >= reserve_in * reserve_out`.
+pub fn constant_product_amount_out(
+    amount_in_net: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+) -> Result<u64> {
+    // u128 intermediates: the product overflows u64 for large inputs and vaults
+    let new_reserve_in = reserve_in as u128 + amount_in_net as u128;
+    let amount_out = (amount_in_net as u128)
+        .checked_mul(reserve_out as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(new_reserve_in)
+        .ok_or(TradiumError::MathOverflow)?;
+
+    // Ensure output amount doesn't exceed the output reserve
+    require!(
+        amount_out <= reserve_out as u128,
+        TradiumError::InsufficientLiquidity
+    );
+
+    u64::try_from(amount_out).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Input for which `get_amount_out` yields at least `amount_out`: the curve
+/// solved backwards, `amount_in_net = reserve_in * amount_out / (reserve_out - amount_out)`,
+/// then grossed up by the fee. Both steps round up.
+pub fn get_amount_in(
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    require!(
+        amount_out < reserve_out,
+        TradiumError::InsufficientLiquidity
+    );
+    let fee_keep = fee_denominator
+        .checked_sub(fee_numerator)
+        .filter(|keep| *keep > 0)
+        .ok_or(TradiumError::MathOverflow)?;
+
+    let amount_in_net = (reserve_in as u128)
+        .checked_mul(amount_out as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        .div_ceil((reserve_out - amount_out) as u128);
+    let amount_in = amount_in_net
+        .checked_mul(fee_denominator as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        .div_ceil(fee_keep as u128);
+    u64::try_from(amount_in).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Pricing curve of a pool, chosen at init by `Tradium::curve_type`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwapCurve {
+    /// `x * y = k`, the default
+    ConstantProduct,
+    /// StableSwap invariant with amplification coefficient `amp`: close to
+    /// constant-sum near the balanced point, constant-product far from it
+    Stable { amp: u64 },
+}
+
+impl SwapCurve {
+    /// The curve for a `curve_type` and amplification coefficient, or
+    /// `InvalidCurveConfig` for an unknown type or an `amp` out of bounds.
+    pub fn from_config(curve_type: u8, amp: u64) -> Result<Self> {
+        match curve_type {
+            CURVE_TYPE_CONSTANT_PRODUCT => Ok(Self::ConstantProduct),
+            CURVE_TYPE_STABLE if (MIN_AMP..=MAX_AMP).contains(&amp) => Ok(Self::Stable { amp }),
+            _ => err!(TradiumError::InvalidCurveConfig),
+        }
+    }
+
+    /// Output for `amount_in` after fees, see `get_amount_out`.
+    pub fn amount_out(
+        self,
+        amount_in: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_numerator: u64,
+        fee_denominator: u64,
+    ) -> Result<u64> {
+        match self {
+            Self::ConstantProduct => get_amount_out(
+                amount_in,
+                reserve_in,
+                reserve_out,
+                fee_numerator,
+                fee_denominator,
+            ),
+            Self::Stable { amp } => stable_amount_out(
+                amp,
+                amount_in,
+                reserve_in,
+                reserve_out,
+                fee_numerator,
+                fee_denominator,
+            ),
+        }
+    }
+
+    /// Input yielding at least `amount_out`, see `get_amount_in`.
+    pub fn amount_in(
+        self,
+        amount_out: u64,
+        reserve_in: u64,
+        reserve_out: u64,
+        fee_numerator: u64,
+        fee_denominator: u64,
+    ) -> Result<u64> {
+        match self {
+            Self::ConstantProduct => get_amount_in(
+                amount_out,
+                reserve_in,
+                reserve_out,
+                fee_numerator,
+                fee_denominator,
+            ),
+            Self::Stable { amp } => stable_amount_in(
+                amp,
+                amount_out,
+                reserve_in,
+                reserve_out,
+                fee_numerator,
+                fee_denominator,
+            ),
+        }
+    }
+}
+
+/// Newton iterations allowed before the StableSwap solvers give up.
+const STABLE_MAX_ITERATIONS: usize = 255;
+
+/// StableSwap invariant `D` of a two-token pool, solved by Newton's method
+/// from `D = x + y`:
+/// `D' = (Ann * S + 2 * D_P) * D / ((Ann - 1) * D + 3 * D_P)`,
+/// with `Ann = 4 * amp`, `S = x + y` and `D_P = D^3 / (4 * x * y)`.
+pub fn stable_invariant(amp: u64, x: u64, y: u64) -> Result<u128> {
+    require!(x > 0 && y > 0, TradiumError::EmptyPool);
+    let ann = amp as u128 * 4;
+    let (x, y) = (x as u128, y as u128);
+    let sum = x + y;
+
+    let mut d = sum;
+    for _ in 0..STABLE_MAX_ITERATIONS {
+        let d_p = d
+            .checked_mul(d)
+            .and_then(|dd| dd.checked_div(x * 2))
+            .and_then(|dd| dd.checked_mul(d))
+            .and_then(|ddd| ddd.checked_div(y * 2))
+            .ok_or(TradiumError::MathOverflow)?;
+        let d_prev = d;
+        let numerator = ann
+            .checked_mul(sum)
+            .and_then(|n| n.checked_add(d_p.checked_mul(2)?))
+            .and_then(|n| n.checked_mul(d))
+            .ok_or(TradiumError::MathOverflow)?;
+        let denominator = (ann - 1)
+            .checked_mul(d)
+            .and_then(|n| n.checked_add(d_p.checked_mul(3)?))
+            .ok_or(TradiumError::MathOverflow)?;
+        d = numerator
+            .checked_div(denominator)
+            .ok_or(TradiumError::MathOverflow)?;
+        if d.abs_diff(d_prev) <= 1 {
+            return Ok(d);
+        }
+    }
+    err!(TradiumError::MathOverflow)
+}
+
+/// The other reserve at which a two-token StableSwap pool with one reserve at
+/// `x` keeps invariant `d`, solved by Newton's method from `y = D`:
+/// `y' = (y^2 + c) / (2y + b - D)`, with `c = D^3 / (4 * x * Ann)` and
+/// `b = x + D / Ann`.
+pub fn stable_other_reserve(amp: u64, x: u64, d: u128) -> Result<u128> {
+    require!(x > 0, TradiumError::EmptyPool);
+    let ann = amp as u128 * 4;
+    let x = x as u128;
+    let c = d
+        .checked_mul(d)
+        .and_then(|dd| dd.checked_div(x * 2))
+        .and_then(|dd| dd.checked_mul(d))
+        .and_then(|ddd| ddd.checked_div(ann * 2))
+        .ok_or(TradiumError::MathOverflow)?;
+    let b = x + d / ann;
+
+    let mut y = d;
+    for _ in 0..STABLE_MAX_ITERATIONS {
+        let y_prev = y;
+        let numerator = y
+            .checked_mul(y)
+            .and_then(|yy| yy.checked_add(c))
+            .ok_or(TradiumError::MathOverflow)?;
+        let denominator = (y * 2)
+            .checked_add(b)
+            .and_then(|n| n.checked_sub(d))
+            .filter(|n| *n > 0)
+            .ok_or(TradiumError::MathOverflow)?;
+        y = numerator / denominator;
+        if y.abs_diff(y_prev) <= 1 {
+            return Ok(y);
+        }
+    }
+    err!(TradiumError::MathOverflow)
+}
+
+/// Output of a StableSwap swap after fees: the input, net of the fee, joins
+/// `reserve_in` and the output reserve drops to keep the invariant. Rounds
+/// down by a unit in the pool's favor.
+pub fn stable_amount_out(
+    amp: u64,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    let amount_in_after_fee = amount_in_after_fee(amount_in, fee_numerator, fee_denominator)?;
+    let d = stable_invariant(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = reserve_in
+        .checked_add(amount_in_after_fee)
+        .ok_or(TradiumError::MathOverflow)?;
+    let new_reserve_out = stable_other_reserve(amp, new_reserve_in, d)?;
+    let amount_out = (reserve_out as u128)
+        .saturating_sub(new_reserve_out)
+        .saturating_sub(1);
+    u64::try_from(amount_out).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Input for which `stable_amount_out` yields at least `amount_out`: the
+/// invariant solved for the input reserve, then grossed up by the fee. Both
+/// steps round up.
+pub fn stable_amount_in(
+    amp: u64,
+    amount_out: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+) -> Result<u64> {
+    require!(
+        amount_out < reserve_out,
+        TradiumError::InsufficientLiquidity
+    );
+    let fee_keep = fee_denominator
+        .checked_sub(fee_numerator)
+        .filter(|keep| *keep > 0)
+        .ok_or(TradiumError::MathOverflow)?;
+
+    let d = stable_invariant(amp, reserve_in, reserve_out)?;
+    let new_reserve_in = stable_other_reserve(amp, reserve_out - amount_out, d)?;
+    let amount_in_net = new_reserve_in
+        .checked_sub(reserve_in as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        + 1;
+    let amount_in = amount_in_net
+        .checked_mul(fee_denominator as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        .div_ceil(fee_keep as u128);
+    u64::try_from(amount_in).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Fixed-point scale for prices returned by `spot_price`.
+pub const PRICE_SCALE: u128 = 1_000_000_000_000;
+
+/// Minimum output to accept for swapping `amount_in` at the given reserves
+/// and fees, allowing `slippage_bps` below the quote. For mints without
+/// a transfer fee this is the bound `swap_with_slippage_bps` computes.
+pub fn min_received(
+    swap_curve: SwapCurve,
+    amount_in: u64,
+    reserve_in: u64,
+    reserve_out: u64,
+    fees: &Fees,
+    slippage_bps: u64,
+) -> Result<u64> {
+    let (fee_numerator, fee_denominator) = fees.total_swap_fee()?;
+    let expected_amount_out = swap_curve.amount_out(
+        amount_in,
+        reserve_in,
+        reserve_out,
+        fee_numerator,
+        fee_denominator,
+    )?;
+    min_amount_out_from_bps(expected_amount_out, slippage_bps)
+}
+
+/// `expected * (10000 - slippage_bps) / 10000`, rounded down.
+pub fn min_amount_out_from_bps(expected_amount_out: u64, max_slippage_bps: u64) -> Result<u64> {
+    require!(
+        max_slippage_bps <= FEE_DENOMINATOR,
+        TradiumError::InvalidSlippageBps
+    );
+    let min_amount_out = (expected_amount_out as u128)
+        .checked_mul((FEE_DENOMINATOR - max_slippage_bps) as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        / FEE_DENOMINATOR as u128;
+    Ok(min_amount_out as u64)
+}
+
+/// Spot price of one coin base unit in pc base units, scaled by `PRICE_SCALE`.
+pub fn spot_price(coin_reserve: u64, pc_reserve: u64) -> Result<u128> {
+    require!(coin_reserve > 0, TradiumError::EmptyPool);
+    (pc_reserve as u128)
+        .checked_mul(PRICE_SCALE)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(coin_reserve as u128)
+        .ok_or(TradiumError::MathOverflow.into())
+}
+
+/// Pc amount matching `amount_coin` at the current reserve ratio, rounded up
+/// so a deposit of the pair never takes a larger share than it pays for.
+pub fn pair_amount(amount_coin: u64, coin_reserve: u64, pc_reserve: u64) -> Result<u64> {
+    // A pair amount needs an existing ratio to match
+    require!(coin_reserve > 0 && pc_reserve > 0, TradiumError::EmptyPool);
+
+    let amount_pc = (amount_coin as u128)
+        .checked_mul(pc_reserve as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_add(coin_reserve as u128 - 1)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(coin_reserve as u128)
+        .ok_or(TradiumError::MathOverflow)?;
+    u64::try_from(amount_pc).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Average price a swap executed at in whole tokens: pc per coin, corrected
+/// for the mints' decimals and scaled by `PRICE_SCALE`.
+pub fn scaled_execution_price(
+    amount_in: u64,
+    amount_out: u64,
+    swap_direction: u8,
+    coin_decimals: u64,
+    pc_decimals: u64,
+) -> Result<u128> {
+    let (pc_amount, coin_amount) = if swap_direction == 0 {
+        (amount_out, amount_in)
+    } else {
+        (amount_in, amount_out)
+    };
+    require!(coin_amount > 0, TradiumError::InvalidInputAmount);
+
+    // One whole coin is 10^coin_decimals base units, one whole pc 10^pc_decimals
+    let decimals_scale = |diff: u64| {
+        u32::try_from(diff)
+            .ok()
+            .and_then(|exp| 10_u128.checked_pow(exp))
+            .ok_or(TradiumError::MathOverflow)
+    };
+    let mut numerator = (pc_amount as u128)
+        .checked_mul(PRICE_SCALE)
+        .ok_or(TradiumError::MathOverflow)?;
+    let mut denominator = coin_amount as u128;
+    if coin_decimals >= pc_decimals {
+        numerator = numerator
+            .checked_mul(decimals_scale(coin_decimals - pc_decimals)?)
+            .ok_or(TradiumError::MathOverflow)?;
+    } else {
+        denominator = denominator
+            .checked_mul(decimals_scale(pc_decimals - coin_decimals)?)
+            .ok_or(TradiumError::MathOverflow)?;
+    }
+    numerator
+        .checked_div(denominator)
+        .ok_or(TradiumError::MathOverflow.into())
+}
+
+/// Average price a swap executed at: pc base units per coin base unit,
+/// scaled by `PRICE_SCALE`, whichever the direction.
+pub fn execution_price(amount_in: u64, amount_out: u64, swap_direction: u8) -> Result<u128> {
+    let (pc_amount, coin_amount) = if swap_direction == 0 {
+        (amount_out, amount_in)
+    } else {
+        (amount_in, amount_out)
+    };
+    require!(coin_amount > 0, TradiumError::InvalidInputAmount);
+    (pc_amount as u128)
+        .checked_mul(PRICE_SCALE)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(coin_amount as u128)
+        .ok_or(TradiumError::MathOverflow.into())
+}
+
+/// Value of one LP base unit in pc base units, scaled by `PRICE_SCALE`. The
+/// coin side is valued at the curve's spot price.
+pub fn lp_price_in_pc(coin_reserve: u64, pc_reserve: u64, lp_supply: u64) -> Result<u128> {
+    require!(lp_supply > 0, TradiumError::EmptyPool);
+    let coin_value_scaled = (coin_reserve as u128)
+        .checked_mul(spot_price(coin_reserve, pc_reserve)?)
+        .ok_or(TradiumError::MathOverflow)?;
+    let pc_value_scaled = (pc_reserve as u128)
+        .checked_mul(PRICE_SCALE)
+        .ok_or(TradiumError::MathOverflow)?;
+    coin_value_scaled
+        .checked_add(pc_value_scaled)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(lp_supply as u128)
+        .ok_or(TradiumError::MathOverflow.into())
+}
+
+/// Reserves and LP supply at one point in time, for `estimate_lp_fees_earned`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PoolSnapshot {
+    pub coin_reserve: u64,
+    pub pc_reserve: u64,
+    pub lp_supply: u64,
+}
+
+/// Estimates the swap fees earned by `lp_amount` LP held from `entry` to
+/// `exit`, as (coin, pc) at the exit reserves. Fees stay in the pool, so they
+/// show up as growth of `sqrt(coin * pc)` per LP; deposits and withdrawals
+/// leave that ratio unchanged. Rounds down.
+pub fn estimate_lp_fees_earned(
+    lp_amount: u64,
+    entry: &PoolSnapshot,
+    exit: &PoolSnapshot,
+) -> Result<(u64, u64)> {
+    require!(
+        entry.lp_supply > 0 && exit.lp_supply > 0,
+        TradiumError::EmptyPool
+    );
+    let liquidity_entry = liquidity(entry.coin_reserve, entry.pc_reserve);
+    let liquidity_exit = liquidity(exit.coin_reserve, exit.pc_reserve);
+    require!(liquidity_exit > 0, TradiumError::EmptyPool);
+
+    // The position's share of sqrt(k) at each point
+    let share = |liquidity: u128, lp_supply: u64| {
+        (lp_amount as u128)
+            .checked_mul(liquidity)
+            .map(|value| value / lp_supply as u128)
+            .ok_or(TradiumError::MathOverflow)
+    };
+    let earned_liquidity = share(liquidity_exit, exit.lp_supply)?
+        .saturating_sub(share(liquidity_entry, entry.lp_supply)?);
+
+    let to_amount = |reserve: u64| -> Result<u64> {
+        let amount = earned_liquidity
+            .checked_mul(reserve as u128)
+            .ok_or(TradiumError::MathOverflow)?
+            / liquidity_exit;
+        u64::try_from(amount).map_err(|_| TradiumError::MathOverflow.into())
+    };
+    Ok((to_amount(exit.coin_reserve)?, to_amount(exit.pc_reserve)?))
+}
+
+/// `sqrt(coin * pc)`, rounded down.
+fn liquidity(coin_reserve: u64, pc_reserve: u64) -> u128 {
+    integer_sqrt(coin_reserve as u128 * pc_reserve as u128)
+}
+
+/// LP minted for the first deposit into an empty pool: the geometric mean of
+/// both amounts after normalizing them to the pool's system decimals.
+pub fn initial_lp(
+    coin: u64,
+    pc: u64,
+    coin_decimals: u64,
+    pc_decimals: u64,
+    sys_decimal_value: u64,
+) -> Result<u64> {
+    let sys_decimals = sys_decimals_from_value(sys_decimal_value)?;
+    let coin_normalized = normalize_amount(coin, coin_decimals, sys_decimals)?;
+    let pc_normalized = normalize_amount(pc, pc_decimals, sys_decimals)?;
+
+    // Calculate geometric mean: sqrt(coin_normalized * pc_normalized), in u128
+    // so large first deposits don't overflow; only the result must fit a u64
+    let lp = integer_sqrt(
+        coin_normalized
+            .checked_mul(pc_normalized)
+            .ok_or(TradiumError::MathOverflow)?,
+    );
+    u64::try_from(lp).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// LP minted for a deposit into a pool with LP outstanding: the smaller of the
+/// two sides' proportional shares, `amount * lp_supply / reserve`, so a deposit
+/// off the pool ratio never dilutes existing LPs. A side with no amount or no
+/// reserve contributes a zero share.
+pub fn get_lp_for_deposit(
+    amount_coin: u64,
+    amount_pc: u64,
+    coin_reserve: u64,
+    pc_reserve: u64,
+    lp_supply: u64,
+) -> Result<u64> {
+    let share = |amount: u64, reserve: u64| -> Result<u128> {
+        if amount == 0 || reserve == 0 {
+            return Ok(0);
+        }
+        (amount as u128)
+            .checked_mul(lp_supply as u128)
+            .ok_or(TradiumError::MathOverflow)?
+            .checked_div(reserve as u128)
+            .ok_or(TradiumError::MathOverflow.into())
+    };
+    let lp = share(amount_coin, coin_reserve)?.min(share(amount_pc, pc_reserve)?);
+    u64::try_from(lp).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Share of the pool a deposit leaves its depositor holding, in basis points
+/// of `lp_supply_after`, rounded down. A deposit that makes up the whole
+/// supply, like the first into an empty pool, owns all of it; an empty supply
+/// is owned by no one.
+pub fn deposit_share_bps(lp_minted: u64, lp_supply_after: u64) -> u64 {
+    if lp_supply_after == 0 {
+        return 0;
+    }
+    let share = (lp_minted as u128) * (FEE_DENOMINATOR as u128) / (lp_supply_after as u128);
+    share.min(FEE_DENOMINATOR as u128) as u64
+}
+
+/// Scales `amount` from `token_decimals` to `sys_decimals`, rejecting nonzero
+/// amounts that truncate to zero.
+pub fn normalize_amount(amount: u64, token_decimals: u64, sys_decimals: u64) -> Result<u128> {
+    let pow10 = |exp: u64| {
+        u32::try_from(exp)
+            .ok()
+            .and_then(|exp| 10_u128.checked_pow(exp))
+            .ok_or(TradiumError::MathOverflow)
+    };
+    if sys_decimals >= token_decimals {
+        (amount as u128)
+            .checked_mul(pow10(sys_decimals - token_decimals)?)
+            .ok_or(TradiumError::MathOverflow.into())
+    } else {
+        let divisor = pow10(token_decimals - sys_decimals)?;
+        let normalized = (amount as u128)
+            .checked_div(divisor)
+            .ok_or(TradiumError::MathOverflow)?;
+
+        // A nonzero deposit that truncates to zero would mint zero LP; say why instead
+        if amount > 0 && normalized == 0 {
+            msg!(
+                "Deposit of {} base units truncates to zero at {} system decimals, minimum is {}",
+                amount,
+                sys_decimals,
+                divisor
+            );
+            return err!(TradiumError::DepositTooSmall);
+        }
+
+        Ok(normalized)
+    }
+}
+
+/// Floor of the square root of `n` (Newton's method).
+pub fn integer_sqrt(n: u128) -> u128 {
+    if n == 0 {
+        return 0;
+    }
+
+    // Start from ceil(n / 2), written so it cannot overflow at u128::MAX
+    let mut x = n;
+    let mut y = n / 2 + n % 2;
+
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+
+    x
+}
+
+/// Base units to a UI amount, for display only. Works for any `u8` decimals,
+/// the full Token-2022 range.
+pub fn ui_amount(amount: u64, decimals: u8) -> f64 {
+    amount as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Inverse of `ui_amount`, rounded to the nearest base unit and saturating at
+/// `0` and `u64::MAX`.
+pub fn ui_amount_to_amount(ui_amount: f64, decimals: u8) -> u64 {
+    (ui_amount * 10f64.powi(decimals as i32)).round() as u64
+}
+
+/// Applies a sequence of `(amount_in, swap_direction)` swaps to the given
+/// reserves and returns the final `(coin, pc)` reserves. Direction 0 is
+/// coin to pc, 1 is pc to coin. The full input, fee included, stays in the pool.
+pub fn simulate_swaps(
+    initial_coin: u64,
+    initial_pc: u64,
+    swaps: &[(u64, u8)],
+    fees: &Fees,
+) -> Result<(u64, u64)> {
+    let (fee_numerator, fee_denominator) = fees.total_swap_fee()?;
+    let mut coin_reserve = initial_coin;
+    let mut pc_reserve = initial_pc;
+
+    for &(amount_in, swap_direction) in swaps {
+        require!(swap_direction <= 1, TradiumError::InvalidSwapDirection);
+        let (reserve_in, reserve_out) = if swap_direction == 0 {
+            (&mut coin_reserve, &mut pc_reserve)
+        } else {
+            (&mut pc_reserve, &mut coin_reserve)
+        };
+
+        let amount_out = get_amount_out(
+            amount_in,
+            *reserve_in,
+            *reserve_out,
+            fee_numerator,
+            fee_denominator,
+        )?;
+
+        *reserve_in = reserve_in
+            .checked_add(amount_in)
+            .ok_or(TradiumError::MathOverflow)?;
+        *reserve_out = reserve_out
+            .checked_sub(amount_out)
+            .ok_or(TradiumError::MathOverflow)?;
+    }
+
+    Ok((coin_reserve, pc_reserve))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_fees() -> Fees {
+        Fees {
+            swap_fee_numerator: 5,
+            swap_fee_denominator: 10_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn scaled_execution_price_corrects_for_mismatched_decimals() {
+        // 2 coin (9 decimals) for 300 pc (6 decimals) is 150 pc per coin
+        assert_eq!(
+            scaled_execution_price(2_000_000_000, 300_000_000, 0, 9, 6).unwrap(),
+            150 * PRICE_SCALE
+        );
+        // The same trade the other way round prices the same
+        assert_eq!(
+            scaled_execution_price(300_000_000, 2_000_000_000, 1, 9, 6).unwrap(),
+            150 * PRICE_SCALE
+        );
+        // 1 coin (6 decimals) for 2.5 pc (9 decimals)
+        assert_eq!(
+            scaled_execution_price(1_000_000, 2_500_000_000, 0, 6, 9).unwrap(),
+            5 * PRICE_SCALE / 2
+        );
+
+        // Prices below one whole pc per coin keep their precision
+        let price = scaled_execution_price(10_000, 9_896, 0, 6, 6).unwrap();
+        assert_eq!(price, 989_600_000_000);
+        assert!(price < PRICE_SCALE);
+
+        // Matches amount_out / amount_in to within a unit of rounding
+        let price = scaled_execution_price(3, 10, 0, 6, 6).unwrap();
+        assert!(price * 3 <= 10 * PRICE_SCALE && 10 * PRICE_SCALE < (price + 1) * 3);
+
+        assert_eq!(
+            scaled_execution_price(0, 10, 0, 6, 6).unwrap_err(),
+            TradiumError::InvalidInputAmount.into()
+        );
+    }
+
+    #[test]
+    fn min_received_allows_the_given_slippage_below_the_quote() {
+        let fees = default_fees();
+        // 10_000 into a 1_000_000 / 1_000_000 pool quotes 9_896
+        assert_eq!(
+            min_received(
+                SwapCurve::ConstantProduct,
+                10_000,
+                1_000_000,
+                1_000_000,
+                &fees,
+                0
+            )
+            .unwrap(),
+            9_896
+        );
+        assert_eq!(
+            min_received(
+                SwapCurve::ConstantProduct,
+                10_000,
+                1_000_000,
+                1_000_000,
+                &fees,
+                50
+            )
+            .unwrap(),
+            9_846
+        );
+        assert_eq!(
+            min_received(
+                SwapCurve::ConstantProduct,
+                10_000,
+                1_000_000,
+                1_000_000,
+                &fees,
+                10_000
+            )
+            .unwrap(),
+            0
+        );
+        assert_eq!(
+            min_received(
+                SwapCurve::ConstantProduct,
+                10_000,
+                1_000_000,
+                1_000_000,
+                &fees,
+                10_001
+            )
+            .unwrap_err(),
+            TradiumError::InvalidSlippageBps.into()
+        );
+    }
+
+    #[test]
+    fn swap_into_huge_vaults_does_not_overflow() {
+        let reserve = u64::MAX / 2;
+        let amount_in: u64 = 1_000_000_000_000;
+        // The u64 product alone would overflow
+        assert!(amount_in.checked_mul(reserve).is_none());
+
+        let amount_out = get_amount_out(amount_in, reserve, reserve, 5, 10_000).unwrap();
+        assert!(amount_out > 0 && amount_out < amount_in);
+
+        // k never decreases
+        let k_before = reserve as u128 * reserve as u128;
+        let k_after = (reserve as u128 + amount_in as u128) * (reserve - amount_out) as u128;
+        assert!(k_after >= k_before);
+    }
+
+    #[test]
+    fn get_amount_out_applies_fee_before_curve() {
+        // 10_000 * 0.9995 = 9_995 net; 9_995 * 1m / 1_009_995 = 9_896.08
+        assert_eq!(
+            get_amount_out(10_000, 1_000_000, 1_000_000, 5, 10_000).unwrap(),
+            9_896
+        );
+    }
+
+    #[test]
+    fn spot_price_is_pc_per_coin_scaled() {
+        assert_eq!(spot_price(1_000, 2_000).unwrap(), 2 * PRICE_SCALE);
+        assert_eq!(spot_price(4, 1).unwrap(), PRICE_SCALE / 4);
+        assert!(spot_price(0, 1_000).is_err());
+    }
+
+    #[test]
+    fn pair_amount_across_ratios() {
+        assert_eq!(pair_amount(1_000, 1_000_000, 1_000_000).unwrap(), 1_000);
+        assert_eq!(pair_amount(1_000, 1_000_000, 4_000_000).unwrap(), 4_000);
+        assert_eq!(pair_amount(4_000, 4_000_000, 1_000_000).unwrap(), 1_000);
+        // 9 vs 6 decimals at 1:1 in whole tokens
+        assert_eq!(
+            pair_amount(1_000_000_000, 5_000_000_000_000, 5_000_000_000).unwrap(),
+            1_000_000
+        );
+        // Inexact ratios round up
+        assert_eq!(pair_amount(1, 3, 2).unwrap(), 1);
+        assert_eq!(pair_amount(10, 3, 2).unwrap(), 7);
+        assert_eq!(pair_amount(0, 3, 2).unwrap(), 0);
+    }
+
+    #[test]
+    fn pair_amount_rejects_empty_reserves() {
+        assert_eq!(
+            pair_amount(1_000, 0, 1_000).unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+        assert_eq!(
+            pair_amount(1_000, 1_000, 0).unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+        assert_eq!(
+            pair_amount(u64::MAX, 1, u64::MAX).unwrap_err(),
+            TradiumError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn execution_price_is_pc_per_coin_in_both_directions() {
+        assert_eq!(execution_price(10_000, 9_896, 0).unwrap(), 989_600_000_000);
+        assert_eq!(execution_price(10_000, 5_000, 1).unwrap(), 2 * PRICE_SCALE);
+        assert!(execution_price(10_000, 0, 1).is_err());
+    }
+
+    #[test]
+    fn lp_price_matches_manual_valuation() {
+        // 1 coin : 4 pc, 2 LP outstanding: pool worth 4 + 4 = 8 pc, so 4 pc per LP
+        assert_eq!(
+            lp_price_in_pc(1_000_000, 4_000_000, 2_000_000).unwrap(),
+            4 * PRICE_SCALE
+        );
+        // 3 coin at 2/3 pc each plus 2 pc over 3 LP => 4/3 pc per LP, rounded down
+        assert_eq!(lp_price_in_pc(3, 2, 3).unwrap(), 1_333_333_333_332);
+    }
+
+    #[test]
+    fn lp_price_requires_supply_and_reserves() {
+        assert_eq!(
+            lp_price_in_pc(1_000, 1_000, 0).unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+        assert_eq!(
+            lp_price_in_pc(0, 1_000, 1_000).unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+    }
+
+    #[test]
+    fn get_amount_in_round_trips_through_get_amount_out() {
+        for (amount_out, reserve_in, reserve_out) in [
+            (9_896, 1_000_000, 1_000_000),
+            (1, 1_000_000, 3_000_000),
+            (500_000, 2_000_000, 1_000_000),
+            (123_456, 7_000_000_000, 900_000),
+        ] {
+            let amount_in = get_amount_in(amount_out, reserve_in, reserve_out, 30, 10_000).unwrap();
+            assert!(
+                get_amount_out(amount_in, reserve_in, reserve_out, 30, 10_000).unwrap()
+                    >= amount_out
+            );
+            assert!(
+                get_amount_out(amount_in - 1, reserve_in, reserve_out, 30, 10_000).unwrap()
+                    < amount_out
+            );
+        }
+    }
+
+    #[test]
+    fn get_amount_in_cannot_drain_the_output_reserve() {
+        assert_eq!(
+            get_amount_in(1_000_000, 1_000_000, 1_000_000, 30, 10_000).unwrap_err(),
+            TradiumError::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn lp_fees_follow_growth_of_k_per_lp() {
+        let entry = PoolSnapshot {
+            coin_reserve: 1_000_000,
+            pc_reserve: 1_000_000,
+            lp_supply: 1_000_000,
+        };
+        // Fees grew sqrt(k) per LP by 1%
+        let exit = PoolSnapshot {
+            coin_reserve: 1_010_000,
+            pc_reserve: 1_010_000,
+            lp_supply: 1_000_000,
+        };
+        // A 10% position earned 10% of the 10_100 of each token added by fees
+        assert_eq!(
+            estimate_lp_fees_earned(100_000, &entry, &exit).unwrap(),
+            (1_000, 1_000)
+        );
+
+        // A deposit doubling the pool in between does not count as fees
+        let exit_after_deposit = PoolSnapshot {
+            coin_reserve: 2_020_000,
+            pc_reserve: 2_020_000,
+            lp_supply: 2_000_000,
+        };
+        assert_eq!(
+            estimate_lp_fees_earned(100_000, &entry, &exit_after_deposit).unwrap(),
+            (1_000, 1_000)
+        );
+
+        // Without fees nothing was earned
+        assert_eq!(
+            estimate_lp_fees_earned(100_000, &entry, &entry).unwrap(),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn lp_fees_match_a_single_swap_fee() {
+        let entry = PoolSnapshot {
+            coin_reserve: 1_000_000,
+            pc_reserve: 1_000_000,
+            lp_supply: 1_000_000,
+        };
+        let out = get_amount_out(100_000, 1_000_000, 1_000_000, 30, 10_000).unwrap();
+        let exit = PoolSnapshot {
+            coin_reserve: 1_100_000,
+            pc_reserve: 1_000_000 - out,
+            lp_supply: 1_000_000,
+        };
+        // The whole supply earns the 300 coin fee, worth about 150 coin and
+        // 123 pc at the exit reserves
+        assert_eq!(
+            estimate_lp_fees_earned(1_000_000, &entry, &exit).unwrap(),
+            (149, 123)
+        );
+    }
+
+    #[test]
+    fn tiny_high_decimal_amount_is_rejected_as_too_small() {
+        // 1 base unit of a 9-decimal token normalized to 6 system decimals
+        assert_eq!(
+            normalize_amount(999, 9, 6).unwrap_err(),
+            TradiumError::DepositTooSmall.into()
+        );
+        assert_eq!(normalize_amount(1_000, 9, 6).unwrap(), 1);
+        assert_eq!(normalize_amount(0, 9, 6).unwrap(), 0);
+    }
+
+    #[test]
+    fn initial_lp_is_geometric_mean_across_decimals() {
+        // 1 coin : 4 pc, in each decimal combination => 2 LP at system decimals
+        assert_eq!(
+            initial_lp(1_000_000, 4_000_000, 6, 6, 1_000_000).unwrap(),
+            2_000_000
+        );
+        assert_eq!(
+            initial_lp(1_000_000_000, 4_000_000, 9, 6, 1_000_000).unwrap(),
+            2_000_000
+        );
+        assert_eq!(
+            initial_lp(1_000_000, 4_000_000_000, 6, 9, 1_000_000_000).unwrap(),
+            2_000_000_000
+        );
+        // Non-square products round down
+        assert_eq!(initial_lp(2, 3, 0, 0, 1).unwrap(), 2);
+    }
+
+    #[test]
+    fn large_first_deposit_does_not_overflow() {
+        // 5 million units each of two 9-decimal tokens at 9 system decimals:
+        // the product of the normalized amounts no longer fits a u64
+        let amount: u64 = 5_000_000 * 1_000_000_000;
+        assert!(amount.checked_mul(amount).is_none());
+        assert_eq!(
+            initial_lp(amount, amount, 9, 9, 1_000_000_000).unwrap(),
+            amount
+        );
+        assert_eq!(initial_lp(u64::MAX, u64::MAX, 0, 0, 1).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn integer_sqrt_rounds_down() {
+        assert_eq!(integer_sqrt(0), 0);
+        assert_eq!(integer_sqrt(1), 1);
+        assert_eq!(integer_sqrt(2), 1);
+        assert_eq!(integer_sqrt(3), 1);
+        assert_eq!(integer_sqrt(4), 2);
+        assert_eq!(integer_sqrt(u128::MAX), u64::MAX as u128);
+    }
+
+    #[test]
+    fn initial_lp_rejects_invalid_sys_decimal_value() {
+        assert_eq!(
+            initial_lp(1_000, 1_000, 6, 6, 6).unwrap_err(),
+            TradiumError::InvalidSysDecimalValue.into()
+        );
+    }
+
+    #[test]
+    fn ui_amount_round_trips() {
+        assert_eq!(ui_amount(1_500_000, 6), 1.5);
+        assert_eq!(ui_amount(1, 9), 0.000_000_001);
+        assert_eq!(ui_amount(42, 0), 42.0);
+
+        for (amount, decimals) in [(1_500_000, 6), (1, 9), (123_456_789, 8), (42, 0)] {
+            assert_eq!(
+                ui_amount_to_amount(ui_amount(amount, decimals), decimals),
+                amount
+            );
+        }
+
+        // Extreme Token-2022 decimals stay finite; out of range values saturate
+        assert!(ui_amount(u64::MAX, u8::MAX) > 0.0);
+        assert_eq!(ui_amount_to_amount(1.0, u8::MAX), u64::MAX);
+        assert_eq!(ui_amount_to_amount(-1.0, 6), 0);
+    }
+
+    #[test]
+    fn simulate_multi_swap_sequence() {
+        let swaps = [(10_000, 0), (5_000, 1), (20_000, 0)];
+        // coin->pc: out 9_896   => (1_010_000, 990_104)
+        // pc->coin: out 5_071   => (1_004_929, 995_104)
+        // coin->pc: out 19_408  => (1_024_929, 975_696)
+        let (coin, pc) = simulate_swaps(1_000_000, 1_000_000, &swaps, &default_fees()).unwrap();
+        assert_eq!((coin, pc), (1_024_929, 975_696));
+    }
+
+    #[test]
+    fn simulate_empty_sequence_keeps_reserves() {
+        let (coin, pc) = simulate_swaps(42, 7, &[], &default_fees()).unwrap();
+        assert_eq!((coin, pc), (42, 7));
+    }
+
+    #[test]
+    fn simulate_rejects_invalid_direction() {
+        assert!(simulate_swaps(1_000, 1_000, &[(10, 2)], &default_fees()).is_err());
+    }
+
+    #[test]
+    fn deposit_lp_is_the_smaller_proportional_share() {
+        // On the 1:4 ratio both sides mint the same share
+        assert_eq!(
+            get_lp_for_deposit(1_000, 4_000, 1_000_000, 4_000_000, 2_000_000).unwrap(),
+            2_000
+        );
+        // Excess pc buys nothing extra
+        assert_eq!(
+            get_lp_for_deposit(1_000, 8_000, 1_000_000, 4_000_000, 2_000_000).unwrap(),
+            2_000
+        );
+        // A missing side or reserve contributes nothing
+        assert_eq!(
+            get_lp_for_deposit(1_000, 0, 1_000_000, 4_000_000, 2_000_000).unwrap(),
+            0
+        );
+        assert_eq!(
+            get_lp_for_deposit(1_000, 4_000, 0, 4_000_000, 2_000_000).unwrap(),
+            0
+        );
+        // Amounts whose product with the supply overflows u64
+        assert_eq!(
+            get_lp_for_deposit(u64::MAX / 2, u64::MAX / 2, u64::MAX, u64::MAX, u64::MAX / 4)
+                .unwrap(),
+            u64::MAX / 8
+        );
+    }
+
+    #[test]
+    fn stable_curve_is_flat_near_the_balanced_point() {
+        let stable = SwapCurve::Stable { amp: 100 };
+
+        // Balanced: almost one for one, far better than constant product
+        let near = stable
+            .amount_out(10_000, 1_000_000, 1_000_000, 25, 10_000)
+            .unwrap();
+        assert_eq!(near, 9_974);
+        assert!(near > get_amount_out(10_000, 1_000_000, 1_000_000, 25, 10_000).unwrap());
+        // Without the fee the rate is one for one, less the rounding unit
+        assert_eq!(
+            stable_amount_out(100, 10_000, 1_000_000, 1_000_000, 0, 10_000).unwrap(),
+            9_999
+        );
+
+        // Far from balance the rate falls off, as the curve turns constant-product
+        let far = stable
+            .amount_out(10_000, 1_900_000, 100_000, 25, 10_000)
+            .unwrap();
+        assert_eq!(far, 7_923);
+        // And buying the scarce side back is rewarded
+        assert_eq!(
+            stable
+                .amount_out(10_000, 100_000, 1_900_000, 25, 10_000)
+                .unwrap(),
+            12_139
+        );
+
+        // A lower amplification bends the curve sooner
+        assert_eq!(
+            stable_amount_out(1, 10_000, 1_000_000, 1_000_000, 25, 10_000).unwrap(),
+            9_941
+        );
+    }
+
+    #[test]
+    fn stable_newton_solvers_converge_and_keep_the_invariant() {
+        assert_eq!(
+            stable_invariant(100, 1_000_000, 1_000_000).unwrap(),
+            2_000_000
+        );
+        let d = stable_invariant(100, 1_900_000, 100_000).unwrap();
+        assert_eq!(d, 1_979_436);
+        // Solving back for the other reserve lands within the convergence unit
+        let y = stable_other_reserve(100, 1_900_000, d).unwrap();
+        assert!(y.abs_diff(100_000) <= 1);
+
+        // A swap never lowers the invariant
+        let amount_out = stable_amount_out(100, 50_000, 1_000_000, 1_000_000, 25, 10_000).unwrap();
+        assert!(
+            stable_invariant(100, 1_050_000, 1_000_000 - amount_out).unwrap()
+                >= stable_invariant(100, 1_000_000, 1_000_000).unwrap()
+        );
+
+        assert_eq!(
+            stable_invariant(100, 0, 1_000_000).unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+    }
+
+    #[test]
+    fn stable_amount_in_round_trips_through_amount_out() {
+        let stable = SwapCurve::Stable { amp: 100 };
+        for amount_out in [10, 1_000, 9_990, 500_000] {
+            let amount_in = stable
+                .amount_in(amount_out, 1_000_000, 1_000_000, 25, 10_000)
+                .unwrap();
+            assert!(
+                stable
+                    .amount_out(amount_in, 1_000_000, 1_000_000, 25, 10_000)
+                    .unwrap()
+                    >= amount_out
+            );
+            assert!(
+                stable
+                    .amount_out(amount_in - 1, 1_000_000, 1_000_000, 25, 10_000)
+                    .unwrap()
+                    < amount_out
+            );
+        }
+    }
+
+    #[test]
+    fn curve_config_is_validated() {
+        assert_eq!(
+            SwapCurve::from_config(CURVE_TYPE_CONSTANT_PRODUCT, 0).unwrap(),
+            SwapCurve::ConstantProduct
+        );
+        assert_eq!(
+            SwapCurve::from_config(CURVE_TYPE_STABLE, 100).unwrap(),
+            SwapCurve::Stable { amp: 100 }
+        );
+        for (curve_type, amp) in [
+            (CURVE_TYPE_STABLE, 0),
+            (CURVE_TYPE_STABLE, MAX_AMP + 1),
+            (2, 100),
+        ] {
+            assert_eq!(
+                SwapCurve::from_config(curve_type, amp).unwrap_err(),
+                TradiumError::InvalidCurveConfig.into()
+            );
+        }
+    }
+
+    #[test]
+    fn deposit_share_bps_covers_first_and_later_deposits() {
+        // The first deposit owns the whole supply it mints
+        assert_eq!(deposit_share_bps(1_000_000, 1_000_000), 10_000);
+
+        // A later deposit owns its slice of the grown supply
+        let lp_minted = get_lp_for_deposit(100, 100, 300, 300, 3_000).unwrap();
+        assert_eq!(lp_minted, 1_000);
+        assert_eq!(deposit_share_bps(lp_minted, 3_000 + lp_minted), 2_500);
+
+        // Rounded down, and nothing of an empty supply
+        assert_eq!(deposit_share_bps(1, 3), 3_333);
+        assert_eq!(deposit_share_bps(0, 0), 0);
+    }
+
+    #[test]
+    fn constant_product_output_never_shrinks_the_invariant() {
+        let amounts = [
+            0,
+            1,
+            2,
+            3,
+            7,
+            999,
+            1_000,
+            1_001,
+            123_457,
+            1_000_000,
+            987_654_321,
+            u32::MAX as u64,
+            u64::MAX / 3,
+            u64::MAX,
+        ];
+        for &reserve_in in &amounts[1..] {
+            for &reserve_out in &amounts[1..] {
+                for &amount_in_net in &amounts {
+                    let amount_out =
+                        constant_product_amount_out(amount_in_net, reserve_in, reserve_out)
+                            .unwrap();
+                    assert!(amount_out <= reserve_out);
+                    let k_before = reserve_in as u128 * reserve_out as u128;
+                    // A product after past u128 certainly exceeds k, which fits
+                    if let Some(k_after) = (reserve_in as u128 + amount_in_net as u128)
+                        .checked_mul((reserve_out - amount_out) as u128)
+                    {
+                        assert!(
+                            k_after >= k_before,
+                            "k shrank: in {amount_in_net}, reserves {reserve_in}/{reserve_out}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
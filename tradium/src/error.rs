@@ -56,4 +56,88 @@ pub enum TradiumError {
     InvalidInputAmount,
     #[msg("Slippage Exceeded")]
     SlippageExceeded,
+    #[msg("Input Mint Collides With LP Mint")]
+    MintCollidesWithLpMint,
+    #[msg("Invalid Fee")]
+    InvalidFee,
+    #[msg("Deposit Too Small: amount truncates to zero after decimal normalization")]
+    DepositTooSmall,
+    #[msg("Invalid Sys Decimal Value: must be a power of ten")]
+    InvalidSysDecimalValue,
+    #[msg("Pool Has Outstanding LP Supply")]
+    PoolHasLpSupply,
+    #[msg("Missing Associated Token Program or System Program")]
+    MissingAssociatedTokenAccounts,
+    #[msg("Invalid Output Token Account")]
+    InvalidOutputTokenAccount,
+    #[msg("Reserve Floor Breached")]
+    ReserveFloorBreached,
+    #[msg("Vault Has CpiGuard Enabled")]
+    VaultCpiGuardEnabled,
+    #[msg("Vault Init Mismatch: vault is not the pool's token account for its mint")]
+    VaultInitMismatch,
+    #[msg("Swap Throttled: large swap within the slot delay of the previous one")]
+    SwapThrottled,
+    #[msg("First Deposit Requires Both Sides")]
+    FirstDepositRequiresBothSides,
+    #[msg("Invalid Flash Loan Vault")]
+    InvalidFlashLoanVault,
+    #[msg("Flash Loan Not Repaid")]
+    FlashLoanNotRepaid,
+    #[msg("Flash Loan Already In Progress")]
+    FlashLoanReentrancy,
+    #[msg("LP Account Not Owned By User Authority")]
+    InvalidLpAccountOwner,
+    #[msg("Invalid Slippage Bps: must be at most 10000")]
+    InvalidSlippageBps,
+    #[msg("Vault Owner Is Not The Pool")]
+    VaultOwnerMismatch,
+    #[msg("Invalid Price Oracle")]
+    InvalidPriceOracle,
+    #[msg("Execution Price Deviates Too Far From Oracle")]
+    OracleDeviationExceeded,
+    #[msg("Swap Output Would Be Zero")]
+    ZeroSwapOutput,
+    #[msg("Swap Output Exceeds Per-Swap Cap")]
+    OutputCapExceeded,
+    #[msg("Transfer Hook Program Is Not Executable")]
+    TransferHookProgramNotExecutable,
+    #[msg("Transfer Hook Whitelist Is Full")]
+    HookWhitelistFull,
+    #[msg("Transfer Hook Already Whitelisted")]
+    HookAlreadyWhitelisted,
+    #[msg("Transfer Hook Not Whitelisted")]
+    HookNotWhitelisted,
+    #[msg("Transfer Hook Is Used By A Pool Mint")]
+    HookInUse,
+    #[msg("Required Input Exceeds Max Amount In")]
+    ExcessiveInputAmount,
+    #[msg("Invalid Swap Route")]
+    InvalidRoute,
+    #[msg("Routes Through Pools With Transfer Hook Mints Are Not Supported")]
+    RouteHookMintUnsupported,
+    #[msg("Deposit Exceeds Per-Transaction Limit")]
+    DepositLimitExceeded,
+    #[msg("Invalid Fee Configuration")]
+    InvalidFeeConfig,
+    #[msg("Owner And Fee Recipient Must Not Be The Default Key")]
+    InvalidGovernanceKey,
+    #[msg("Reserve Desynced: LP is outstanding against an empty reserve")]
+    ReserveDesynced,
+    #[msg("Bump Mismatch: the stored bump does not derive the pool address")]
+    BumpMismatch,
+    #[msg("Duplicate Mint: both sides of the pair are the same mint")]
+    DuplicateMint,
+    #[msg("Invalid Curve Configuration")]
+    InvalidCurveConfig,
+    #[msg("Invalid Liquidity Lock: zero, or too large for the first deposit")]
+    InvalidLiquidityLock,
+    #[msg("Deadline Exceeded: the swap landed after its deadline")]
+    DeadlineExceeded,
+    #[msg("Input Token Account Must Be Owned By The Signer, Not Spent Through A Delegate")]
+    InvalidInputTokenAccount,
+    #[msg("Price Oracle Required: the pool checks swaps against its oracle")]
+    PriceOracleRequired,
+    #[msg("Stale Price Oracle: the feed was published too many slots ago")]
+    StalePriceOracle,
 }
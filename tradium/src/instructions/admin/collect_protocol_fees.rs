@@ -0,0 +1,122 @@
+use crate::error::TradiumError;
+use crate::shared;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct CollectProtocolFees<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    /// Pool's coin vault
+    #[account(mut, address = pool.coin_vault @ TradiumError::InvalidCoinVault)]
+    pub coin_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool's PC vault
+    #[account(mut, address = pool.pc_vault @ TradiumError::InvalidPcVault)]
+    pub pc_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = pool.coin_vault_mint)]
+    pub coin_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(address = pool.pc_vault_mint)]
+    pub pc_mint: InterfaceAccount<'info, Mint>,
+
+    /// Fee recipient's coin token account receiving the coin fees
+    #[account(
+        mut,
+        token::mint = coin_mint,
+        token::authority = pool.protocol_fee_recipient()
+    )]
+    pub fee_recipient_coin_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Fee recipient's PC token account receiving the PC fees
+    #[account(
+        mut,
+        token::mint = pc_mint,
+        token::authority = pool.protocol_fee_recipient()
+    )]
+    pub fee_recipient_pc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = pool.coin_token_program @ TradiumError::InvalidTokenProgram)]
+    pub coin_token_program: Interface<'info, TokenInterface>,
+
+    #[account(address = pool.pc_token_program @ TradiumError::InvalidTokenProgram)]
+    pub pc_token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Optional, only required if coin_mint has a transfer hook; validated in the handler
+    pub coin_transfer_hook_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Optional, only required if pc_mint has a transfer hook; validated in the handler
+    pub pc_transfer_hook_program: Option<UncheckedAccount<'info>>,
+}
+
+/// Pays the owed owner fees out of the vaults to the fee recipient's token
+/// accounts and zeroes them. The active reserves are unaffected.
+pub fn handle_collect_protocol_fees<'info>(
+    ctx: Context<'_, '_, 'info, 'info, CollectProtocolFees<'info>>,
+) -> Result<()> {
+    let coin_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.coin_transfer_hook_program.as_ref(),
+    )?;
+    let pc_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.pc_mint.to_account_info(),
+        ctx.accounts.pc_transfer_hook_program.as_ref(),
+    )?;
+
+    let (coin_fees, pc_fees) = ctx.accounts.pool.take_owed_fees();
+
+    let pool_account_info = ctx.accounts.pool.to_account_info();
+    let pool_seeds = ctx.accounts.pool.signer_seeds();
+    let signer_seeds = &[&pool_seeds[..]];
+
+    if coin_fees > 0 {
+        shared::transfer_tokens_with_hook_support(
+            &ctx.accounts.coin_token_program,
+            &ctx.accounts.coin_vault,
+            &ctx.accounts.fee_recipient_coin_account,
+            &ctx.accounts.coin_mint.to_account_info(),
+            &pool_account_info,
+            coin_hook,
+            ctx.accounts.coin_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
+            coin_fees,
+            Some(signer_seeds),
+        )?;
+    }
+    if pc_fees > 0 {
+        shared::transfer_tokens_with_hook_support(
+            &ctx.accounts.pc_token_program,
+            &ctx.accounts.pc_vault,
+            &ctx.accounts.fee_recipient_pc_account,
+            &ctx.accounts.pc_mint.to_account_info(),
+            &pool_account_info,
+            pc_hook,
+            ctx.accounts.pc_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
+            pc_fees,
+            Some(signer_seeds),
+        )?;
+    }
+
+    ctx.accounts.coin_vault.reload()?;
+    ctx.accounts.pc_vault.reload()?;
+    ctx.accounts
+        .pool
+        .record_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount);
+
+    msg!(
+        "Protocol fees collected: coin {}, pc {}",
+        coin_fees,
+        pc_fees
+    );
+
+    Ok(())
+}
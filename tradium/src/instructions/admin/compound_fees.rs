@@ -0,0 +1,26 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct CompoundFees<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Gives the owed owner fees to the LPs by folding them into the active
+/// reserves. No tokens move and no LP is minted, so value per LP rises.
+pub fn handle_compound_fees(ctx: Context<CompoundFees>) -> Result<()> {
+    let (coin_compounded, pc_compounded) = ctx.accounts.pool.compound_owed_fees();
+
+    msg!(
+        "Fees compounded into reserves: coin {}, pc {}",
+        coin_compounded,
+        pc_compounded
+    );
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct EnableAutoUnpause<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Opts the pool in to pauses lapsing after `MAX_PAUSE_DURATION` unless
+/// renewed. There is no way back, so LPs can rely on it once it is set.
+pub fn handle_enable_auto_unpause(ctx: Context<EnableAutoUnpause>) -> Result<()> {
+    ctx.accounts.pool.auto_unpause = true;
+
+    msg!("Auto-unpause enabled");
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+pub mod set_fees;
+pub use set_fees::*;
+
+pub mod set_sys_decimal_value;
+pub use set_sys_decimal_value::*;
+
+pub mod set_reserve_floors;
+pub use set_reserve_floors::*;
+
+pub mod set_swap_throttle;
+pub use set_swap_throttle::*;
+
+pub mod compound_fees;
+pub use compound_fees::*;
+
+pub mod set_price_oracle;
+pub use set_price_oracle::*;
+
+pub mod set_max_output_per_swap;
+pub use set_max_output_per_swap::*;
+
+pub mod update_whitelisted_hooks;
+pub use update_whitelisted_hooks::*;
+
+pub mod set_max_deposit;
+pub use set_max_deposit::*;
+
+pub mod collect_protocol_fees;
+pub use collect_protocol_fees::*;
+
+pub mod update_fees;
+pub use update_fees::*;
+
+pub mod set_pool_status;
+pub use set_pool_status::*;
+
+pub mod set_auto_compound;
+pub use set_auto_compound::*;
+
+pub mod skim;
+pub use skim::*;
+
+pub mod enable_auto_unpause;
+pub use enable_auto_unpause::*;
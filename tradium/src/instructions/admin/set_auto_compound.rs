@@ -0,0 +1,22 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetAutoCompound<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Opts the pool in or out of compounding the owed owner fees into the
+/// reserves before every swap. Off by default.
+pub fn handle_set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+    ctx.accounts.pool.auto_compound_fees = enabled;
+
+    msg!("Auto-compounding of owed fees set: {}", enabled);
+
+    Ok(())
+}
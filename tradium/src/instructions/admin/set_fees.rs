@@ -0,0 +1,119 @@
+use crate::error::TradiumError;
+use crate::instructions::admin::validate_fee_config;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetFees<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Replaces the swap (owner) fee, keeping the pool's trade fee. The pair is
+/// held to the same limits as `update_fees`.
+pub fn handle_set_fees(
+    ctx: Context<SetFees>,
+    swap_fee_numerator: u64,
+    swap_fee_denominator: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    validate_fee_config(
+        pool.fees.trade_fee_numerator,
+        pool.fees.trade_fee_denominator,
+        swap_fee_numerator,
+        swap_fee_denominator,
+    )?;
+
+    pool.fees.swap_fee_numerator = swap_fee_numerator;
+    pool.fees.swap_fee_denominator = swap_fee_denominator;
+
+    msg!(
+        "Swap fee set to {}/{}",
+        swap_fee_numerator,
+        swap_fee_denominator
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+    use crate::shared::TestAccount;
+    use std::collections::BTreeSet;
+
+    /// Sets the swap fee on a pool charging the default 0.3% trade fee
+    fn set_swap_fee(numerator: u64, denominator: u64) -> Result<Tradium> {
+        let owner = Pubkey::new_unique();
+        let mut pool = Tradium {
+            amm_owner: owner,
+            ..Default::default()
+        };
+        pool.fees.trade_fee_numerator = DEFAULT_TRADE_FEE;
+        pool.fees.trade_fee_denominator = FEE_DENOMINATOR;
+        let mut pool_data = vec![];
+        pool.try_serialize(&mut pool_data).unwrap();
+
+        let mut pool_account = TestAccount::new(Pubkey::new_unique(), crate::ID, pool_data);
+        let mut owner_account = TestAccount::signer(owner);
+        let infos = [pool_account.info(), owner_account.info()];
+        let mut remaining = &infos[..];
+        let mut accounts = SetFees::try_accounts(
+            &crate::ID,
+            &mut remaining,
+            &[],
+            &mut Default::default(),
+            &mut BTreeSet::new(),
+        )
+        .unwrap();
+        handle_set_fees(
+            Context::new(&crate::ID, &mut accounts, &[], Default::default()),
+            numerator,
+            denominator,
+        )?;
+        Ok(accounts.pool.clone().into_inner())
+    }
+
+    #[test]
+    fn total_fee_at_max_is_accepted() {
+        let pool = set_swap_fee(MAX_FEE_BPS - DEFAULT_TRADE_FEE, FEE_DENOMINATOR).unwrap();
+        assert_eq!(
+            pool.fees.total_swap_fee().unwrap(),
+            (MAX_FEE_BPS, FEE_DENOMINATOR)
+        );
+        // Same ratio on a different denominator
+        assert!(set_swap_fee((MAX_FEE_BPS - DEFAULT_TRADE_FEE) * 10, FEE_DENOMINATOR * 10).is_ok());
+    }
+
+    #[test]
+    fn swap_fee_pushing_the_total_over_max_is_rejected() {
+        // Under the cap on its own, over it with the trade fee added
+        for (numerator, denominator) in [
+            (MAX_FEE_BPS - DEFAULT_TRADE_FEE + 1, FEE_DENOMINATOR),
+            (MAX_FEE_BPS, FEE_DENOMINATOR),
+            (
+                (MAX_FEE_BPS - DEFAULT_TRADE_FEE) * 10 + 1,
+                FEE_DENOMINATOR * 10,
+            ),
+        ] {
+            assert_eq!(
+                set_swap_fee(numerator, denominator).unwrap_err(),
+                TradiumError::InvalidFeeConfig.into()
+            );
+        }
+    }
+
+    #[test]
+    fn fee_of_one_hundred_percent_or_zero_denominator_is_rejected() {
+        for (numerator, denominator) in [(FEE_DENOMINATOR, FEE_DENOMINATOR), (0, 0)] {
+            assert_eq!(
+                set_swap_fee(numerator, denominator).unwrap_err(),
+                TradiumError::InvalidFeeConfig.into()
+            );
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetMaxDeposit<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Caps how much of each token a single deposit may add (0 = unlimited).
+pub fn handle_set_max_deposit(
+    ctx: Context<SetMaxDeposit>,
+    max_deposit_coin: u64,
+    max_deposit_pc: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.max_deposit_coin = max_deposit_coin;
+    pool.max_deposit_pc = max_deposit_pc;
+
+    msg!(
+        "Max deposit set: coin {}, pc {}",
+        max_deposit_coin,
+        max_deposit_pc
+    );
+
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetMaxOutputPerSwap<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Caps how much of each token a single swap may pay out (0 = unlimited).
+pub fn handle_set_max_output_per_swap(
+    ctx: Context<SetMaxOutputPerSwap>,
+    max_output_coin: u64,
+    max_output_pc: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.max_output_per_swap_coin = max_output_coin;
+    pool.max_output_per_swap_pc = max_output_pc;
+
+    msg!(
+        "Max output per swap set: coin {}, pc {}",
+        max_output_coin,
+        max_output_pc
+    );
+
+    Ok(())
+}
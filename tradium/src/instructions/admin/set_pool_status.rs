@@ -0,0 +1,69 @@
+use crate::constants::{POOL_STATUS_ACTIVE, POOL_STATUS_PAUSED};
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPoolStatus<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Pauses or resumes the pool. A pause stops swaps, deposits and flash loans
+/// but not withdrawals. On pools with `auto_unpause` it lapses after
+/// `MAX_PAUSE_DURATION` unless renewed.
+pub fn handle_set_pool_status(ctx: Context<SetPoolStatus>, status: u64) -> Result<()> {
+    apply_pool_status(&mut ctx.accounts.pool, status, Clock::get()?.unix_timestamp)?;
+
+    msg!("Pool status set to {}", status);
+
+    Ok(())
+}
+
+fn apply_pool_status(pool: &mut Tradium, status: u64, now: i64) -> Result<()> {
+    match status {
+        POOL_STATUS_ACTIVE => {
+            pool.status = POOL_STATUS_ACTIVE;
+            pool.pause_expiry = 0;
+            Ok(())
+        }
+        POOL_STATUS_PAUSED => pool.pause(now),
+        _ => err!(TradiumError::InvalidPoolState),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_and_resume() {
+        let mut pool = Tradium {
+            status: POOL_STATUS_ACTIVE,
+            ..Default::default()
+        };
+
+        apply_pool_status(&mut pool, POOL_STATUS_PAUSED, 100).unwrap();
+        assert!(!pool.is_active(100));
+
+        apply_pool_status(&mut pool, POOL_STATUS_ACTIVE, 200).unwrap();
+        assert!(pool.is_active(200));
+        assert_eq!(pool.pause_expiry, 0);
+    }
+
+    #[test]
+    fn unknown_status_is_rejected() {
+        let mut pool = Tradium::default();
+        assert_eq!(
+            apply_pool_status(&mut pool, 0, 100).unwrap_err(),
+            TradiumError::InvalidPoolState.into()
+        );
+        assert_eq!(
+            apply_pool_status(&mut pool, 3, 100).unwrap_err(),
+            TradiumError::InvalidPoolState.into()
+        );
+    }
+}
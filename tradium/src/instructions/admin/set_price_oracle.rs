@@ -0,0 +1,38 @@
+use crate::constants::FEE_DENOMINATOR;
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetPriceOracle<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Configures the reference price feed swaps are checked against
+/// (a zero deviation disables the check).
+pub fn handle_set_price_oracle(
+    ctx: Context<SetPriceOracle>,
+    price_oracle: Pubkey,
+    max_oracle_deviation_bps: u64,
+) -> Result<()> {
+    require!(
+        max_oracle_deviation_bps <= FEE_DENOMINATOR,
+        TradiumError::InvalidSlippageBps
+    );
+
+    let pool = &mut ctx.accounts.pool;
+    pool.price_oracle = price_oracle;
+    pool.max_oracle_deviation_bps = max_oracle_deviation_bps;
+
+    msg!(
+        "Price oracle set: {}, max deviation {} bps",
+        price_oracle,
+        max_oracle_deviation_bps
+    );
+
+    Ok(())
+}
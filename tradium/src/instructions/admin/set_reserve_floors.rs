@@ -0,0 +1,31 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetReserveFloors<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Sets the minimum vault balances withdrawals must leave behind (0 = no floor).
+pub fn handle_set_reserve_floors(
+    ctx: Context<SetReserveFloors>,
+    min_coin_reserve: u64,
+    min_pc_reserve: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.min_coin_reserve = min_coin_reserve;
+    pool.min_pc_reserve = min_pc_reserve;
+
+    msg!(
+        "Reserve floors set: coin {}, pc {}",
+        min_coin_reserve,
+        min_pc_reserve
+    );
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct SetSwapThrottle<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Requires `slot_delay` slots between swaps of at least `threshold_coin`
+/// coin or `threshold_pc` pc input (0 disables the throttle for that side,
+/// a 0 delay for both).
+pub fn handle_set_swap_throttle(
+    ctx: Context<SetSwapThrottle>,
+    threshold_coin: u64,
+    threshold_pc: u64,
+    slot_delay: u64,
+) -> Result<()> {
+    let pool = &mut ctx.accounts.pool;
+    pool.large_swap_threshold_coin = threshold_coin;
+    pool.large_swap_threshold_pc = threshold_pc;
+    pool.large_swap_slot_delay = slot_delay;
+
+    msg!(
+        "Swap throttle set: threshold coin {}, pc {}, slot delay {}",
+        threshold_coin,
+        threshold_pc,
+        slot_delay
+    );
+
+    Ok(())
+}
@@ -0,0 +1,138 @@
+use crate::error::TradiumError;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint as MintInterface;
+
+#[derive(Accounts)]
+pub struct SetSysDecimalValue<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    #[account(address = pool.lp_mint @ TradiumError::InvalidLpMint)]
+    pub lp_mint: InterfaceAccount<'info, MintInterface>,
+}
+
+pub fn handle_set_sys_decimal_value(
+    ctx: Context<SetSysDecimalValue>,
+    sys_decimal_value: u64,
+) -> Result<()> {
+    validate_sys_decimal_update(sys_decimal_value, ctx.accounts.lp_mint.supply)?;
+
+    let pool = &mut ctx.accounts.pool;
+    let old_value = pool.sys_decimal_value;
+    pool.sys_decimal_value = sys_decimal_value;
+
+    msg!(
+        "sys_decimal_value updated from {} to {}",
+        old_value,
+        sys_decimal_value
+    );
+
+    Ok(())
+}
+
+/// `sys_decimal_value` only shapes the first-deposit LP amount, so it may only
+/// change while no LP has been minted against the old value.
+pub fn validate_sys_decimal_update(sys_decimal_value: u64, lp_supply: u64) -> Result<()> {
+    require!(lp_supply == 0, TradiumError::PoolHasLpSupply);
+    require!(
+        power_of_ten_exponent(sys_decimal_value).is_some(),
+        TradiumError::InvalidSysDecimalValue
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_allowed_on_empty_pool() {
+        assert!(validate_sys_decimal_update(1_000_000, 0).is_ok());
+    }
+
+    #[test]
+    fn update_rejected_on_funded_pool() {
+        assert_eq!(
+            validate_sys_decimal_update(1_000_000, 1).unwrap_err(),
+            TradiumError::PoolHasLpSupply.into()
+        );
+    }
+
+    #[test]
+    fn update_rejects_non_power_of_ten() {
+        assert_eq!(
+            validate_sys_decimal_update(6, 0).unwrap_err(),
+            TradiumError::InvalidSysDecimalValue.into()
+        );
+    }
+
+    #[test]
+    fn handler_refuses_to_rescale_a_pool_with_lp() {
+        use crate::shared::TestAccount;
+        use anchor_lang::solana_program::program_pack::Pack;
+        use std::collections::BTreeSet;
+
+        let (owner, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pool_data = vec![];
+        Tradium {
+            amm_owner: owner,
+            lp_mint,
+            sys_decimal_value: 1_000_000,
+            ..Default::default()
+        }
+        .try_serialize(&mut pool_data)
+        .unwrap();
+        let lp_mint_data = |supply| {
+            let mut data = vec![0u8; spl_token::state::Mint::LEN];
+            spl_token::state::Mint {
+                supply,
+                decimals: 6,
+                is_initialized: true,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+
+        // Returns the handler's result and the value the pool is left with
+        let set_value = |supply| {
+            let mut pool_account =
+                TestAccount::new(Pubkey::new_unique(), crate::ID, pool_data.clone());
+            let mut owner_account = TestAccount::signer(owner);
+            let mut lp_mint_account =
+                TestAccount::new(lp_mint, spl_token::ID, lp_mint_data(supply));
+            let infos = [
+                pool_account.info(),
+                owner_account.info(),
+                lp_mint_account.info(),
+            ];
+            let mut remaining = &infos[..];
+            let mut accounts = SetSysDecimalValue::try_accounts(
+                &crate::ID,
+                &mut remaining,
+                &[],
+                &mut Default::default(),
+                &mut BTreeSet::new(),
+            )
+            .unwrap();
+            let result = handle_set_sys_decimal_value(
+                Context::new(&crate::ID, &mut accounts, &[], Default::default()),
+                1_000,
+            );
+            (result, accounts.pool.sys_decimal_value)
+        };
+
+        // Outstanding LP keeps the value it was minted against
+        let (result, value) = set_value(1);
+        assert_eq!(result.unwrap_err(), TradiumError::PoolHasLpSupply.into());
+        assert_eq!(value, 1_000_000);
+
+        let (result, value) = set_value(0);
+        result.unwrap();
+        assert_eq!(value, 1_000);
+    }
+}
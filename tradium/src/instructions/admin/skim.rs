@@ -0,0 +1,171 @@
+use crate::error::TradiumError;
+use crate::shared;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    /// Pool's coin vault
+    #[account(mut, address = pool.coin_vault @ TradiumError::InvalidCoinVault)]
+    pub coin_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool's PC vault
+    #[account(mut, address = pool.pc_vault @ TradiumError::InvalidPcVault)]
+    pub pc_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = pool.coin_vault_mint)]
+    pub coin_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(address = pool.pc_vault_mint)]
+    pub pc_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(address = pool.lp_mint @ TradiumError::InvalidLpMint)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+
+    /// Owner-chosen coin token account receiving the coin surplus
+    #[account(mut, token::mint = coin_mint)]
+    pub destination_coin_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Owner-chosen PC token account receiving the PC surplus
+    #[account(mut, token::mint = pc_mint)]
+    pub destination_pc_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = pool.coin_token_program @ TradiumError::InvalidTokenProgram)]
+    pub coin_token_program: Interface<'info, TokenInterface>,
+
+    #[account(address = pool.pc_token_program @ TradiumError::InvalidTokenProgram)]
+    pub pc_token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Optional, only required if coin_mint has a transfer hook; validated in the handler
+    pub coin_transfer_hook_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Optional, only required if pc_mint has a transfer hook; validated in the handler
+    pub pc_transfer_hook_program: Option<UncheckedAccount<'info>>,
+}
+
+/// Sweeps what each vault holds beyond the balance the pool recorded for it
+/// (donations, stray transfers) to the owner's destination accounts. The
+/// recorded balances, reserves and owed fees alike, are never touched.
+pub fn handle_skim<'info>(ctx: Context<'_, '_, 'info, 'info, Skim<'info>>) -> Result<()> {
+    let (coin_surplus, pc_surplus) = skim_amounts(
+        &ctx.accounts.pool,
+        ctx.accounts.coin_vault.amount,
+        ctx.accounts.pc_vault.amount,
+        ctx.accounts
+            .pool
+            .claim_supply(ctx.accounts.lp_mint.supply)?,
+    )?;
+
+    let coin_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.coin_transfer_hook_program.as_ref(),
+    )?;
+    let pc_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.pc_mint.to_account_info(),
+        ctx.accounts.pc_transfer_hook_program.as_ref(),
+    )?;
+
+    let pool_account_info = ctx.accounts.pool.to_account_info();
+    let pool_seeds = ctx.accounts.pool.signer_seeds();
+    let signer_seeds = &[&pool_seeds[..]];
+
+    if coin_surplus > 0 {
+        shared::transfer_tokens_with_hook_support(
+            &ctx.accounts.coin_token_program,
+            &ctx.accounts.coin_vault,
+            &ctx.accounts.destination_coin_account,
+            &ctx.accounts.coin_mint.to_account_info(),
+            &pool_account_info,
+            coin_hook,
+            ctx.accounts.coin_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
+            coin_surplus,
+            Some(signer_seeds),
+        )?;
+    }
+    if pc_surplus > 0 {
+        shared::transfer_tokens_with_hook_support(
+            &ctx.accounts.pc_token_program,
+            &ctx.accounts.pc_vault,
+            &ctx.accounts.destination_pc_account,
+            &ctx.accounts.pc_mint.to_account_info(),
+            &pool_account_info,
+            pc_hook,
+            ctx.accounts.pc_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
+            pc_surplus,
+            Some(signer_seeds),
+        )?;
+    }
+
+    msg!("Skimmed surplus: coin {}, pc {}", coin_surplus, pc_surplus);
+
+    Ok(())
+}
+
+/// Surplus of each vault over its recorded balance. A pool with LP
+/// outstanding but no recorded balance on a side predates the recording
+/// and must be `sync`ed first, or its whole vault would look like surplus.
+fn skim_amounts(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    lp_claim_supply: u64,
+) -> Result<(u64, u64)> {
+    let recorded_coin = pool.state_data.pool_coin_amount;
+    let recorded_pc = pool.state_data.pool_pc_amount;
+    if lp_claim_supply > 0 {
+        require!(
+            recorded_coin > 0 && recorded_pc > 0,
+            TradiumError::ReserveDesynced
+        );
+    }
+    Ok((
+        coin_vault_amount.saturating_sub(recorded_coin),
+        pc_vault_amount.saturating_sub(recorded_pc),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_surplus_over_the_recorded_balances_is_skimmed() {
+        let mut pool = Tradium::default();
+        pool.record_reserves(1_000_000, 4_000_000);
+
+        // A donation of 500 coin; the pc vault is exactly as recorded
+        assert_eq!(
+            skim_amounts(&pool, 1_000_500, 4_000_000, 2_000_000).unwrap(),
+            (500, 0)
+        );
+
+        // A vault below its record has no surplus rather than an underflow
+        assert_eq!(
+            skim_amounts(&pool, 999_000, 4_000_000, 2_000_000).unwrap(),
+            (0, 0)
+        );
+
+        // Unrecorded balances of a live pool are not surplus
+        assert_eq!(
+            skim_amounts(&Tradium::default(), 1_000_500, 4_000_000, 2_000_000).unwrap_err(),
+            TradiumError::ReserveDesynced.into()
+        );
+
+        // An empty pool owes no one, so everything in it is surplus
+        assert_eq!(
+            skim_amounts(&Tradium::default(), 500, 0, 0).unwrap(),
+            (500, 0)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::constants::*;
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateFees<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+}
+
+/// Replaces both the trade fee and the swap (owner) fee.
+pub fn handle_update_fees(
+    ctx: Context<UpdateFees>,
+    trade_fee_numerator: u64,
+    trade_fee_denominator: u64,
+    swap_fee_numerator: u64,
+    swap_fee_denominator: u64,
+) -> Result<()> {
+    validate_fee_config(
+        trade_fee_numerator,
+        trade_fee_denominator,
+        swap_fee_numerator,
+        swap_fee_denominator,
+    )?;
+
+    let fees = &mut ctx.accounts.pool.fees;
+    fees.trade_fee_numerator = trade_fee_numerator;
+    fees.trade_fee_denominator = trade_fee_denominator;
+    fees.swap_fee_numerator = swap_fee_numerator;
+    fees.swap_fee_denominator = swap_fee_denominator;
+
+    msg!(
+        "Fees updated: trade {}/{}, swap {}/{}",
+        trade_fee_numerator,
+        trade_fee_denominator,
+        swap_fee_numerator,
+        swap_fee_denominator
+    );
+
+    Ok(())
+}
+
+/// Each fee must be below 100%, and the two together at most `MAX_FEE_BPS`.
+/// Differing denominators must multiply within a `u64`, since that product
+/// is the denominator `Fees::total_swap_fee` charges swaps with.
+pub fn validate_fee_config(
+    trade_fee_numerator: u64,
+    trade_fee_denominator: u64,
+    swap_fee_numerator: u64,
+    swap_fee_denominator: u64,
+) -> Result<()> {
+    require!(
+        trade_fee_numerator < trade_fee_denominator,
+        TradiumError::InvalidFeeConfig
+    );
+    require!(
+        swap_fee_numerator < swap_fee_denominator,
+        TradiumError::InvalidFeeConfig
+    );
+
+    if trade_fee_denominator != swap_fee_denominator {
+        require!(
+            trade_fee_denominator
+                .checked_mul(swap_fee_denominator)
+                .is_some(),
+            TradiumError::InvalidFeeConfig
+        );
+    }
+
+    // trade_num / trade_den + swap_num / swap_den <= MAX_FEE_BPS / FEE_DENOMINATOR
+    let (trade_num, trade_den) = (trade_fee_numerator as u128, trade_fee_denominator as u128);
+    let (swap_num, swap_den) = (swap_fee_numerator as u128, swap_fee_denominator as u128);
+    let total = (trade_num * swap_den)
+        .checked_add(swap_num * trade_den)
+        .and_then(|sum| sum.checked_mul(FEE_DENOMINATOR as u128))
+        .ok_or(TradiumError::MathOverflow)?;
+    let cap = (MAX_FEE_BPS as u128 * trade_den)
+        .checked_mul(swap_den)
+        .ok_or(TradiumError::MathOverflow)?;
+    require!(total <= cap, TradiumError::InvalidFeeConfig);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_fees_are_valid() {
+        assert!(validate_fee_config(
+            DEFAULT_TRADE_FEE,
+            FEE_DENOMINATOR,
+            DEFAULT_OWNER_FEE,
+            FEE_DENOMINATOR
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn total_fee_is_capped_across_denominators() {
+        // 9% + 1% is exactly the cap, on mixed denominators
+        assert!(validate_fee_config(900, FEE_DENOMINATOR, 1, 100).is_ok());
+        assert_eq!(
+            validate_fee_config(901, FEE_DENOMINATOR, 1, 100).unwrap_err(),
+            TradiumError::InvalidFeeConfig.into()
+        );
+    }
+
+    #[test]
+    fn denominators_that_would_overflow_the_swap_fee_are_rejected() {
+        use crate::instructions::swap::swap_preflight;
+        use crate::shared::TestAccount;
+        use std::collections::BTreeSet;
+
+        let owner = Pubkey::new_unique();
+        let mut pool = Tradium {
+            amm_owner: owner,
+            ..Default::default()
+        };
+        pool.fees.swap_fee_numerator = 5;
+        pool.fees.swap_fee_denominator = FEE_DENOMINATOR;
+        let mut pool_data = vec![];
+        pool.try_serialize(&mut pool_data).unwrap();
+
+        let mut pool_account = TestAccount::new(Pubkey::new_unique(), crate::ID, pool_data);
+        let mut owner_account = TestAccount::signer(owner);
+        let infos = [pool_account.info(), owner_account.info()];
+        let mut remaining = &infos[..];
+        let mut accounts = UpdateFees::try_accounts(
+            &crate::ID,
+            &mut remaining,
+            &[],
+            &mut Default::default(),
+            &mut BTreeSet::new(),
+        )
+        .unwrap();
+
+        // Each fee is tiny, but 10^10 * (10^10 + 1) does not fit a u64
+        let denominator = 10_000_000_000;
+        let result = handle_update_fees(
+            Context::new(&crate::ID, &mut accounts, &[], Default::default()),
+            1,
+            denominator,
+            1,
+            denominator + 1,
+        );
+        assert_eq!(result.unwrap_err(), TradiumError::InvalidFeeConfig.into());
+
+        // The pool keeps fees it can still charge, so swaps go through
+        assert!(swap_preflight(&accounts.pool, 1_000_000, 1_000_000, 10_000, 0, 0).is_ok());
+        handle_update_fees(
+            Context::new(&crate::ID, &mut accounts, &[], Default::default()),
+            30,
+            FEE_DENOMINATOR,
+            1,
+            100_000,
+        )
+        .unwrap();
+        assert!(swap_preflight(&accounts.pool, 1_000_000, 1_000_000, 10_000, 0, 0).is_ok());
+    }
+
+    #[test]
+    fn each_numerator_must_be_below_its_denominator() {
+        assert_eq!(
+            validate_fee_config(0, 0, 0, FEE_DENOMINATOR).unwrap_err(),
+            TradiumError::InvalidFeeConfig.into()
+        );
+        assert_eq!(
+            validate_fee_config(0, FEE_DENOMINATOR, 5, 5).unwrap_err(),
+            TradiumError::InvalidFeeConfig.into()
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use crate::error::TradiumError;
+use crate::shared;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct UpdateWhitelistedHooks<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.amm_owner @ TradiumError::Unauthorized)]
+    pub owner: Signer<'info>,
+
+    /// CHECK: Only read for its transfer hook; must be the pool's coin mint
+    #[account(address = pool.coin_vault_mint @ TradiumError::InvalidCoinMint)]
+    pub coin_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Only read for its transfer hook; must be the pool's pc mint
+    #[account(address = pool.pc_vault_mint @ TradiumError::InvalidPcMint)]
+    pub pc_mint: UncheckedAccount<'info>,
+}
+
+/// Adds `hook` to the pool's transfer hook whitelist.
+pub fn handle_add_whitelisted_hook(
+    ctx: Context<UpdateWhitelistedHooks>,
+    hook: Pubkey,
+) -> Result<()> {
+    whitelist_hook(&mut ctx.accounts.pool, hook)?;
+    msg!("Transfer hook whitelisted: {}", hook);
+    Ok(())
+}
+
+/// Removes `hook` from the pool's transfer hook whitelist. Removing a hook
+/// one of the pool's own mints requires would brick every transfer, so that
+/// needs `force`.
+pub fn handle_remove_whitelisted_hook(
+    ctx: Context<UpdateWhitelistedHooks>,
+    hook: Pubkey,
+    force: bool,
+) -> Result<()> {
+    check_hook_not_in_use(
+        &[
+            ctx.accounts.coin_mint.to_account_info(),
+            ctx.accounts.pc_mint.to_account_info(),
+        ],
+        &hook,
+        force,
+    )?;
+
+    unwhitelist_hook(&mut ctx.accounts.pool, hook)?;
+    msg!("Transfer hook removed from whitelist: {}", hook);
+    Ok(())
+}
+
+/// Fails with `HookInUse` if one of `mints` requires `hook`, unless `force`.
+fn check_hook_not_in_use(mints: &[AccountInfo], hook: &Pubkey, force: bool) -> Result<()> {
+    let in_use = mints
+        .iter()
+        .any(|mint| shared::mint_transfer_hook_program_id(mint) == Some(*hook));
+    require!(!in_use || force, TradiumError::HookInUse);
+    Ok(())
+}
+
+fn whitelist_hook(pool: &mut Tradium, hook: Pubkey) -> Result<()> {
+    require!(
+        !pool.active_whitelisted_hooks().contains(&hook),
+        TradiumError::HookAlreadyWhitelisted
+    );
+    let len = pool.active_whitelisted_hooks().len();
+    require!(
+        len < pool.whitelisted_transfer_hooks.len(),
+        TradiumError::HookWhitelistFull
+    );
+
+    pool.whitelisted_transfer_hooks[len] = hook;
+    pool.num_whitelisted_hooks = (len + 1) as u8;
+    Ok(())
+}
+
+/// Removes `hook`, keeping the active entries contiguous.
+fn unwhitelist_hook(pool: &mut Tradium, hook: Pubkey) -> Result<()> {
+    let len = pool.active_whitelisted_hooks().len();
+    let index = pool
+        .active_whitelisted_hooks()
+        .iter()
+        .position(|whitelisted| *whitelisted == hook)
+        .ok_or(TradiumError::HookNotWhitelisted)?;
+
+    pool.whitelisted_transfer_hooks
+        .copy_within(index + 1..len, index);
+    pool.whitelisted_transfer_hooks[len - 1] = Pubkey::default();
+    pool.num_whitelisted_hooks = (len - 1) as u8;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MAX_WHITELISTED_HOOKS;
+
+    #[test]
+    fn hooks_are_added_and_removed_contiguously() {
+        let mut pool = Tradium::default();
+        let hooks = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        for hook in hooks {
+            whitelist_hook(&mut pool, hook).unwrap();
+        }
+        assert_eq!(
+            whitelist_hook(&mut pool, hooks[0]).unwrap_err(),
+            TradiumError::HookAlreadyWhitelisted.into()
+        );
+
+        unwhitelist_hook(&mut pool, hooks[0]).unwrap();
+        assert_eq!(pool.active_whitelisted_hooks(), &hooks[1..]);
+        assert_eq!(pool.whitelisted_transfer_hooks[2], Pubkey::default());
+        assert_eq!(
+            unwhitelist_hook(&mut pool, hooks[0]).unwrap_err(),
+            TradiumError::HookNotWhitelisted.into()
+        );
+    }
+
+    #[test]
+    fn full_whitelist_rejects_new_hooks() {
+        let mut pool = Tradium::default();
+        for _ in 0..MAX_WHITELISTED_HOOKS {
+            whitelist_hook(&mut pool, Pubkey::new_unique()).unwrap();
+        }
+        assert_eq!(
+            whitelist_hook(&mut pool, Pubkey::new_unique()).unwrap_err(),
+            TradiumError::HookWhitelistFull.into()
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn removing_a_hook_used_by_a_pool_mint_is_guarded() {
+        use crate::shared::{hooked_mint_data, TestAccount};
+
+        let hook = Pubkey::new_unique();
+        let mut mint_account = TestAccount::new(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            hooked_mint_data(hook),
+        );
+        let hooked_mint = mint_account.info();
+        let mints = [hooked_mint];
+
+        assert_eq!(
+            check_hook_not_in_use(&mints, &hook, false).unwrap_err(),
+            TradiumError::HookInUse.into()
+        );
+        assert!(check_hook_not_in_use(&mints, &hook, true).is_ok());
+        assert!(check_hook_not_in_use(&mints, &Pubkey::new_unique(), false).is_ok());
+    }
+}
@@ -1,13 +1,14 @@
+use crate::constants::{FEE_DENOMINATOR, MAX_LIQUIDITY_LOCK_BPS};
+use crate::curve;
 use crate::error::TradiumError;
+use crate::instructions::swap::check_mint_token_program;
+use crate::pda;
 use crate::shared; // Import shared module
 use crate::state::Tradium;
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
 use anchor_spl::token_interface::{
-    Mint as MintInterface, TokenAccount as TokenAccountInterface, TokenInterface,
+    self, Mint as MintInterface, MintTo, TokenAccount as TokenAccountInterface, TokenInterface,
 };
-use spl_token_2022::extension::transfer_hook::TransferHook;
-use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
 
 #[derive(Accounts)]
 pub struct Deposit<'info> {
@@ -21,7 +22,7 @@ pub struct Deposit<'info> {
     pub user_pc_account: InterfaceAccount<'info, TokenAccountInterface>,
 
     #[account(mut)]
-    pub user_lp_account: Account<'info, TokenAccount>,
+    pub user_lp_account: InterfaceAccount<'info, TokenAccountInterface>,
 
     #[account(mut)]
     pub coin_vault: InterfaceAccount<'info, TokenAccountInterface>,
@@ -30,41 +31,34 @@ pub struct Deposit<'info> {
     pub pc_vault: InterfaceAccount<'info, TokenAccountInterface>,
 
     #[account(mut)]
-    pub lp_mint: Account<'info, Mint>,
+    pub lp_mint: InterfaceAccount<'info, MintInterface>,
 
     pub coin_mint: InterfaceAccount<'info, MintInterface>,
     pub pc_mint: InterfaceAccount<'info, MintInterface>,
 
     pub user: Signer<'info>,
 
-    pub token_program: Program<'info, Token>,
+    /// LP token program (Token or Token2022), the LP mint's owner
+    pub token_program: Interface<'info, TokenInterface>,
     pub coin_token_program: Interface<'info, TokenInterface>,
     pub pc_token_program: Interface<'info, TokenInterface>,
 
-    /// CHECK: Optional, only required if coin_mint has a transfer hook
-    #[account(
-        constraint = shared::validate_transfer_hook_program(
-            &coin_mint,
-            &coin_transfer_hook_program.to_account_info(),
-            &pool.whitelisted_transfer_hooks,
-            pool.num_whitelisted_hooks
-        ) @ TradiumError::InvalidTransferHookProgram
-    )]
+    /// CHECK: Optional, only required if coin_mint has a transfer hook; validated in the handler
     pub coin_transfer_hook_program: Option<UncheckedAccount<'info>>,
 
-    /// CHECK: Optional, only required if pc_mint has a transfer hook
-    #[account(
-        constraint = shared::validate_transfer_hook_program(
-            &pc_mint,
-            &pc_transfer_hook_program.to_account_info(),
-            &pool.whitelisted_transfer_hooks,
-            pool.num_whitelisted_hooks
-        ) @ TradiumError::InvalidTransferHookProgram
-    )]
+    /// CHECK: Optional, only required if pc_mint has a transfer hook; validated in the handler
     pub pc_transfer_hook_program: Option<UncheckedAccount<'info>>,
 }
 
-pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Result<()> {
+/// Deposits `amount_coin` and `amount_pc`, failing if they would mint less
+/// than `min_lp_out` LP. Returns the LP minted and the pool share it leaves
+/// the depositor with, so a simulated deposit doubles as a quote.
+pub fn handle_deposit<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+    amount_coin: u64,
+    amount_pc: u64,
+    min_lp_out: u64,
+) -> Result<DepositResult> {
     let pool = &mut ctx.accounts.pool;
 
     // Validate input amounts
@@ -73,6 +67,14 @@ pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Resul
         TradiumError::InvalidDepositAmount
     );
 
+    // Paused pools take no deposits
+    require!(
+        pool.is_active(Clock::get()?.unix_timestamp),
+        TradiumError::InvalidPoolState
+    );
+
+    check_deposit_limit(pool, amount_coin, amount_pc)?;
+
     // Validate token programs match pool configuration
     require!(
         ctx.accounts.coin_token_program.key() == pool.coin_token_program,
@@ -92,10 +94,19 @@ pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Resul
         ctx.accounts.pc_vault.key() == pool.pc_vault,
         TradiumError::InvalidPcVault
     );
+    // LP is only credited against vaults the pool actually controls
+    check_vault_owner(&ctx.accounts.coin_vault.owner, &pool.key())?;
+    check_vault_owner(&ctx.accounts.pc_vault.owner, &pool.key())?;
+    // Only a canonical pool, whose stored bump derives its address, takes deposits
+    pool.check_bump(&pool.key())?;
     require!(
         ctx.accounts.lp_mint.key() == pool.lp_mint,
         TradiumError::InvalidLpMint
     );
+    require!(
+        pda::is_canonical_lp_mint(&pool.key(), &ctx.accounts.lp_mint.key()),
+        TradiumError::InvalidLpMint
+    );
     require!(
         ctx.accounts.coin_mint.key() == pool.coin_vault_mint,
         TradiumError::InvalidCoinMint
@@ -105,10 +116,45 @@ pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Resul
         TradiumError::InvalidPcMint
     );
 
-    // Get current vault balances before deposit
-    let coin_vault_balance_before = ctx.accounts.coin_vault.amount;
-    let pc_vault_balance_before = ctx.accounts.pc_vault.amount;
-    let total_lp_supply = ctx.accounts.lp_mint.supply;
+    // Get current reserves before deposit, excluding fees owed to the owner
+    let (coin_vault_balance_before, pc_vault_balance_before) =
+        pool.active_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount)?;
+    // Shares are priced against the supply including the locked LP
+    let total_lp_supply = pool.claim_supply(ctx.accounts.lp_mint.supply)?;
+
+    // The first deposit sets the price, so it needs both sides; check before any transfer
+    check_first_deposit_sides(total_lp_supply, amount_coin, amount_pc)?;
+
+    // Outstanding LP needs both reserves to price against
+    check_reserves_back_supply(
+        total_lp_supply,
+        coin_vault_balance_before,
+        pc_vault_balance_before,
+    )?;
+
+    // Price the deposit before any transfer, so a deposit that would mint
+    // nothing (e.g. a single-sided one) fails before the user's tokens move
+    let lp_amount = deposit_lp_amount(
+        pool,
+        amount_coin,
+        amount_pc,
+        coin_vault_balance_before,
+        pc_vault_balance_before,
+        total_lp_supply,
+        min_lp_out,
+    )?;
+
+    // Resolve each mint's transfer hook once; the transfers reuse the result
+    let coin_hook = shared::resolve_pool_transfer_hook(
+        pool,
+        &ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.coin_transfer_hook_program.as_ref(),
+    )?;
+    let pc_hook = shared::resolve_pool_transfer_hook(
+        pool,
+        &ctx.accounts.pc_mint.to_account_info(),
+        ctx.accounts.pc_transfer_hook_program.as_ref(),
+    )?;
 
     // Transfer coin tokens from user to vault if amount > 0
     if amount_coin > 0 {
@@ -116,9 +162,11 @@ pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Resul
             &ctx.accounts.coin_token_program,
             &ctx.accounts.user_coin_account,
             &ctx.accounts.coin_vault,
+            &ctx.accounts.coin_mint.to_account_info(),
             &ctx.accounts.user.to_account_info(),
-            &ctx.accounts.coin_mint,
+            coin_hook,
             ctx.accounts.coin_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
             amount_coin,
             None,
         )?;
@@ -130,34 +178,48 @@ pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Resul
             &ctx.accounts.pc_token_program,
             &ctx.accounts.user_pc_account,
             &ctx.accounts.pc_vault,
+            &ctx.accounts.pc_mint.to_account_info(),
             &ctx.accounts.user.to_account_info(),
-            &ctx.accounts.pc_mint,
+            pc_hook,
             ctx.accounts.pc_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
             amount_pc,
             None,
         )?;
     }
 
-    // Calculate LP tokens to mint
-    let lp_amount = calculate_lp_tokens(
-        pool,
-        amount_coin,
-        amount_pc,
-        coin_vault_balance_before,
-        pc_vault_balance_before,
-        total_lp_supply,
+    // The share is of the claim supply, so the first depositor's excludes the lock
+    let lp_supply_after = pool.claim_supply(
+        ctx.accounts
+            .lp_mint
+            .supply
+            .checked_add(lp_amount)
+            .ok_or(TradiumError::MathOverflow)?,
     )?;
+    let share_bps = curve::deposit_share_bps(lp_amount, lp_supply_after);
+
+    // The LP mint's authority (and freeze authority) is the pool PDA itself
+    let pool_seeds = pool.signer_seeds();
+    let signer_seeds = &[&pool_seeds[..]];
+
+    // Soulbound LP accounts are frozen between deposits; thaw to mint into them
+    if pool.soulbound_lp && ctx.accounts.user_lp_account.is_frozen() {
+        shared::set_lp_account_frozen(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_lp_account.to_account_info(),
+            &ctx.accounts.lp_mint.to_account_info(),
+            &pool.to_account_info(),
+            signer_seeds,
+            false,
+        )?;
+    }
 
-    require!(lp_amount > 0, TradiumError::InsufficientLiquidityMinted);
-
-    // Create mint authority seeds for PDA signing
-    let mint_authority_bump = pool.nonce[0];
-    let pool_key = pool.key();
-    let mint_authority_seeds: &[&[u8]] =
-        &[b"mint_authority", pool_key.as_ref(), &[mint_authority_bump]];
-    let signer_seeds: &[&[&[u8]]] = &[mint_authority_seeds];
-
-    // Mint LP tokens to user
+    // Mint LP tokens to user through the LP mint's own program; minting never
+    // runs a Token-2022 transfer hook, so a hooked LP mint needs no extra accounts
+    check_mint_token_program(
+        ctx.accounts.lp_mint.to_account_info().owner,
+        &ctx.accounts.token_program.key(),
+    )?;
     let mint_ctx = CpiContext::new_with_signer(
         ctx.accounts.token_program.to_account_info(),
         MintTo {
@@ -168,29 +230,184 @@ pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Resul
         signer_seeds,
     );
 
-    token::mint_to(mint_ctx, lp_amount)?;
+    token_interface::mint_to(mint_ctx, lp_amount)?;
+
+    if pool.soulbound_lp {
+        shared::set_lp_account_frozen(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.user_lp_account.to_account_info(),
+            &ctx.accounts.lp_mint.to_account_info(),
+            &pool.to_account_info(),
+            signer_seeds,
+            true,
+        )?;
+    }
 
     // Update pool state
     pool.lp_amount = pool
         .lp_amount
         .checked_add(lp_amount)
         .ok_or(TradiumError::MathOverflow)?;
+    pool.state_data.record_deposit(amount_coin, amount_pc);
+    ctx.accounts.coin_vault.reload()?;
+    ctx.accounts.pc_vault.reload()?;
+    pool.record_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount);
 
-    // Update nonce for security
-    pool.nonce[0] = pool.nonce[0]
-        .checked_add(1)
-        .ok_or(TradiumError::MathOverflow)?;
+    // Advance the anti-replay counter; the bump is left alone
+    pool.advance_counter();
+    pool.record_activity(Clock::get()?.unix_timestamp);
 
     msg!(
-        "Deposited {} coin tokens, {} pc tokens, minted {} LP tokens",
+        "Deposited {} coin tokens, {} pc tokens, minted {} LP tokens ({} bps of the pool)",
         amount_coin,
         amount_pc,
-        lp_amount
+        lp_amount,
+        share_bps
     );
 
+    Ok(DepositResult {
+        lp_minted: lp_amount,
+        share_bps,
+    })
+}
+
+/// LP minted by a deposit and the depositor's resulting share of the pool in
+/// basis points, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct DepositResult {
+    pub lp_minted: u64,
+    pub share_bps: u64,
+}
+
+/// Deposits `amount_coin` together with the exact pc amount that matches the
+/// current pool ratio, failing if that amount exceeds `max_pc`.
+pub fn handle_deposit_balanced<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+    amount_coin: u64,
+    max_pc: u64,
+) -> Result<DepositResult> {
+    require!(amount_coin > 0, TradiumError::InvalidDepositAmount);
+
+    let (coin_reserve, pc_reserve) = ctx
+        .accounts
+        .pool
+        .active_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount)?;
+    let amount_pc = balanced_pc_amount(amount_coin, coin_reserve, pc_reserve, max_pc)?;
+
+    // max_pc already bounds what the ratio may cost
+    handle_deposit(ctx, amount_coin, amount_pc, 0)
+}
+
+/// Pc amount matching `amount_coin` at the current reserves, capped by `max_pc`.
+fn balanced_pc_amount(
+    amount_coin: u64,
+    coin_reserve: u64,
+    pc_reserve: u64,
+    max_pc: u64,
+) -> Result<u64> {
+    let amount_pc = curve::pair_amount(amount_coin, coin_reserve, pc_reserve)?;
+    require!(amount_pc <= max_pc, TradiumError::SlippageExceeded);
+    Ok(amount_pc)
+}
+
+/// Rejects a vault whose token account owner is no longer the pool.
+fn check_vault_owner(vault_owner: &Pubkey, pool_key: &Pubkey) -> Result<()> {
+    require_keys_eq!(*vault_owner, *pool_key, TradiumError::VaultOwnerMismatch);
+    Ok(())
+}
+
+/// A pool with LP supply 0 has no price yet; a one-sided deposit would mint
+/// zero LP, so require both amounts up front.
+fn check_first_deposit_sides(total_lp_supply: u64, amount_coin: u64, amount_pc: u64) -> Result<()> {
+    if total_lp_supply == 0 {
+        require!(
+            amount_coin > 0 && amount_pc > 0,
+            TradiumError::FirstDepositRequiresBothSides
+        );
+    }
+    Ok(())
+}
+
+/// Rejects a pool with LP outstanding but an empty reserve, where the
+/// proportional share on that side would be zero.
+fn check_reserves_back_supply(
+    total_lp_supply: u64,
+    coin_reserve: u64,
+    pc_reserve: u64,
+) -> Result<()> {
+    if total_lp_supply > 0 {
+        require!(
+            coin_reserve > 0 && pc_reserve > 0,
+            TradiumError::ReserveDesynced
+        );
+    }
     Ok(())
 }
 
+/// Fails with `SlippageExceeded` if the deposit mints less than `min_lp_out`,
+/// e.g. because the pool ratio moved since it was quoted.
+fn check_min_lp_out(lp_amount: u64, min_lp_out: u64) -> Result<()> {
+    require!(lp_amount >= min_lp_out, TradiumError::SlippageExceeded);
+    Ok(())
+}
+
+/// Fails with `DepositLimitExceeded` if either side is above the pool's
+/// per-transaction deposit limit.
+fn check_deposit_limit(pool: &Tradium, amount_coin: u64, amount_pc: u64) -> Result<()> {
+    let within = |amount: u64, limit: u64| limit == 0 || amount <= limit;
+    require!(
+        within(amount_coin, pool.max_deposit_coin) && within(amount_pc, pool.max_deposit_pc),
+        TradiumError::DepositLimitExceeded
+    );
+    Ok(())
+}
+
+/// The first deposit permanently locks `Tradium::first_deposit_lock` of the
+/// LP it would mint, so the supply can never be a few units an attacker could
+/// inflate the value of by donating to the vaults. The locked LP is never
+/// minted; it only counts towards `Tradium::claim_supply`. A lock above
+/// `MAX_LIQUIDITY_LOCK_BPS` of the LP is rejected rather than letting the
+/// creator give most of their deposit away. Returns the LP left to mint.
+fn lock_minimum_liquidity(pool: &mut Tradium, lp_amount: u64) -> Result<u64> {
+    let lock = pool.first_deposit_lock();
+    require!(lp_amount > lock, TradiumError::InsufficientLiquidityMinted);
+    require!(
+        (lock as u128) * (FEE_DENOMINATOR as u128)
+            < (lp_amount as u128) * (MAX_LIQUIDITY_LOCK_BPS as u128),
+        TradiumError::InvalidLiquidityLock
+    );
+    pool.locked_lp = lock;
+    Ok(lp_amount - lock)
+}
+
+/// LP a deposit mints, with the first deposit's lock taken out, failing if it
+/// is zero or below `min_lp_out`. The deposit calls this before any transfer.
+fn deposit_lp_amount(
+    pool: &mut Tradium,
+    amount_coin: u64,
+    amount_pc: u64,
+    coin_vault_balance_before: u64,
+    pc_vault_balance_before: u64,
+    total_lp_supply: u64,
+    min_lp_out: u64,
+) -> Result<u64> {
+    let mut lp_amount = calculate_lp_tokens(
+        pool,
+        amount_coin,
+        amount_pc,
+        coin_vault_balance_before,
+        pc_vault_balance_before,
+        total_lp_supply,
+    )?;
+    if total_lp_supply == 0 {
+        lp_amount = lock_minimum_liquidity(pool, lp_amount)?;
+    }
+
+    require!(lp_amount > 0, TradiumError::InsufficientLiquidityMinted);
+    check_min_lp_out(lp_amount, min_lp_out)?;
+    Ok(lp_amount)
+}
+
 fn calculate_lp_tokens(
     pool: &Tradium,
     amount_coin: u64,
@@ -200,70 +417,361 @@ fn calculate_lp_tokens(
     total_lp_supply: u64,
 ) -> Result<u64> {
     let lp_amount = if total_lp_supply == 0 {
-        let coin_amount_normalized =
-            normalize_amount(amount_coin, pool.coin_decimals, pool.sys_decimal_value)?;
-        let pc_amount_normalized =
-            normalize_amount(amount_pc, pool.pc_decimals, pool.sys_decimal_value)?;
-
-        // Calculate geometric mean: sqrt(coin_normalized * pc_normalized)
-        integer_sqrt(
-            coin_amount_normalized
-                .checked_mul(pc_amount_normalized)
-                .ok_or(TradiumError::MathOverflow)?,
+        curve::initial_lp(
+            amount_coin,
+            amount_pc,
+            pool.coin_decimals,
+            pool.pc_decimals,
+            pool.sys_decimal_value,
         )?
     } else {
         // Subsequent deposits - maintain proportional shares
-        let coin_share = if coin_vault_balance_before > 0 && amount_coin > 0 {
-            amount_coin
-                .checked_mul(total_lp_supply)
-                .ok_or(TradiumError::MathOverflow)?
-                .checked_div(coin_vault_balance_before)
-                .ok_or(TradiumError::MathOverflow)?
-        } else {
-            0
+        curve::get_lp_for_deposit(
+            amount_coin,
+            amount_pc,
+            coin_vault_balance_before,
+            pc_vault_balance_before,
+            total_lp_supply,
+        )?
+    };
+
+    Ok(lp_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MIN_LIQUIDITY;
+    use crate::instructions::withdraw::withdraw_amounts;
+    #[cfg(feature = "token-2022")]
+    use crate::shared::TestAccount;
+
+    #[test]
+    fn tiny_first_deposit_reports_deposit_too_small() {
+        let pool = Tradium {
+            coin_decimals: 9,
+            pc_decimals: 6,
+            sys_decimal_value: 1_000_000,
+            ..Default::default()
         };
+        assert_eq!(
+            calculate_lp_tokens(&pool, 1, 1_000_000, 0, 0, 0).unwrap_err(),
+            TradiumError::DepositTooSmall.into()
+        );
+    }
+
+    #[test]
+    fn balanced_deposit_pulls_matching_pc_amount() {
+        // 1:4 pool, 1_000 coin needs exactly 4_000 pc
+        assert_eq!(
+            balanced_pc_amount(1_000, 1_000_000, 4_000_000, u64::MAX).unwrap(),
+            4_000
+        );
+
+        // Inexact ratios round up in the pool's favor
+        assert_eq!(balanced_pc_amount(1, 3, 2, u64::MAX).unwrap(), 1);
+
+        // The matched amount mints the same share on both sides
+        let pool = Tradium::default();
+        let lp = calculate_lp_tokens(&pool, 1_000, 4_000, 1_000_000, 4_000_000, 2_000_000).unwrap();
+        assert_eq!(lp, 2_000);
+    }
+
+    #[test]
+    fn balanced_deposit_respects_max_pc_and_empty_pool() {
+        assert_eq!(
+            balanced_pc_amount(1_000, 1_000_000, 4_000_000, 3_999).unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+        assert_eq!(
+            balanced_pc_amount(1_000, 0, 0, u64::MAX).unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+    }
+
+    #[test]
+    fn one_sided_first_deposit_is_rejected() {
+        assert_eq!(
+            check_first_deposit_sides(0, 1_000, 0).unwrap_err(),
+            TradiumError::FirstDepositRequiresBothSides.into()
+        );
+        assert_eq!(
+            check_first_deposit_sides(0, 0, 1_000).unwrap_err(),
+            TradiumError::FirstDepositRequiresBothSides.into()
+        );
+        assert!(check_first_deposit_sides(0, 1_000, 1_000).is_ok());
+        // Later deposits may still be one-sided
+        assert!(check_first_deposit_sides(1_000, 1_000, 0).is_ok());
+    }
+
+    #[test]
+    fn vault_with_foreign_owner_is_rejected() {
+        let pool_key = Pubkey::new_unique();
+        assert!(check_vault_owner(&pool_key, &pool_key).is_ok());
+        assert_eq!(
+            check_vault_owner(&Pubkey::new_unique(), &pool_key).unwrap_err(),
+            TradiumError::VaultOwnerMismatch.into()
+        );
+    }
 
-        let pc_share = if pc_vault_balance_before > 0 && amount_pc > 0 {
-            amount_pc
-                .checked_mul(total_lp_supply)
-                .ok_or(TradiumError::MathOverflow)?
-                .checked_div(pc_vault_balance_before)
-                .ok_or(TradiumError::MathOverflow)?
-        } else {
+    #[test]
+    fn nonzero_supply_with_empty_vault_is_reserve_desync() {
+        assert_eq!(
+            check_reserves_back_supply(1_000, 0, 4_000).unwrap_err(),
+            TradiumError::ReserveDesynced.into()
+        );
+        assert_eq!(
+            check_reserves_back_supply(1_000, 1_000, 0).unwrap_err(),
+            TradiumError::ReserveDesynced.into()
+        );
+        assert!(check_reserves_back_supply(1_000, 1_000, 4_000).is_ok());
+        assert!(check_reserves_back_supply(0, 0, 0).is_ok());
+
+        // Without the guard the deposit would have minted nothing
+        let pool = Tradium::default();
+        assert_eq!(
+            calculate_lp_tokens(&pool, 1_000, 4_000, 0, 4_000, 1_000).unwrap(),
             0
+        );
+    }
+
+    #[test]
+    fn deposit_limit_is_inclusive_per_side() {
+        let pool = Tradium {
+            max_deposit_coin: 1_000,
+            max_deposit_pc: 4_000,
+            ..Default::default()
         };
+        assert!(check_deposit_limit(&pool, 1_000, 4_000).is_ok());
+        assert_eq!(
+            check_deposit_limit(&pool, 1_001, 4_000).unwrap_err(),
+            TradiumError::DepositLimitExceeded.into()
+        );
+        assert_eq!(
+            check_deposit_limit(&pool, 1_000, 4_001).unwrap_err(),
+            TradiumError::DepositLimitExceeded.into()
+        );
+
+        // Unset limits allow any size
+        assert!(check_deposit_limit(&Tradium::default(), u64::MAX, u64::MAX).is_ok());
+    }
 
-        // Use the minimum of the two shares to prevent dilution attacks
-        std::cmp::min(coin_share, pc_share)
-    };
+    #[test]
+    fn locked_minimum_liquidity_neutralizes_the_inflation_attack() {
+        let mut pool = Tradium {
+            coin_decimals: 6,
+            pc_decimals: 6,
+            sys_decimal_value: 1_000_000,
+            ..Default::default()
+        };
+        let donation = 1_000_000;
+
+        // Unlocked, a 1:1 first deposit holds the whole supply of 1 LP; after
+        // a donation the victim's deposit rounds down to nothing
+        let dust_lp = calculate_lp_tokens(&pool, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(dust_lp, 1);
+        assert_eq!(
+            calculate_lp_tokens(&pool, donation, donation, 1 + donation, 1 + donation, 1).unwrap(),
+            0
+        );
+
+        // That first deposit is now rejected outright
+        assert_eq!(
+            lock_minimum_liquidity(&mut pool.clone(), dust_lp).unwrap_err(),
+            TradiumError::InsufficientLiquidityMinted.into()
+        );
+
+        // The smallest accepted first deposit locks under a tenth of its LP
+        let first_lp = calculate_lp_tokens(&pool, 10_001, 10_001, 0, 0, 0).unwrap();
+        let attacker_lp = lock_minimum_liquidity(&mut pool, first_lp).unwrap();
+        assert_eq!((attacker_lp, pool.locked_lp), (9_001, MIN_LIQUIDITY));
+
+        // After the same donation the victim still gets a fair share
+        let reserve = 10_001 + donation;
+        let supply = pool.claim_supply(attacker_lp).unwrap();
+        let victim_lp =
+            calculate_lp_tokens(&pool, donation, donation, reserve, reserve, supply).unwrap();
+        assert_eq!(victim_lp, 9_901);
+
+        // And the attacker forfeits part of the donation to the locked LP
+        let supply = supply + victim_lp;
+        let reserve = reserve + donation;
+        let (attacker_coin, _) = withdraw_amounts(reserve, reserve, attacker_lp, supply).unwrap();
+        let (victim_coin, _) = withdraw_amounts(reserve, reserve, victim_lp, supply).unwrap();
+        assert_eq!(attacker_coin, 909_055);
+        assert_eq!(victim_coin, 999_950);
+    }
 
-    Ok(lp_amount)
-}
+    #[test]
+    fn small_first_deposit_with_large_lock_is_rejected() {
+        let mut pool = Tradium {
+            coin_decimals: 6,
+            pc_decimals: 6,
+            sys_decimal_value: 1_000_000,
+            liquidity_lock: 1_000_000,
+            ..Default::default()
+        };
 
-fn normalize_amount(amount: u64, token_decimals: u64, sys_decimals: u64) -> Result<u64> {
-    if sys_decimals >= token_decimals {
-        amount
-            .checked_mul(10_u64.pow((sys_decimals - token_decimals) as u32))
-            .ok_or(TradiumError::MathOverflow.into())
-    } else {
-        amount
-            .checked_div(10_u64.pow((token_decimals - sys_decimals) as u32))
-            .ok_or(TradiumError::MathOverflow.into())
+        // Enough LP to cover the lock, but the lock would take most of it
+        let first_lp = calculate_lp_tokens(&pool, 2_000_000, 2_000_000, 0, 0, 0).unwrap();
+        assert_eq!(
+            lock_minimum_liquidity(&mut pool.clone(), first_lp).unwrap_err(),
+            TradiumError::InvalidLiquidityLock.into()
+        );
+
+        // The lock must stay under a tenth of the LP, not merely reach it
+        let first_lp = calculate_lp_tokens(&pool, 10_000_000, 10_000_000, 0, 0, 0).unwrap();
+        assert_eq!(
+            lock_minimum_liquidity(&mut pool.clone(), first_lp).unwrap_err(),
+            TradiumError::InvalidLiquidityLock.into()
+        );
+
+        let first_lp = calculate_lp_tokens(&pool, 10_000_001, 10_000_001, 0, 0, 0).unwrap();
+        assert_eq!(
+            lock_minimum_liquidity(&mut pool, first_lp).unwrap(),
+            9_000_001
+        );
+        assert_eq!(pool.locked_lp, 1_000_000);
     }
-}
 
-fn integer_sqrt(n: u64) -> Result<u64> {
-    if n == 0 {
-        return Ok(0);
+    #[test]
+    fn deposit_after_ratio_shift_fails_min_lp_out() {
+        let pool = Tradium::default();
+        let supply = 2_000_000;
+
+        // Quoted against a 1:4 pool, the deposit mints 2_000 LP
+        let quoted =
+            calculate_lp_tokens(&pool, 1_000, 4_000, 1_000_000, 4_000_000, supply).unwrap();
+        assert_eq!(quoted, 2_000);
+        let min_lp_out = quoted * 99 / 100;
+        assert!(check_min_lp_out(quoted, min_lp_out).is_ok());
+
+        // A swap front-running the deposit moves the ratio, and the coin side
+        // now mints less
+        let lp_amount =
+            calculate_lp_tokens(&pool, 1_000, 4_000, 1_100_000, 3_640_000, supply).unwrap();
+        assert_eq!(lp_amount, 1_818);
+        assert_eq!(
+            check_min_lp_out(lp_amount, min_lp_out).unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
     }
 
-    let mut x = n;
-    let mut y = (x + 1) / 2;
+    #[test]
+    fn single_sided_deposit_minting_nothing_fails_before_transfer() {
+        let mut pool = Tradium::default();
+
+        // Coin alone into a pool with outstanding LP mints min(coin, pc) = 0;
+        // the deposit prices this before transferring, so nothing moves
+        assert_eq!(
+            deposit_lp_amount(&mut pool, 1_000, 0, 1_000_000, 4_000_000, 2_000_000, 0).unwrap_err(),
+            TradiumError::InsufficientLiquidityMinted.into()
+        );
+        assert_eq!(
+            deposit_lp_amount(&mut pool, 0, 4_000, 1_000_000, 4_000_000, 2_000_000, 0).unwrap_err(),
+            TradiumError::InsufficientLiquidityMinted.into()
+        );
+
+        // Both sides mint, and min_lp_out is enforced at the same point
+        assert_eq!(
+            deposit_lp_amount(&mut pool, 1_000, 4_000, 1_000_000, 4_000_000, 2_000_000, 2_000)
+                .unwrap(),
+            2_000
+        );
+        assert_eq!(
+            deposit_lp_amount(&mut pool, 1_000, 4_000, 1_000_000, 4_000_000, 2_000_000, 2_001)
+                .unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+    }
 
-    while y < x {
-        x = y;
-        y = (x + n / x) / 2;
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn hooked_token_2022_lp_mint_is_minted_and_burned_without_hook_accounts() {
+        use crate::constants::{SPL_TOKEN_2022_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID};
+
+        let lp_mint = Pubkey::new_unique();
+        let hook_program_id = Pubkey::new_unique();
+        let mut lp_mint_account = TestAccount::new(
+            lp_mint,
+            SPL_TOKEN_2022_PROGRAM_ID,
+            shared::hooked_mint_data(hook_program_id),
+        );
+        let lp_mint_info = lp_mint_account.info();
+        assert!(shared::mint_has_transfer_hook(&lp_mint_info));
+
+        // The LP CPIs go to the program owning the LP mint
+        assert!(check_mint_token_program(lp_mint_info.owner, &SPL_TOKEN_2022_PROGRAM_ID).is_ok());
+        assert_eq!(
+            check_mint_token_program(lp_mint_info.owner, &SPL_TOKEN_PROGRAM_ID).unwrap_err(),
+            TradiumError::InvalidTokenProgram.into()
+        );
+
+        // Neither mint_to nor burn invokes the hook, so they take no extra accounts
+        let (pool, user, lp_account) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mint_to = spl_token_2022::instruction::mint_to(
+            &SPL_TOKEN_2022_PROGRAM_ID,
+            &lp_mint,
+            &lp_account,
+            &pool,
+            &[],
+            1_000,
+        )
+        .unwrap();
+        let burn = spl_token_2022::instruction::burn(
+            &SPL_TOKEN_2022_PROGRAM_ID,
+            &lp_account,
+            &lp_mint,
+            &user,
+            &[],
+            1_000,
+        )
+        .unwrap();
+        assert_eq!((mint_to.accounts.len(), burn.accounts.len()), (3, 3));
+        assert!(mint_to
+            .accounts
+            .iter()
+            .chain(&burn.accounts)
+            .all(|meta| meta.pubkey != hook_program_id));
     }
 
-    Ok(x)
+    #[test]
+    fn second_deposit_mints_under_the_pool_signature() {
+        let (coin_mint, pc_mint) =
+            pda::canonical_mint_order(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let (pool_key, bump) = pda::pool_pda(&coin_mint, &pc_mint);
+        let mut pool = Tradium {
+            coin_vault_mint: coin_mint,
+            pc_vault_mint: pc_mint,
+            bump,
+            nonce: [bump],
+            coin_decimals: 6,
+            pc_decimals: 6,
+            sys_decimal_value: 1_000_000,
+            ..Default::default()
+        };
+
+        let first_lp = calculate_lp_tokens(&pool, 1_000_000, 1_000_000, 0, 0, 0).unwrap();
+        let minted = lock_minimum_liquidity(&mut pool, first_lp).unwrap();
+        let supply = pool.claim_supply(minted).unwrap();
+        let second_lp =
+            calculate_lp_tokens(&pool, 500_000, 500_000, 1_000_000, 1_000_000, supply).unwrap();
+        assert_eq!(second_lp, 500_000);
+
+        // `initialize_pool` makes the pool the LP mint authority, so the mint
+        // CPI must sign with the pool's seeds; the old `mint_authority` seeds
+        // derive some other address and the mint would fail
+        assert_eq!(
+            Pubkey::create_program_address(&pool.signer_seeds(), &crate::ID).unwrap(),
+            pool_key
+        );
+        assert_ne!(
+            Pubkey::find_program_address(&[b"mint_authority", pool_key.as_ref()], &crate::ID).0,
+            pool_key
+        );
+    }
 }
@@ -0,0 +1,88 @@
+use crate::pda;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct FindPool<'info> {
+    /// CHECK: Must be the pair's canonical pool PDA, which may not exist yet;
+    /// checked in the handler
+    pub pool: UncheckedAccount<'info>,
+}
+
+/// The canonical pool address of a mint pair and whether a pool lives there.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PoolLookup {
+    pub pool: Pubkey,
+    pub initialized: bool,
+}
+
+/// Returns the canonical pool PDA for `mint_a`/`mint_b`, in either order, and
+/// whether it holds an initialized pool via return data, saving clients the
+/// account lookup.
+pub fn handle_find_pool(
+    ctx: Context<FindPool>,
+    mint_a: Pubkey,
+    mint_b: Pubkey,
+) -> Result<PoolLookup> {
+    lookup_pool(&ctx.accounts.pool, &mint_a, &mint_b)
+}
+
+fn lookup_pool(pool_info: &AccountInfo, mint_a: &Pubkey, mint_b: &Pubkey) -> Result<PoolLookup> {
+    // No pool can exist for a mint paired with itself
+    pda::check_distinct_mints(mint_a, mint_b)?;
+    let (pool, _) = pda::pool_pda(mint_a, mint_b);
+    require_keys_eq!(*pool_info.key, pool);
+
+    let initialized = *pool_info.owner == crate::ID
+        && pool_info
+            .try_borrow_data()?
+            .starts_with(Tradium::DISCRIMINATOR);
+
+    Ok(PoolLookup { pool, initialized })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::TestAccount;
+
+    fn lookup(
+        pool_key: &Pubkey,
+        owner: &Pubkey,
+        data: Vec<u8>,
+        mints: (Pubkey, Pubkey),
+    ) -> Result<PoolLookup> {
+        let mut pool_account = TestAccount::new(*pool_key, *owner, data);
+        lookup_pool(&pool_account.info(), &mints.0, &mints.1)
+    }
+
+    #[test]
+    fn existing_and_missing_pairs_are_told_apart() {
+        let mints = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (pool_key, _) = pda::pool_pda(&mints.0, &mints.1);
+
+        let mut pool_data = vec![];
+        Tradium::default().try_serialize(&mut pool_data).unwrap();
+        let existing = lookup(&pool_key, &crate::ID, pool_data, mints).unwrap();
+        assert_eq!(
+            existing,
+            PoolLookup {
+                pool: pool_key,
+                initialized: true
+            }
+        );
+
+        // An unused PDA is still a system account with no data
+        let missing = lookup(&pool_key, &System::id(), vec![], (mints.1, mints.0)).unwrap();
+        assert_eq!(
+            missing,
+            PoolLookup {
+                pool: pool_key,
+                initialized: false
+            }
+        );
+
+        // Some other account in the pool slot is refused
+        assert!(lookup(&Pubkey::new_unique(), &System::id(), vec![], mints).is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use crate::error::TradiumError;
+use crate::shared;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct FlashLoan<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    pub borrower: Signer<'info>,
+
+    /// The pool vault being borrowed from, either the coin or the pc vault
+    #[account(
+        mut,
+        constraint = vault.key() == pool.coin_vault || vault.key() == pool.pc_vault
+            @ TradiumError::InvalidFlashLoanVault
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = vault.mint)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Receives the loan; the receiver program must repay from it (or elsewhere)
+    #[account(mut, token::mint = mint)]
+    pub borrower_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: Invoked with the remaining accounts once the loan is transferred
+    #[account(executable)]
+    pub receiver_program: UncheckedAccount<'info>,
+
+    /// CHECK: Optional, only required if the mint has a transfer hook; validated in the handler
+    pub transfer_hook_program: Option<UncheckedAccount<'info>>,
+}
+
+/// Lends `amount` from one vault to the borrower, invokes the receiver program,
+/// and requires the vault to be repaid with the flash loan fee before returning.
+pub fn handle_flash_loan<'info>(
+    ctx: Context<'_, '_, 'info, 'info, FlashLoan<'info>>,
+    amount: u64,
+) -> Result<()> {
+    require!(amount > 0, TradiumError::InvalidInputAmount);
+
+    let pool = &ctx.accounts.pool;
+    require!(
+        pool.is_active(Clock::get()?.unix_timestamp),
+        TradiumError::InvalidPoolState
+    );
+    let is_coin_vault = ctx.accounts.vault.key() == pool.coin_vault;
+    let expected_token_program = if is_coin_vault {
+        pool.coin_token_program
+    } else {
+        pool.pc_token_program
+    };
+    require_keys_eq!(
+        ctx.accounts.token_program.key(),
+        expected_token_program,
+        TradiumError::InvalidTokenProgram
+    );
+
+    // Only the active reserves may be lent, never the fees owed to the owner
+    let fees_owed = if is_coin_vault {
+        pool.coin_fees_owed
+    } else {
+        pool.pc_fees_owed
+    };
+    let available = ctx
+        .accounts
+        .vault
+        .amount
+        .checked_sub(fees_owed)
+        .ok_or(TradiumError::InsufficientLiquidity)?;
+    require!(amount <= available, TradiumError::InsufficientLiquidity);
+
+    let fee = flash_loan_fee(
+        amount,
+        pool.fees.swap_fee_numerator,
+        pool.fees.swap_fee_denominator,
+    )?;
+
+    let hook = shared::resolve_pool_transfer_hook(
+        pool,
+        &ctx.accounts.mint.to_account_info(),
+        ctx.accounts.transfer_hook_program.as_ref(),
+    )?;
+
+    let vault_balance_before = ctx.accounts.vault.amount;
+
+    // Pool-signed transfer of the loan to the borrower
+    let pool_seeds = pool.signer_seeds();
+    let signer_seeds = &[&pool_seeds[..]];
+
+    shared::transfer_tokens_with_hook_support(
+        &ctx.accounts.token_program,
+        &ctx.accounts.vault,
+        &ctx.accounts.borrower_token_account,
+        &ctx.accounts.mint.to_account_info(),
+        &ctx.accounts.pool.to_account_info(),
+        hook,
+        ctx.accounts.transfer_hook_program.as_ref(),
+        ctx.remaining_accounts,
+        amount,
+        Some(signer_seeds),
+    )?;
+
+    // Lock the pool and persist the lock before the callback can observe it
+    lock_flash_loan(&mut ctx.accounts.pool)?;
+    ctx.accounts.pool.exit(&crate::ID)?;
+
+    // Hand control to the receiver, which must repay amount + fee to the vault
+    let receiver_ix = Instruction {
+        program_id: ctx.accounts.receiver_program.key(),
+        accounts: ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: [amount.to_le_bytes(), fee.to_le_bytes()].concat(),
+    };
+    invoke(&receiver_ix, ctx.remaining_accounts)?;
+
+    ctx.accounts.vault.reload()?;
+    let fee_captured = settle_flash_loan(
+        &mut ctx.accounts.pool,
+        is_coin_vault,
+        vault_balance_before,
+        ctx.accounts.vault.amount,
+        fee,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    emit!(FlashLoanEvent {
+        pool: ctx.accounts.pool.key(),
+        borrower: ctx.accounts.borrower.key(),
+        amount,
+        fee: fee_captured,
+        token: ctx.accounts.mint.key(),
+    });
+
+    msg!("Flash loan repaid: {} + {} fee", amount, fee_captured);
+
+    Ok(())
+}
+
+/// Marks a flash loan as outstanding, failing if one already is.
+fn lock_flash_loan(pool: &mut Tradium) -> Result<()> {
+    require!(!pool.flash_loan_locked, TradiumError::FlashLoanReentrancy);
+    pool.flash_loan_locked = true;
+    Ok(())
+}
+
+/// Flash loan fee at the pool's swap fee rate, rounded up so every loan pays
+/// something.
+fn flash_loan_fee(amount: u64, fee_numerator: u64, fee_denominator: u64) -> Result<u64> {
+    require!(fee_denominator > 0, TradiumError::InvalidFee);
+    let fee = (amount as u128)
+        .checked_mul(fee_numerator as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_add(fee_denominator as u128 - 1)
+        .ok_or(TradiumError::MathOverflow)?
+        .checked_div(fee_denominator as u128)
+        .ok_or(TradiumError::MathOverflow)?;
+    u64::try_from(fee).map_err(|_| TradiumError::MathOverflow.into())
+}
+
+/// Settles the loan once the receiver returns: checks the repayment, releases
+/// the lock and records the borrowed vault's balance. The other vault did not
+/// move and keeps its recorded balance. Returns the fee actually captured.
+fn settle_flash_loan(
+    pool: &mut Tradium,
+    is_coin_vault: bool,
+    balance_before: u64,
+    balance_after: u64,
+    fee: u64,
+    now: i64,
+) -> Result<u64> {
+    let fee_captured = check_repayment(balance_before, balance_after, fee)?;
+    pool.flash_loan_locked = false;
+    let (coin_vault_amount, pc_vault_amount) = if is_coin_vault {
+        (balance_after, pool.state_data.pool_pc_amount)
+    } else {
+        (pool.state_data.pool_coin_amount, balance_after)
+    };
+    pool.record_reserves(coin_vault_amount, pc_vault_amount);
+    pool.record_activity(now);
+    Ok(fee_captured)
+}
+
+/// Requires the vault to hold at least its pre-loan balance plus `fee`, and
+/// returns the fee actually captured.
+fn check_repayment(balance_before: u64, balance_after: u64, fee: u64) -> Result<u64> {
+    let required = balance_before
+        .checked_add(fee)
+        .ok_or(TradiumError::MathOverflow)?;
+    require!(balance_after >= required, TradiumError::FlashLoanNotRepaid);
+    Ok(balance_after - balance_before)
+}
+
+#[event]
+pub struct FlashLoanEvent {
+    pub pool: Pubkey,
+    pub borrower: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub token: Pubkey,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flash_loan_fee_rounds_up() {
+        assert_eq!(flash_loan_fee(1_000_000, 5, 10_000).unwrap(), 500);
+        assert_eq!(flash_loan_fee(1, 5, 10_000).unwrap(), 1);
+        assert_eq!(flash_loan_fee(1_000, 0, 10_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn unrepaid_flash_loan_is_rejected() {
+        assert_eq!(
+            check_repayment(1_000_000, 1_000_499, 500).unwrap_err(),
+            TradiumError::FlashLoanNotRepaid.into()
+        );
+        // Overpaying is allowed and reported as the captured fee
+        assert_eq!(check_repayment(1_000_000, 1_000_600, 500).unwrap(), 600);
+    }
+
+    #[test]
+    fn settlement_reports_the_captured_fee_and_unlocks() {
+        let mut pool = Tradium::default();
+        pool.record_reserves(1_000_000, 4_000_000);
+        lock_flash_loan(&mut pool).unwrap();
+
+        // 1_000_000 pc lent at 0.05%, repaid with 100 more than the fee
+        let fee = flash_loan_fee(1_000_000, 5, 10_000).unwrap();
+        let fee_captured =
+            settle_flash_loan(&mut pool, false, 4_000_000, 4_000_600, fee, 1_700_000_000).unwrap();
+        assert_eq!((fee, fee_captured), (500, 600));
+        assert!(!pool.flash_loan_locked);
+        // Only the borrowed vault's recorded balance moves
+        assert_eq!(
+            (
+                pool.state_data.pool_coin_amount,
+                pool.state_data.pool_pc_amount
+            ),
+            (1_000_000, 4_000_600)
+        );
+        assert_eq!(pool.last_activity_ts, 1_700_000_000);
+
+        // Short repayment leaves the loan, and the lock, in place
+        lock_flash_loan(&mut pool).unwrap();
+        assert_eq!(
+            settle_flash_loan(&mut pool, true, 1_000_000, 1_000_499, 500, 1_700_000_001)
+                .unwrap_err(),
+            TradiumError::FlashLoanNotRepaid.into()
+        );
+        assert!(pool.flash_loan_locked);
+    }
+
+    #[test]
+    fn nested_flash_loan_is_rejected() {
+        let mut pool = Tradium::default();
+        lock_flash_loan(&mut pool).unwrap();
+
+        // A callback re-entering flash_loan sees the persisted lock
+        assert_eq!(
+            lock_flash_loan(&mut pool).unwrap_err(),
+            TradiumError::FlashLoanReentrancy.into()
+        );
+
+        // Once repaid the lock is cleared and the next loan may proceed
+        pool.flash_loan_locked = false;
+        assert!(lock_flash_loan(&mut pool).is_ok());
+    }
+}
@@ -0,0 +1,24 @@
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetActivity<'info> {
+    pub pool: Account<'info, Tradium>,
+}
+
+/// Pool age and last activity, as unix timestamps.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct PoolActivity {
+    pub created_at: i64,
+    pub last_activity_ts: i64,
+}
+
+/// Returns the pool's creation and last-activity timestamps via return data,
+/// so keepers can find dormant pools.
+pub fn handle_get_activity(ctx: Context<GetActivity>) -> Result<PoolActivity> {
+    let pool = &ctx.accounts.pool;
+    Ok(PoolActivity {
+        created_at: pool.created_at,
+        last_activity_ts: pool.last_activity_ts,
+    })
+}
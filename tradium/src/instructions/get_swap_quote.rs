@@ -0,0 +1,241 @@
+use crate::error::TradiumError;
+use crate::instructions::{
+    auto_compound_before_swap, swap_preflight_with_transfer_fees, TransferFeeAdjustedQuote,
+};
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+/// Minimal, read-only account set: everything is deserialized and validated
+/// in the handler so clients only need the five addresses.
+#[derive(Accounts)]
+pub struct GetSwapQuote<'info> {
+    /// CHECK: Owner and discriminator are checked in the handler
+    pub pool: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the pool's coin vault; checked in the handler
+    pub coin_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the pool's pc vault; checked in the handler
+    pub pc_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the pool's coin mint; checked in the handler and only
+    /// read for a Token-2022 transfer fee
+    pub coin_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Must be the pool's pc mint; checked in the handler and only
+    /// read for a Token-2022 transfer fee
+    pub pc_mint: UncheckedAccount<'info>,
+}
+
+/// Returns the quote for swapping `amount_in` at the current reserves via
+/// return data, net of both mints' transfer fees as the swap executes it,
+/// failing with the error the swap itself would hit.
+pub fn handle_get_swap_quote(
+    ctx: Context<GetSwapQuote>,
+    amount_in: u64,
+    swap_direction: u8,
+) -> Result<TransferFeeAdjustedQuote> {
+    quote_from_accounts(
+        &ctx.accounts.pool,
+        &ctx.accounts.coin_vault,
+        &ctx.accounts.pc_vault,
+        &ctx.accounts.coin_mint,
+        &ctx.accounts.pc_mint,
+        amount_in,
+        swap_direction,
+        Clock::get()?.epoch,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn quote_from_accounts<'info>(
+    pool_info: &AccountInfo,
+    coin_vault_info: &AccountInfo,
+    pc_vault_info: &AccountInfo,
+    coin_mint_info: &AccountInfo<'info>,
+    pc_mint_info: &AccountInfo<'info>,
+    amount_in: u64,
+    swap_direction: u8,
+    epoch: u64,
+) -> Result<TransferFeeAdjustedQuote> {
+    require_keys_eq!(*pool_info.owner, crate::ID, TradiumError::InvalidPoolState);
+    let mut pool = Tradium::try_deserialize(&mut &pool_info.try_borrow_data()?[..])?;
+    // Quote against the reserves the swap would compound into first
+    auto_compound_before_swap(&mut pool);
+
+    let coin_vault_amount =
+        read_vault_amount(coin_vault_info, &pool.coin_vault, &pool.coin_token_program)
+            .map_err(|_| TradiumError::InvalidCoinVault)?;
+    let pc_vault_amount = read_vault_amount(pc_vault_info, &pool.pc_vault, &pool.pc_token_program)
+        .map_err(|_| TradiumError::InvalidPcVault)?;
+
+    // The mints decide the transfer fees, so they must be the pool's own
+    require_keys_eq!(
+        *coin_mint_info.key,
+        pool.coin_vault_mint,
+        TradiumError::InvalidCoinMint
+    );
+    require_keys_eq!(
+        *pc_mint_info.key,
+        pool.pc_vault_mint,
+        TradiumError::InvalidPcMint
+    );
+    let (input_mint, output_mint) = if swap_direction == 0 {
+        (coin_mint_info, pc_mint_info)
+    } else {
+        (pc_mint_info, coin_mint_info)
+    };
+
+    swap_preflight_with_transfer_fees(
+        &pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        amount_in,
+        0,
+        swap_direction,
+        input_mint,
+        output_mint,
+        epoch,
+    )
+}
+
+/// Balance of `vault_info`, which must be the pool's vault at `expected_key`
+/// owned by the pool's token program for that side.
+fn read_vault_amount(
+    vault_info: &AccountInfo,
+    expected_key: &Pubkey,
+    expected_token_program: &Pubkey,
+) -> Result<u64> {
+    require_keys_eq!(*vault_info.key, *expected_key);
+    require_keys_eq!(*vault_info.owner, *expected_token_program);
+    let vault = TokenAccount::try_deserialize(&mut &vault_info.try_borrow_data()?[..])?;
+    Ok(vault.amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::TestAccount;
+    use anchor_lang::solana_program::program_pack::Pack;
+
+    fn token_account_data(amount: u64) -> Vec<u8> {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        data
+    }
+
+    #[test]
+    fn quote_via_lightweight_accounts() {
+        let mut pool = Tradium {
+            coin_vault: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+            coin_vault_mint: Pubkey::new_unique(),
+            pc_vault_mint: Pubkey::new_unique(),
+            coin_token_program: spl_token::ID,
+            pc_token_program: spl_token::ID,
+            ..Default::default()
+        };
+        pool.fees.swap_fee_numerator = 5;
+        pool.fees.swap_fee_denominator = 10_000;
+
+        let pool_key = Pubkey::new_unique();
+        let program_id = crate::ID;
+        let mut pool_data = vec![];
+        pool.try_serialize(&mut pool_data).unwrap();
+        let mut pool_account = TestAccount::new(pool_key, program_id, pool_data);
+        let pool_info = pool_account.info();
+
+        let token_program = spl_token::ID;
+        let mut coin_vault_account = TestAccount::new(
+            pool.coin_vault,
+            token_program,
+            token_account_data(1_000_000),
+        );
+        let coin_vault_info = coin_vault_account.info();
+        let mut pc_vault_account =
+            TestAccount::new(pool.pc_vault, token_program, token_account_data(1_000_000));
+        let pc_vault_info = pc_vault_account.info();
+
+        let mut coin_mint_account = TestAccount::new(pool.coin_vault_mint, token_program, vec![]);
+        let coin_mint_info = coin_mint_account.info();
+        let mut pc_mint_account = TestAccount::new(pool.pc_vault_mint, token_program, vec![]);
+        let pc_mint_info = pc_mint_account.info();
+
+        let quote = quote_from_accounts(
+            &pool_info,
+            &coin_vault_info,
+            &pc_vault_info,
+            &coin_mint_info,
+            &pc_mint_info,
+            10_000,
+            0,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote.amount_out, 9_896);
+        // SPL mints charge no transfer fee
+        assert_eq!(quote.amount_received, quote.amount_out);
+
+        // Vaults passed in the wrong slots are not the pool's
+        assert_eq!(
+            quote_from_accounts(
+                &pool_info,
+                &pc_vault_info,
+                &coin_vault_info,
+                &coin_mint_info,
+                &pc_mint_info,
+                10_000,
+                0,
+                0,
+            )
+            .unwrap_err(),
+            TradiumError::InvalidCoinVault.into()
+        );
+
+        // Nor are mints, whose transfer fees would be applied to the quote
+        assert_eq!(
+            quote_from_accounts(
+                &pool_info,
+                &coin_vault_info,
+                &pc_vault_info,
+                &pc_mint_info,
+                &coin_mint_info,
+                10_000,
+                0,
+                0,
+            )
+            .unwrap_err(),
+            TradiumError::InvalidCoinMint.into()
+        );
+
+        // A quote above the pool's output cap fails as the swap would
+        for (cap, within) in [(9_896, true), (9_895, false)] {
+            pool.max_output_per_swap_pc = cap;
+            pool.try_serialize(&mut &mut pool_info.try_borrow_mut_data().unwrap()[..])
+                .unwrap();
+            let result = quote_from_accounts(
+                &pool_info,
+                &coin_vault_info,
+                &pc_vault_info,
+                &coin_mint_info,
+                &pc_mint_info,
+                10_000,
+                0,
+                0,
+            );
+            if within {
+                assert_eq!(result.unwrap().amount_out, cap);
+            } else {
+                assert_eq!(result.unwrap_err(), TradiumError::OutputCapExceeded.into());
+            }
+        }
+    }
+}
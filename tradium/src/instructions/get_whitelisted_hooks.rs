@@ -0,0 +1,12 @@
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct GetWhitelistedHooks<'info> {
+    pub pool: Account<'info, Tradium>,
+}
+
+/// Returns the active whitelisted transfer hook programs via return data.
+pub fn handle_get_whitelisted_hooks(ctx: Context<GetWhitelistedHooks>) -> Result<Vec<Pubkey>> {
+    Ok(ctx.accounts.pool.active_whitelisted_hooks().to_vec())
+}
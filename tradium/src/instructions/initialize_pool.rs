@@ -1,10 +1,16 @@
 use crate::constants::*;
+use crate::curve::SwapCurve;
 use crate::error::TradiumError;
+use crate::pda;
+use crate::shared;
 use crate::state::*;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
 use anchor_lang::solana_program::program::invoke_signed;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::{Mint, Token};
+use anchor_spl::token_interface::{self, TokenAccount as TokenInterfaceAccount};
 use spl_token::instruction as spl_token_instruction;
+#[cfg(feature = "token-2022")]
 use spl_token_2022::instruction as spl_token_2022_instruction;
 
 #[derive(Accounts)]
@@ -16,13 +22,29 @@ pub struct InitializePool<'info> {
         init,
         payer = payer,
         space = 8 + Tradium::INIT_SPACE, // 8 bytes for discriminator + AnchorSize generated size
-        seeds = [POOL_SEED, coin_mint.key().as_ref(), pc_mint.key().as_ref()],
+        seeds = [
+            POOL_SEED,
+            pda::canonical_mint_order(&coin_mint.key(), &pc_mint.key()).0.as_ref(),
+            pda::canonical_mint_order(&coin_mint.key(), &pc_mint.key()).1.as_ref(),
+        ],
         bump
     )]
     pub pool: Account<'info, Tradium>,
 
-    pub coin_mint: Account<'info, Mint>,
-    pub pc_mint: Account<'info, Mint>,
+    // Neither input mint may be the LP mint PDA, or the LP mint `init` below would collide
+    #[account(
+        constraint = !pda::is_canonical_lp_mint(&pool.key(), &coin_mint.key())
+            @ TradiumError::MintCollidesWithLpMint
+    )]
+    pub coin_mint: InterfaceAccount<'info, token_interface::Mint>,
+    // Checked here as well as in the handler: with one mint on both sides the
+    // vault `init`s below would collide before the handler ever ran
+    #[account(
+        constraint = !pda::is_canonical_lp_mint(&pool.key(), &pc_mint.key())
+            @ TradiumError::MintCollidesWithLpMint,
+        constraint = pc_mint.key() != coin_mint.key() @ TradiumError::DuplicateMint
+    )]
+    pub pc_mint: InterfaceAccount<'info, token_interface::Mint>,
 
     #[account(
         init,
@@ -31,6 +53,7 @@ pub struct InitializePool<'info> {
         bump,
         mint::decimals = 6,
         mint::authority = pool,
+        mint::freeze_authority = pool, // Used only by soulbound-LP pools
     )]
     pub lp_mint: Account<'info, Mint>,
 
@@ -65,11 +88,26 @@ pub struct InitializePool<'info> {
     pub rent: Sysvar<'info, Rent>,
 }
 
-pub fn initialize_pool(
+#[allow(clippy::too_many_arguments)]
+pub fn handle_initialize_pool(
     ctx: Context<InitializePool>,
     _initial_coin_amount: u64, // Prefixed with underscore to indicate intentionally unused
     _initial_pc_amount: u64,   // Prefixed with underscore to indicate intentionally unused
+    bootstrap_swaps: u64,
+    bootstrap_fee_numerator: u64,
+    soulbound_lp: bool,
+    owner: Option<Pubkey>,
+    fee_recipient: Option<Pubkey>,
+    curve_type: u8,
+    amp_coefficient: u64,
+    liquidity_lock: Option<u64>,
 ) -> Result<()> {
+    // The bootstrap fee may only reduce the default swap fee
+    require!(
+        bootstrap_fee_numerator <= DEFAULT_OWNER_FEE,
+        TradiumError::InvalidFee
+    );
+
     let pool = &mut ctx.accounts.pool;
     let coin_program_id = ctx.accounts.coin_token_program.key();
     let pc_program_id = ctx.accounts.pc_token_program.key();
@@ -82,6 +120,13 @@ pub fn initialize_pool(
         return Err(TradiumError::InvalidTokenProgram.into());
     }
 
+    // Token-2022 pools need the extension handling compiled in
+    #[cfg(not(feature = "token-2022"))]
+    require!(
+        coin_program_id != SPL_TOKEN_2022_PROGRAM_ID && pc_program_id != SPL_TOKEN_2022_PROGRAM_ID,
+        TradiumError::InvalidTokenProgram
+    );
+
     // Access the bumps that Anchor automatically derives
     let pool_bump = ctx.bumps.pool;
     let _coin_vault_bump = ctx.bumps.coin_vault; // Prefixed with underscore
@@ -89,8 +134,8 @@ pub fn initialize_pool(
 
     // Get keys before borrowing pool mutably
     let _pool_key = pool.key(); // Prefixed with underscore
-    let coin_mint_key = ctx.accounts.coin_mint.key();
-    let pc_mint_key = ctx.accounts.pc_mint.key();
+    let (coin_mint_key, pc_mint_key) =
+        pda::canonical_mint_order(&ctx.accounts.coin_mint.key(), &ctx.accounts.pc_mint.key());
 
     // Generate signer seeds for pool (authority for vaults)
     let pool_seeds = &[
@@ -101,24 +146,12 @@ pub fn initialize_pool(
     ];
     let pool_signer = &[&pool_seeds[..]];
 
-    // FIXED: Use correct token program instruction based on coin mint type
-    let init_coin_vault_ix = if coin_program_id == SPL_TOKEN_2022_PROGRAM_ID {
-        // Use Token-2022 instruction for Token-2022 mints
-        spl_token_2022_instruction::initialize_account(
-            &coin_program_id,
-            ctx.accounts.coin_vault.key,
-            &ctx.accounts.coin_mint.key(),
-            &pool.key(),
-        )?
-    } else {
-        // Use standard SPL Token instruction for standard mints
-        spl_token_instruction::initialize_account(
-            &coin_program_id,
-            ctx.accounts.coin_vault.key,
-            &ctx.accounts.coin_mint.key(),
-            &pool.key(),
-        )?
-    };
+    let init_coin_vault_ix = initialize_vault_ix(
+        &coin_program_id,
+        ctx.accounts.coin_vault.key,
+        &ctx.accounts.coin_mint.key(),
+        &pool.key(),
+    )?;
 
     // Initialize coin vault with correct instruction
     invoke_signed(
@@ -133,24 +166,12 @@ pub fn initialize_pool(
         pool_signer,
     )?;
 
-    // FIXED: Use correct token program instruction based on pc mint type
-    let init_pc_vault_ix = if pc_program_id == SPL_TOKEN_2022_PROGRAM_ID {
-        // Use Token-2022 instruction for Token-2022 mints
-        spl_token_2022_instruction::initialize_account(
-            &pc_program_id,
-            ctx.accounts.pc_vault.key,
-            &ctx.accounts.pc_mint.key(),
-            &pool.key(),
-        )?
-    } else {
-        // Use standard SPL Token instruction for standard mints
-        spl_token_instruction::initialize_account(
-            &pc_program_id,
-            ctx.accounts.pc_vault.key,
-            &ctx.accounts.pc_mint.key(),
-            &pool.key(),
-        )?
-    };
+    let init_pc_vault_ix = initialize_vault_ix(
+        &pc_program_id,
+        ctx.accounts.pc_vault.key,
+        &ctx.accounts.pc_mint.key(),
+        &pool.key(),
+    )?;
 
     // Initialize pc vault with correct instruction
     invoke_signed(
@@ -165,22 +186,73 @@ pub fn initialize_pool(
         pool_signer,
     )?;
 
+    check_vault_initialized(
+        &ctx.accounts.coin_vault.to_account_info(),
+        &pool.key(),
+        &ctx.accounts.coin_mint.key(),
+    )?;
+    check_vault_initialized(
+        &ctx.accounts.pc_vault.to_account_info(),
+        &pool.key(),
+        &ctx.accounts.pc_mint.key(),
+    )?;
+
+    // Pool transfers out of the vaults are CPIs; a locked CpiGuard would block them
+    require!(
+        !shared::token_account_has_cpi_guard(&ctx.accounts.coin_vault.to_account_info())
+            && !shared::token_account_has_cpi_guard(&ctx.accounts.pc_vault.to_account_info()),
+        TradiumError::VaultCpiGuardEnabled
+    );
+
+    // Store the pair in canonical order, swapping the coin/pc roles if the
+    // caller passed the mints the other way round
+    let passed_coin = PoolSide {
+        mint: ctx.accounts.coin_mint.key(),
+        vault: ctx.accounts.coin_vault.key(),
+        token_program: coin_program_id,
+        decimals: ctx.accounts.coin_mint.decimals,
+    };
+    let passed_pc = PoolSide {
+        mint: ctx.accounts.pc_mint.key(),
+        vault: ctx.accounts.pc_vault.key(),
+        token_program: pc_program_id,
+        decimals: ctx.accounts.pc_mint.decimals,
+    };
+    check_distinct_sides(&passed_coin, &passed_pc)?;
+    let mints_reversed = passed_coin.mint != coin_mint_key;
+    let (coin, pc) = if mints_reversed {
+        (passed_pc, passed_coin)
+    } else {
+        (passed_coin, passed_pc)
+    };
+
+    check_curve_config(curve_type, amp_coefficient, coin.decimals, pc.decimals)?;
+    let liquidity_lock = resolve_liquidity_lock(liquidity_lock)?;
+
     // Initialize the pool state
-    pool.status = 1; // Active
+    pool.status = POOL_STATUS_ACTIVE;
+    let payer = ctx.accounts.payer.key();
+    assign_governance(
+        pool,
+        governance_key(owner, payer)?,
+        governance_key(fee_recipient, payer)?,
+    );
+    pool.bump = pool_bump;
     pool.nonce = [pool_bump];
-    pool.coin_decimals = ctx.accounts.coin_mint.decimals as u64;
-    pool.pc_decimals = ctx.accounts.pc_mint.decimals as u64;
+    pool.mints_reversed = mints_reversed;
+    pool.coin_decimals = coin.decimals as u64;
+    pool.pc_decimals = pc.decimals as u64;
 
     // Set mint and vault addresses
-    pool.coin_vault_mint = ctx.accounts.coin_mint.key();
-    pool.pc_vault_mint = ctx.accounts.pc_mint.key();
+    pool.coin_vault_mint = coin.mint;
+    pool.pc_vault_mint = pc.mint;
     pool.lp_mint = ctx.accounts.lp_mint.key();
-    pool.coin_vault = ctx.accounts.coin_vault.key();
-    pool.pc_vault = ctx.accounts.pc_vault.key();
+    pool.coin_vault = coin.vault;
+    pool.pc_vault = pc.vault;
 
     // Set the program IDs
-    pool.coin_token_program = coin_program_id;
-    pool.pc_token_program = pc_program_id;
+    pool.coin_token_program = coin.token_program;
+    pool.pc_token_program = pc.token_program;
 
     // Initialize fee with default values
     pool.fees.trade_fee_numerator = DEFAULT_TRADE_FEE;
@@ -188,20 +260,290 @@ pub fn initialize_pool(
     pool.fees.swap_fee_numerator = DEFAULT_OWNER_FEE;
     pool.fees.swap_fee_denominator = FEE_DENOMINATOR;
 
+    // Reduced fee for the first swaps of the pool
+    pool.bootstrap_swaps_remaining = bootstrap_swaps;
+    pool.bootstrap_fee_numerator = bootstrap_fee_numerator;
+
+    // Pricing curve, fixed for the life of the pool
+    pool.curve_type = curve_type;
+    pool.amp_coefficient = amp_coefficient;
+
+    // LP the first deposit locks, checked against that deposit when it lands
+    pool.liquidity_lock = liquidity_lock;
+
+    // Non-transferable LP: the pool freezes every LP account it mints into
+    pool.soulbound_lp = soulbound_lp;
+
     // Initialize whitelisted transfer hooks (empty by default)
     pool.whitelisted_transfer_hooks = [Pubkey::default(); crate::constants::MAX_WHITELISTED_HOOKS];
     pool.num_whitelisted_hooks = 0;
 
+    // Probe both mints once so pools without hooked mints can skip hook validation
+    pool.has_any_hook_mints = shared::pool_mints_have_hook(
+        &ctx.accounts.coin_mint.to_account_info(),
+        &ctx.accounts.pc_mint.to_account_info(),
+    );
+
+    let now = Clock::get()?.unix_timestamp;
+    pool.created_at = now;
+    pool.last_activity_ts = now;
+
     // Set initialization flag
     pool.state_data.initialized = true;
 
     msg!("Pool initialized successfully");
-    msg!("Coin mint: {}", ctx.accounts.coin_mint.key());
-    msg!("PC mint: {}", ctx.accounts.pc_mint.key());
+    msg!("Coin mint: {}", pool.coin_vault_mint);
+    msg!("PC mint: {}", pool.pc_vault_mint);
     msg!("LP mint: {}", ctx.accounts.lp_mint.key());
-    msg!("Coin vault: {}", ctx.accounts.coin_vault.key());
-    msg!("PC vault: {}", ctx.accounts.pc_vault.key());
+    msg!("Coin vault: {}", pool.coin_vault);
+    msg!("PC vault: {}", pool.pc_vault);
+    if mints_reversed {
+        msg!("Mints were passed in reverse order; coin/pc roles swapped");
+    }
     msg!("Allocated space: {} bytes", 8 + Tradium::INIT_SPACE);
 
     Ok(())
 }
+
+/// Every owner-gated instruction authorizes against `amm_owner`; left at the
+/// default key, none of them could ever be called.
+fn assign_governance(pool: &mut Tradium, owner: Pubkey, fee_recipient: Pubkey) {
+    pool.amm_owner = owner;
+    pool.fee_recipient = fee_recipient;
+}
+
+/// An owner or fee recipient passed to `initialize_pool`, defaulting to the payer.
+fn governance_key(key: Option<Pubkey>, payer: Pubkey) -> Result<Pubkey> {
+    let key = key.unwrap_or(payer);
+    require!(key != Pubkey::default(), TradiumError::InvalidGovernanceKey);
+    Ok(key)
+}
+
+/// Validates the pool's curve choice. StableSwap prices raw amounts close to
+/// one for one near balance, so a stable pair must share its decimals.
+fn check_curve_config(
+    curve_type: u8,
+    amp_coefficient: u64,
+    coin_decimals: u8,
+    pc_decimals: u8,
+) -> Result<()> {
+    if let SwapCurve::Stable { .. } = SwapCurve::from_config(curve_type, amp_coefficient)? {
+        require!(
+            coin_decimals == pc_decimals,
+            TradiumError::InvalidCurveConfig
+        );
+    }
+    Ok(())
+}
+
+/// The LP lock passed to `initialize_pool`, defaulting to `MIN_LIQUIDITY`. A
+/// zero lock would reopen the first-deposit inflation attack.
+fn resolve_liquidity_lock(liquidity_lock: Option<u64>) -> Result<u64> {
+    let liquidity_lock = liquidity_lock.unwrap_or(MIN_LIQUIDITY);
+    require!(liquidity_lock > 0, TradiumError::InvalidLiquidityLock);
+    Ok(liquidity_lock)
+}
+
+/// One side of the pair as passed to `initialize_pool`.
+struct PoolSide {
+    mint: Pubkey,
+    vault: Pubkey,
+    token_program: Pubkey,
+    decimals: u8,
+}
+
+/// A pool pairs two different mints held in two different vaults; with either
+/// shared, both sides would be one token and one balance.
+fn check_distinct_sides(coin: &PoolSide, pc: &PoolSide) -> Result<()> {
+    pda::check_distinct_mints(&coin.mint, &pc.mint)?;
+    require_keys_neq!(coin.vault, pc.vault, TradiumError::InvalidPcVault);
+    Ok(())
+}
+
+/// `initialize_account` instruction for a vault owned by `token_program_id`.
+fn initialize_vault_ix(
+    token_program_id: &Pubkey,
+    vault: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Result<Instruction> {
+    // Use Token-2022 instruction for Token-2022 mints
+    #[cfg(feature = "token-2022")]
+    {
+        if *token_program_id == SPL_TOKEN_2022_PROGRAM_ID {
+            return Ok(spl_token_2022_instruction::initialize_account(
+                token_program_id,
+                vault,
+                mint,
+                owner,
+            )?);
+        }
+    }
+
+    // Use standard SPL Token instruction for standard mints
+    Ok(spl_token_instruction::initialize_account(
+        token_program_id,
+        vault,
+        mint,
+        owner,
+    )?)
+}
+
+/// After `initialize_account`, the vault must be a token account for `mint`
+/// owned by the pool.
+fn check_vault_initialized(vault: &AccountInfo, pool_key: &Pubkey, mint: &Pubkey) -> Result<()> {
+    let account = TokenInterfaceAccount::try_deserialize(&mut &vault.try_borrow_data()?[..])?;
+    require!(
+        account.owner == *pool_key && account.mint == *mint,
+        TradiumError::VaultInitMismatch
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::TestAccount;
+
+    #[test]
+    fn vault_must_be_the_pools_account_for_its_mint() {
+        use anchor_lang::solana_program::program_pack::Pack;
+
+        let (pool_key, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let vault_data = |owner, mint| {
+            let mut data = vec![0u8; spl_token::state::Account::LEN];
+            spl_token::state::Account {
+                mint,
+                owner,
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+
+        let mut vault_account = TestAccount::new(
+            Pubkey::new_unique(),
+            SPL_TOKEN_PROGRAM_ID,
+            vault_data(pool_key, mint),
+        );
+        assert!(check_vault_initialized(&vault_account.info(), &pool_key, &mint).is_ok());
+
+        for (owner, vault_mint) in [
+            (Pubkey::new_unique(), mint),
+            (pool_key, Pubkey::new_unique()),
+        ] {
+            let mut vault_account = TestAccount::new(
+                Pubkey::new_unique(),
+                SPL_TOKEN_PROGRAM_ID,
+                vault_data(owner, vault_mint),
+            );
+            assert_eq!(
+                check_vault_initialized(&vault_account.info(), &pool_key, &mint).unwrap_err(),
+                TradiumError::VaultInitMismatch.into()
+            );
+        }
+    }
+
+    #[test]
+    fn payer_owns_the_pool_and_other_signers_are_rejected() {
+        use std::collections::BTreeSet;
+
+        let payer = Pubkey::new_unique();
+        let mut pool = Tradium::default();
+        assign_governance(
+            &mut pool,
+            governance_key(None, payer).unwrap(),
+            governance_key(None, payer).unwrap(),
+        );
+        assert_eq!(pool.amm_owner, payer);
+
+        let pool_key = Pubkey::new_unique();
+        let program_id = crate::ID;
+        let mut pool_data = vec![];
+        pool.try_serialize(&mut pool_data).unwrap();
+
+        // An owner-gated admin instruction, signed by the payer and by a stranger
+        for (signer, authorized) in [(payer, true), (Pubkey::new_unique(), false)] {
+            let mut pool_account = TestAccount::new(pool_key, program_id, pool_data.clone());
+            let mut signer_account = TestAccount::signer(signer);
+            let infos = [pool_account.info(), signer_account.info()];
+            let mut accounts = &infos[..];
+            let result = crate::instructions::SetFees::try_accounts(
+                &program_id,
+                &mut accounts,
+                &[],
+                &mut Default::default(),
+                &mut BTreeSet::new(),
+            );
+            if authorized {
+                assert!(result.is_ok());
+            } else {
+                assert_eq!(result.err(), Some(TradiumError::Unauthorized.into()));
+            }
+        }
+    }
+
+    #[test]
+    fn owner_and_fee_recipient_are_set_at_init() {
+        let payer = Pubkey::new_unique();
+        let (owner, fee_recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut pool = Tradium::default();
+        assign_governance(
+            &mut pool,
+            governance_key(Some(owner), payer).unwrap(),
+            governance_key(Some(fee_recipient), payer).unwrap(),
+        );
+        assert_eq!(pool.amm_owner, owner);
+        assert_eq!(pool.fee_recipient, fee_recipient);
+        assert_eq!(pool.protocol_fee_recipient(), fee_recipient);
+
+        assert_eq!(
+            governance_key(Some(Pubkey::default()), payer).unwrap_err(),
+            TradiumError::InvalidGovernanceKey.into()
+        );
+    }
+
+    #[test]
+    fn stable_pools_need_a_valid_amp_and_matching_decimals() {
+        assert!(check_curve_config(CURVE_TYPE_CONSTANT_PRODUCT, 0, 9, 6).is_ok());
+        assert!(check_curve_config(CURVE_TYPE_STABLE, 100, 6, 6).is_ok());
+        for (curve_type, amp, coin_decimals) in [
+            (CURVE_TYPE_STABLE, 100, 9),
+            (CURVE_TYPE_STABLE, 0, 6),
+            (2, 100, 6),
+        ] {
+            assert_eq!(
+                check_curve_config(curve_type, amp, coin_decimals, 6).unwrap_err(),
+                TradiumError::InvalidCurveConfig.into()
+            );
+        }
+    }
+
+    #[test]
+    fn identical_mints_or_vaults_are_rejected() {
+        let side = |mint: Pubkey, vault: Pubkey| PoolSide {
+            mint,
+            vault,
+            token_program: SPL_TOKEN_PROGRAM_ID,
+            decimals: 6,
+        };
+        let (mint, vault) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(check_distinct_sides(
+            &side(mint, vault),
+            &side(Pubkey::new_unique(), Pubkey::new_unique())
+        )
+        .is_ok());
+        assert_eq!(
+            check_distinct_sides(&side(mint, vault), &side(mint, Pubkey::new_unique()))
+                .unwrap_err(),
+            TradiumError::DuplicateMint.into()
+        );
+        assert_eq!(
+            check_distinct_sides(&side(mint, vault), &side(Pubkey::new_unique(), vault))
+                .unwrap_err(),
+            TradiumError::InvalidPcVault.into()
+        );
+    }
+}
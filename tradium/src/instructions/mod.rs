@@ -1,11 +1,34 @@
 pub mod deposit;
+pub mod find_pool;
+pub mod flash_loan;
+pub mod get_activity;
+pub mod get_swap_quote;
+pub mod get_whitelisted_hooks;
 pub mod initialize_pool;
+pub mod quote_swap;
+pub mod reconcile_lp_amount;
 pub mod shared;
 pub mod swap;
+pub mod swap_route;
+pub mod sync;
+pub mod verify_invariants;
 pub mod withdraw;
 
 pub use deposit::*;
+pub use find_pool::*;
+pub use flash_loan::*;
+pub use get_activity::*;
+pub use get_swap_quote::*;
+pub use get_whitelisted_hooks::*;
 pub use initialize_pool::*;
+pub use quote_swap::*;
+pub use reconcile_lp_amount::*;
 pub use shared::*;
 pub use swap::*;
+pub use swap_route::*;
+pub use sync::*;
+pub use verify_invariants::*;
 pub use withdraw::*;
+
+pub mod admin;
+pub use admin::*;
@@ -0,0 +1,216 @@
+use crate::instructions::swap::{swap_preflight_with_transfer_fees, TransferFeeAdjustedQuote};
+use crate::state::{Fees, Tradium};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct QuoteSwap<'info> {
+    /// CHECK: Only read for a Token-2022 transfer fee; any other account charges none
+    pub coin_mint: UncheckedAccount<'info>,
+
+    /// CHECK: Only read for a Token-2022 transfer fee; any other account charges none
+    pub pc_mint: UncheckedAccount<'info>,
+}
+
+/// Quotes swapping `amount_in` against the given vault balances, fee config
+/// and curve (`curve_type` and `amp_coefficient` as stored on a pool) without
+/// any pool account, via return data, so clients can simulate arbitrary
+/// states. The transfer fee of each mint is applied.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_quote_swap(
+    ctx: Context<QuoteSwap>,
+    amount_in: u64,
+    swap_direction: u8,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    fees: Fees,
+    curve_type: u8,
+    amp_coefficient: u64,
+) -> Result<TransferFeeAdjustedQuote> {
+    let (input_mint, output_mint) = if swap_direction == 0 {
+        (&ctx.accounts.coin_mint, &ctx.accounts.pc_mint)
+    } else {
+        (&ctx.accounts.pc_mint, &ctx.accounts.coin_mint)
+    };
+    quote_with_fees(
+        fees,
+        curve_type,
+        amp_coefficient,
+        coin_vault_amount,
+        pc_vault_amount,
+        amount_in,
+        swap_direction,
+        input_mint,
+        output_mint,
+        Clock::get()?.epoch,
+    )
+}
+
+/// The quote a swap would execute with: the vault balances are taken as the
+/// active reserves, and the pricing is the swap's own preflight on the
+/// pool's own curve dispatch, so an invalid curve fails as the pool would.
+#[allow(clippy::too_many_arguments)]
+fn quote_with_fees(
+    fees: Fees,
+    curve_type: u8,
+    amp_coefficient: u64,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_in: u64,
+    swap_direction: u8,
+    input_mint: &AccountInfo,
+    output_mint: &AccountInfo,
+    epoch: u64,
+) -> Result<TransferFeeAdjustedQuote> {
+    let pool = Tradium {
+        fees,
+        curve_type,
+        amp_coefficient,
+        ..Default::default()
+    };
+    swap_preflight_with_transfer_fees(
+        &pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        amount_in,
+        0,
+        swap_direction,
+        input_mint,
+        output_mint,
+        epoch,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CURVE_TYPE_CONSTANT_PRODUCT, CURVE_TYPE_STABLE};
+    use crate::error::TradiumError;
+    use crate::instructions::swap::quote_amount_out;
+    use crate::shared::TestAccount;
+
+    fn swap_fees() -> Fees {
+        Fees {
+            swap_fee_numerator: 25,
+            swap_fee_denominator: 10_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn quote_matches_the_swap_pricing() {
+        let spl_token = spl_token::ID;
+        let (coin_key, pc_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut coin_mint_account = TestAccount::new(coin_key, spl_token, vec![]);
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account = TestAccount::new(pc_key, spl_token, vec![]);
+        let pc_mint = pc_mint_account.info();
+
+        let quote = quote_with_fees(
+            swap_fees(),
+            CURVE_TYPE_CONSTANT_PRODUCT,
+            0,
+            2_000_000,
+            5_000_000,
+            100_000,
+            0,
+            &coin_mint,
+            &pc_mint,
+            0,
+        )
+        .unwrap();
+        let pool = Tradium {
+            fees: swap_fees(),
+            ..Default::default()
+        };
+        let amount_out = quote_amount_out(&pool, 2_000_000, 5_000_000, 100_000, 0).unwrap();
+        assert_eq!(
+            quote,
+            TransferFeeAdjustedQuote {
+                net_amount_in: 100_000,
+                amount_out,
+                amount_received: amount_out,
+                fee_amount: 250,
+            }
+        );
+
+        // Fails the way the swap would
+        assert_eq!(
+            quote_with_fees(
+                swap_fees(),
+                CURVE_TYPE_CONSTANT_PRODUCT,
+                0,
+                0,
+                5_000_000,
+                100_000,
+                0,
+                &coin_mint,
+                &pc_mint,
+                0
+            )
+            .unwrap_err(),
+            TradiumError::EmptyPool.into()
+        );
+
+        // A stable pool is quoted on its own curve, not constant product
+        let stable_pool = Tradium {
+            fees: swap_fees(),
+            curve_type: CURVE_TYPE_STABLE,
+            amp_coefficient: 100,
+            ..Default::default()
+        };
+        let quote = quote_with_fees(
+            swap_fees(),
+            CURVE_TYPE_STABLE,
+            100,
+            5_000_000,
+            5_000_000,
+            100_000,
+            0,
+            &coin_mint,
+            &pc_mint,
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            quote.amount_out,
+            quote_amount_out(&stable_pool, 5_000_000, 5_000_000, 100_000, 0).unwrap()
+        );
+        assert!(
+            quote.amount_out > quote_amount_out(&pool, 5_000_000, 5_000_000, 100_000, 0).unwrap()
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn quote_applies_transfer_fees() {
+        use crate::instructions::shared;
+
+        let token_2022 = spl_token_2022::ID;
+        let (coin_key, pc_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut coin_mint_account =
+            TestAccount::new(coin_key, token_2022, shared::transfer_fee_mint_data(100));
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account =
+            TestAccount::new(pc_key, token_2022, shared::transfer_fee_mint_data(100));
+        let pc_mint = pc_mint_account.info();
+
+        let quote = quote_with_fees(
+            swap_fees(),
+            CURVE_TYPE_CONSTANT_PRODUCT,
+            0,
+            2_000_000,
+            5_000_000,
+            100_000,
+            1,
+            &pc_mint,
+            &coin_mint,
+            0,
+        )
+        .unwrap();
+        assert_eq!(quote.net_amount_in, 99_000);
+        assert_eq!(
+            quote.amount_received,
+            quote.amount_out - (quote.amount_out * 100).div_ceil(10_000)
+        );
+    }
+}
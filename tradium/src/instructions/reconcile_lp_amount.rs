@@ -0,0 +1,51 @@
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+/// Permissionless: the LP mint supply is the source of truth, so anyone may
+/// resync the pool's copy of it.
+#[derive(Accounts)]
+pub struct ReconcileLpAmount<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.lp_mint @ TradiumError::InvalidLpMint)]
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+}
+
+/// Resets `pool.lp_amount` to the LP mint supply, recovering pools whose
+/// count drifted before withdrawals decremented it.
+pub fn handle_reconcile_lp_amount(ctx: Context<ReconcileLpAmount>) -> Result<()> {
+    let supply = ctx.accounts.lp_mint.supply;
+    let previous = reconcile(&mut ctx.accounts.pool, supply);
+
+    msg!("lp_amount reconciled from {} to {}", previous, supply);
+
+    Ok(())
+}
+
+/// Sets `lp_amount` to `lp_supply`, returning the previous value.
+fn reconcile(pool: &mut Tradium, lp_supply: u64) -> u64 {
+    std::mem::replace(&mut pool.lp_amount, lp_supply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drifted_lp_amount_is_reset_to_supply() {
+        // Deposits counted 3_000 LP, withdrawals burned 1_200 without decrementing
+        let mut pool = Tradium {
+            lp_amount: 3_000,
+            ..Default::default()
+        };
+        assert_eq!(reconcile(&mut pool, 1_800), 3_000);
+        assert_eq!(pool.lp_amount, 1_800);
+
+        // Reconciling an accurate pool is a no-op
+        assert_eq!(reconcile(&mut pool, 1_800), 1_800);
+        assert_eq!(pool.lp_amount, 1_800);
+    }
+}
@@ -1,49 +1,72 @@
 use crate::error::TradiumError;
+use crate::state::Tradium;
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{
-    Mint as MintInterface, TokenAccount as TokenAccountInterface, TokenInterface,
+#[cfg(feature = "token-2022")]
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token_interface::{self, TokenInterface};
+#[cfg(feature = "token-2022")]
+use spl_token_2022::extension::{
+    cpi_guard::CpiGuard, transfer_fee::TransferFeeConfig, transfer_hook::TransferHook,
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
 };
-use spl_token_2022::extension::transfer_hook::TransferHook;
-use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+#[cfg(feature = "token-2022")]
+use spl_transfer_hook_interface::{
+    get_extra_account_metas_address, onchain::add_extra_accounts_for_execute_cpi,
+};
+
+/// Transfer hook requirement of a mint, resolved once per instruction so the
+/// mint data is only borrowed and unpacked a single time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookResolution {
+    /// The mint has no transfer hook
+    NoHook,
+    /// The mint's hook program, already matched against the pool whitelist
+    Hook(Pubkey),
+}
 
+/// Transfers `amount` from `from` to `to`. Hooked mints go through
+/// `transfer_checked` with every account the hook declares in its
+/// `ExtraAccountMetaList`, resolved from `extra_accounts` (typically the
+/// instruction's remaining accounts).
+#[allow(clippy::too_many_arguments)]
 pub fn transfer_tokens_with_hook_support<'info>(
     token_program: &Interface<'info, TokenInterface>,
-    from: &InterfaceAccount<'info, TokenAccountInterface>,
-    to: &InterfaceAccount<'info, TokenAccountInterface>,
+    from: &impl ToAccountInfo<'info>,
+    to: &impl ToAccountInfo<'info>,
+    mint: &AccountInfo<'info>,
     authority: &AccountInfo<'info>,
-    mint: &InterfaceAccount<'info, MintInterface>,
+    hook: HookResolution,
     transfer_hook_program: Option<&UncheckedAccount<'info>>,
+    extra_accounts: &[AccountInfo<'info>],
     amount: u64,
-    signer_seeds: Option<&'info [&'info [&'info [u8]]]>,
+    signer_seeds: Option<&[&[&[u8]]]>,
 ) -> Result<()> {
-    use anchor_spl::token_interface;
-    use spl_token_2022::extension::transfer_hook::TransferHook;
-    use spl_token_2022::extension::{ExtensionType, StateWithExtensions};
+    // The hook was resolved and validated up front, so no need to re-read the mint here
+    if let HookResolution::Hook(hook_program_id) = hook {
+        let hook_program_acc =
+            transfer_hook_program.ok_or(TradiumError::MissingTransferHookProgram)?;
 
-    let mut remaining_accounts: Vec<AccountInfo> = Vec::new();
+        #[cfg(feature = "token-2022")]
+        {
+            return transfer_checked_with_extra_metas(
+                &token_program.to_account_info(),
+                &from.to_account_info(),
+                mint,
+                &to.to_account_info(),
+                authority,
+                &hook_program_id,
+                &hook_program_acc.to_account_info(),
+                extra_accounts,
+                amount,
+                signer_seeds.unwrap_or_default(),
+            );
+        }
 
-    // Check if the mint is a Token-2022 mint and has a TransferHook extension
-    let mint_info = mint.to_account_info();
-    if mint_info.owner == &spl_token_2022::ID {
-        if let Ok(mint_data_with_extensions) =
-            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_info.data.borrow())
+        // Hooks are only ever resolved with the Token-2022 extensions compiled in
+        #[cfg(not(feature = "token-2022"))]
         {
-            if let Ok(_transfer_hook_extension) =
-                mint_data_with_extensions.get_extension::<TransferHook>()
-            {
-                // If the mint has a transfer hook, ensure the hook program account is provided
-                if let Some(hook_program_acc) = transfer_hook_program {
-                    remaining_accounts.push(hook_program_acc.to_account_info());
-                    // NOTE: If the specific transfer hook requires *other* accounts,
-                    // they would also need to be added to `remaining_accounts` here.
-                    // For a generic AMM, this is a common point of customization.
-                } else {
-                    // This case should ideally be caught by the `validate_transfer_hook_program` constraint
-                    // but it's good to be explicit. Using a generic error since MissingTransferHookProgram
-                    // might not exist in the error enum.
-                    return Err(TradiumError::InvalidTransferHookProgram.into());
-                }
-            }
+            let _ = (hook_program_id, hook_program_acc, mint, extra_accounts);
+            return err!(TradiumError::InvalidTransferHookProgram);
         }
     }
 
@@ -60,59 +83,964 @@ pub fn transfer_tokens_with_hook_support<'info>(
         CpiContext::new(token_program.to_account_info(), transfer_accounts)
     };
 
-    // Add remaining_accounts to the CPI context
-    let transfer_ctx = transfer_ctx.with_remaining_accounts(remaining_accounts);
-
+    // Unhooked mints keep the plain `transfer`; only this call needs the allow
+    #[allow(deprecated)]
     token_interface::transfer(transfer_ctx, amount)?;
 
     Ok(())
 }
 
-pub fn validate_transfer_hook_program(
-    mint: &InterfaceAccount<MintInterface>,
-    transfer_hook_program: &AccountInfo,
+/// `transfer_checked` CPI for a hooked mint, see `hooked_transfer_instruction`.
+#[cfg(feature = "token-2022")]
+#[allow(clippy::too_many_arguments)]
+fn transfer_checked_with_extra_metas<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    hook_program_id: &Pubkey,
+    hook_program: &AccountInfo<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let (transfer_ix, account_infos) = hooked_transfer_instruction(
+        token_program,
+        from,
+        mint,
+        to,
+        authority,
+        hook_program_id,
+        hook_program,
+        extra_accounts,
+        amount,
+    )?;
+    invoke_signed(&transfer_ix, &account_infos, signer_seeds)?;
+
+    Ok(())
+}
+
+/// `transfer_checked` instruction and account infos for a hooked mint. The
+/// hook's validation state PDA (`get_extra_account_metas_address`) must be
+/// among `extra_accounts`; the accounts its `ExtraAccountMetaList` declares
+/// are resolved from them and appended.
+#[cfg(feature = "token-2022")]
+#[allow(clippy::too_many_arguments)]
+fn hooked_transfer_instruction<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    hook_program_id: &Pubkey,
+    hook_program: &AccountInfo<'info>,
+    extra_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<(
+    anchor_lang::solana_program::instruction::Instruction,
+    Vec<AccountInfo<'info>>,
+)> {
+    let validation_address = get_extra_account_metas_address(mint.key, hook_program_id);
+    require!(
+        extra_accounts
+            .iter()
+            .any(|account| *account.key == validation_address),
+        TradiumError::MissingTransferHookAccount
+    );
+
+    let decimals = {
+        let mint_data = mint.try_borrow_data()?;
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?
+            .base
+            .decimals
+    };
+
+    let mut transfer_ix = spl_token_2022::instruction::transfer_checked(
+        token_program.key,
+        from.key,
+        mint.key,
+        to.key,
+        authority.key,
+        &[],
+        amount,
+        decimals,
+    )?;
+    let mut account_infos = vec![from.clone(), mint.clone(), to.clone(), authority.clone()];
+
+    // The hook program itself must be resolvable alongside its extra accounts
+    let mut additional_accounts = extra_accounts.to_vec();
+    additional_accounts.push(hook_program.clone());
+    add_extra_accounts_for_execute_cpi(
+        &mut transfer_ix,
+        &mut account_infos,
+        hook_program_id,
+        from.clone(),
+        mint.clone(),
+        to.clone(),
+        authority.clone(),
+        amount,
+        &additional_accounts,
+    )?;
+    account_infos.push(token_program.clone());
+
+    Ok((transfer_ix, account_infos))
+}
+
+/// Resolves everything `transfer_tokens_with_hook_support` would need for a
+/// hooked transfer, extra accounts included, without moving any tokens. An
+/// instruction making several transfers checks them all first, so a
+/// malformed hook setup on a later transfer fails before an earlier one has
+/// run. Unhooked transfers need nothing and always pass.
+#[allow(clippy::too_many_arguments)]
+pub fn prevalidate_hook_transfer<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    hook: HookResolution,
+    transfer_hook_program: Option<&UncheckedAccount<'info>>,
+    extra_accounts: &[AccountInfo<'info>],
+    amount: u64,
+) -> Result<()> {
+    let HookResolution::Hook(hook_program_id) = hook else {
+        return Ok(());
+    };
+    let hook_program_acc = transfer_hook_program.ok_or(TradiumError::MissingTransferHookProgram)?;
+
+    #[cfg(feature = "token-2022")]
+    {
+        hooked_transfer_instruction(
+            token_program,
+            from,
+            mint,
+            to,
+            authority,
+            &hook_program_id,
+            &hook_program_acc.to_account_info(),
+            extra_accounts,
+            amount,
+        )?;
+        Ok(())
+    }
+
+    // Hooks are only ever resolved with the Token-2022 extensions compiled in
+    #[cfg(not(feature = "token-2022"))]
+    {
+        let _ = (
+            token_program,
+            from,
+            to,
+            mint,
+            authority,
+            hook_program_id,
+            hook_program_acc,
+            extra_accounts,
+            amount,
+        );
+        err!(TradiumError::InvalidTransferHookProgram)
+    }
+}
+
+/// Program id of the mint's transfer hook, if it is a Token-2022 mint with an
+/// active TransferHook extension.
+#[cfg(feature = "token-2022")]
+pub fn mint_transfer_hook_program_id(mint_info: &AccountInfo) -> Option<Pubkey> {
+    if mint_info.owner != &spl_token_2022::ID {
+        return None;
+    }
+    let mint_data = mint_info.try_borrow_data().ok()?;
+    let mint_with_extensions =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data).ok()?;
+    let transfer_hook = mint_with_extensions.get_extension::<TransferHook>().ok()?;
+    Option::<Pubkey>::from(transfer_hook.program_id)
+}
+
+/// Without Token-2022 support no mint can carry a transfer hook.
+#[cfg(not(feature = "token-2022"))]
+pub fn mint_transfer_hook_program_id(_mint_info: &AccountInfo) -> Option<Pubkey> {
+    None
+}
+
+/// Every extension present on a Token-2022 mint; empty for SPL mints and for
+/// data that does not unpack as a mint.
+#[cfg(feature = "token-2022")]
+pub fn mint_extensions(mint_account: &AccountInfo) -> Vec<ExtensionType> {
+    if mint_account.owner != &spl_token_2022::ID {
+        return Vec::new();
+    }
+    let Ok(mint_data) = mint_account.try_borrow_data() else {
+        return Vec::new();
+    };
+    StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
+        .and_then(|mint| mint.get_extension_types())
+        .unwrap_or_default()
+}
+
+/// Returns true if the mint is a Token-2022 mint carrying a TransferHook extension.
+pub fn mint_has_transfer_hook(mint_info: &AccountInfo) -> bool {
+    mint_transfer_hook_program_id(mint_info).is_some()
+}
+
+/// Value of `Tradium::has_any_hook_mints` for a pool of these two mints.
+pub fn pool_mints_have_hook(coin_mint: &AccountInfo, pc_mint: &AccountInfo) -> bool {
+    mint_has_transfer_hook(coin_mint) || mint_has_transfer_hook(pc_mint)
+}
+
+/// Transfer fee the mint withholds from a transfer of `amount` in `epoch`;
+/// zero unless it is a Token-2022 mint with a TransferFeeConfig extension.
+#[cfg(feature = "token-2022")]
+pub fn mint_transfer_fee(mint_info: &AccountInfo, epoch: u64, amount: u64) -> Result<u64> {
+    with_transfer_fee_config(mint_info, |config| {
+        config.calculate_epoch_fee(epoch, amount)
+    })
+}
+
+/// Fee to add to `post_fee_amount` so that exactly that much arrives after
+/// the mint's transfer fee in `epoch`; zero for mints without one.
+#[cfg(feature = "token-2022")]
+pub fn mint_transfer_inverse_fee(
+    mint_info: &AccountInfo,
+    epoch: u64,
+    post_fee_amount: u64,
+) -> Result<u64> {
+    with_transfer_fee_config(mint_info, |config| {
+        config.calculate_inverse_epoch_fee(epoch, post_fee_amount)
+    })
+}
+
+#[cfg(feature = "token-2022")]
+fn with_transfer_fee_config(
+    mint_info: &AccountInfo,
+    fee: impl FnOnce(&TransferFeeConfig) -> Option<u64>,
+) -> Result<u64> {
+    if mint_info.owner != &spl_token_2022::ID {
+        return Ok(0);
+    }
+    let mint_data = mint_info.try_borrow_data()?;
+    let mint_with_extensions =
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+    let Ok(transfer_fee_config) = mint_with_extensions.get_extension::<TransferFeeConfig>() else {
+        return Ok(0);
+    };
+    fee(transfer_fee_config).ok_or(TradiumError::MathOverflow.into())
+}
+
+/// Without Token-2022 support no mint can charge a transfer fee.
+#[cfg(not(feature = "token-2022"))]
+pub fn mint_transfer_fee(_mint_info: &AccountInfo, _epoch: u64, _amount: u64) -> Result<u64> {
+    Ok(0)
+}
+
+#[cfg(not(feature = "token-2022"))]
+pub fn mint_transfer_inverse_fee(
+    _mint_info: &AccountInfo,
+    _epoch: u64,
+    _post_fee_amount: u64,
+) -> Result<u64> {
+    Ok(0)
+}
+
+/// Returns true if the token account is a Token-2022 account with CpiGuard
+/// locked, which would block the pool's CPI transfers out of it.
+#[cfg(feature = "token-2022")]
+pub fn token_account_has_cpi_guard(account_info: &AccountInfo) -> bool {
+    if account_info.owner != &spl_token_2022::ID {
+        return false;
+    }
+    let Ok(data) = account_info.try_borrow_data() else {
+        return false;
+    };
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+        .ok()
+        .and_then(|account| {
+            account
+                .get_extension::<CpiGuard>()
+                .ok()
+                .map(|guard| bool::from(guard.lock_cpi))
+        })
+        .unwrap_or(false)
+}
+
+/// Without Token-2022 support no token account can carry a CpiGuard.
+#[cfg(not(feature = "token-2022"))]
+pub fn token_account_has_cpi_guard(_account_info: &AccountInfo) -> bool {
+    false
+}
+
+/// Freezes or thaws an LP token account of a soulbound-LP pool. The pool is
+/// the LP mint's freeze authority and signs with `signer_seeds`.
+pub fn set_lp_account_frozen<'info>(
+    token_program: &AccountInfo<'info>,
+    lp_account: &AccountInfo<'info>,
+    lp_mint: &AccountInfo<'info>,
+    pool: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    frozen: bool,
+) -> Result<()> {
+    if frozen {
+        token_interface::freeze_account(CpiContext::new_with_signer(
+            token_program.clone(),
+            token_interface::FreezeAccount {
+                account: lp_account.clone(),
+                mint: lp_mint.clone(),
+                authority: pool.clone(),
+            },
+            signer_seeds,
+        ))
+    } else {
+        token_interface::thaw_account(CpiContext::new_with_signer(
+            token_program.clone(),
+            token_interface::ThawAccount {
+                account: lp_account.clone(),
+                mint: lp_mint.clone(),
+                authority: pool.clone(),
+            },
+            signer_seeds,
+        ))
+    }
+}
+
+/// Resolves the transfer hook of `mint_info` and validates the provided hook
+/// program: it must match the mint's hook, be an executable program and be
+/// whitelisted on the pool. Only the first `num_whitelisted` entries count,
+/// bounded by the slice, so a corrupted count can never index past it.
+pub fn resolve_transfer_hook(
+    mint_info: &AccountInfo,
+    transfer_hook_program: Option<&UncheckedAccount>,
     whitelisted_hooks: &[Pubkey],
     num_whitelisted: u8,
-) -> bool {
-    // Check if mint has transfer hook extension
-    let mint_info = mint.to_account_info();
-    let mint_data = mint_info.data.borrow();
-
-    // For Token-2022 mints, check for transfer hook extension
-    if mint_info.owner == &spl_token_2022::ID {
-        match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data) {
-            Ok(mint_with_extensions) => {
-                if let Ok(transfer_hook_account) =
-                    mint_with_extensions.get_extension::<TransferHook>()
-                {
-                    // Mint has transfer hook - validate the provided program
-                    let hook_program_id =
-                        if let Some(pubkey) = transfer_hook_account.program_id.into() {
-                            pubkey
-                        } else {
-                            return false;
-                        };
-
-                    // Check if the hook program matches the mint's hook
-                    if transfer_hook_program.key() != hook_program_id {
-                        return false;
-                    }
-
-                    // Check if the hook program is whitelisted
-                    for i in 0..(num_whitelisted as usize) {
-                        if whitelisted_hooks[i] == hook_program_id {
-                            return true;
-                        }
-                    }
-                    return false;
-                } else {
-                    return false;
-                }
-            }
-            Err(_) => return false,
+) -> Result<HookResolution> {
+    let Some(hook_program_id) = mint_transfer_hook_program_id(mint_info) else {
+        return Ok(HookResolution::NoHook);
+    };
+
+    let hook_program = transfer_hook_program.ok_or(TradiumError::MissingTransferHookProgram)?;
+    require_keys_eq!(
+        hook_program.key(),
+        hook_program_id,
+        TradiumError::InvalidTransferHookProgram
+    );
+    require!(
+        hook_program.executable,
+        TradiumError::TransferHookProgramNotExecutable
+    );
+    require!(
+        whitelisted_hooks
+            .iter()
+            .take(num_whitelisted as usize)
+            .any(|hook| *hook == hook_program_id),
+        TradiumError::UnwhitelistedTransferHookProgram
+    );
+
+    Ok(HookResolution::Hook(hook_program_id))
+}
+
+/// `resolve_transfer_hook` against the pool's whitelist, skipping the mint
+/// probe entirely for pools whose mints carry no hooks.
+pub fn resolve_pool_transfer_hook(
+    pool: &Tradium,
+    mint_info: &AccountInfo,
+    transfer_hook_program: Option<&UncheckedAccount>,
+) -> Result<HookResolution> {
+    if !pool.has_any_hook_mints {
+        return Ok(HookResolution::NoHook);
+    }
+    resolve_transfer_hook(
+        mint_info,
+        transfer_hook_program,
+        &pool.whitelisted_transfer_hooks,
+        pool.num_whitelisted_hooks,
+    )
+}
+
+/// Owns what an `AccountInfo` borrows, so tests can build one in a line
+#[cfg(test)]
+pub(crate) struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub executable: bool,
+}
+
+#[cfg(test)]
+impl TestAccount {
+    /// A writable account at `key` owned by `owner`
+    pub fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self {
+            key,
+            owner,
+            lamports: 0,
+            data,
+            is_signer: false,
+            executable: false,
         }
-    } else {
-        // Regular SPL token but program was provided - invalid
-        return false;
+    }
+
+    /// A data-less system account signing at `key`
+    pub fn signer(key: Pubkey) -> Self {
+        Self {
+            is_signer: true,
+            ..Self::new(key, anchor_lang::system_program::ID, vec![])
+        }
+    }
+
+    /// An executable program account at `key` owned by `loader`
+    #[cfg(feature = "token-2022")]
+    pub fn program(key: Pubkey, loader: Pubkey) -> Self {
+        Self {
+            executable: true,
+            ..Self::new(key, loader, vec![])
+        }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            true,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+/// Token-2022 mint data charging `basis_points` on every transfer
+#[cfg(all(test, feature = "token-2022"))]
+pub(crate) fn transfer_fee_mint_data(basis_points: u16) -> Vec<u8> {
+    use spl_token_2022::extension::transfer_fee::{TransferFee, TransferFeeConfig};
+    use spl_token_2022::extension::{
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    };
+
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferFeeConfig,
+    ])
+    .unwrap();
+    let mut data = vec![0u8; space];
+    let mut state =
+        StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+            .unwrap();
+    let fee = TransferFee {
+        epoch: 0.into(),
+        maximum_fee: u64::MAX.into(),
+        transfer_fee_basis_points: basis_points.into(),
+    };
+    let extension = state.init_extension::<TransferFeeConfig>(true).unwrap();
+    extension.older_transfer_fee = fee;
+    extension.newer_transfer_fee = fee;
+    state.base = spl_token_2022::state::Mint {
+        decimals: 6,
+        is_initialized: true,
+        ..Default::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+/// Token-2022 mint data carrying a TransferHook extension pointing at `hook_program_id`
+#[cfg(all(test, feature = "token-2022"))]
+pub(crate) fn hooked_mint_data(hook_program_id: Pubkey) -> Vec<u8> {
+    use spl_token_2022::extension::{BaseStateWithExtensionsMut, StateWithExtensionsMut};
+
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+        ExtensionType::TransferHook,
+    ])
+    .unwrap();
+    let mut data = vec![0u8; space];
+    let mut state =
+        StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+            .unwrap();
+    let extension = state.init_extension::<TransferHook>(true).unwrap();
+    extension.program_id = Some(hook_program_id).try_into().unwrap();
+    state.base = spl_token_2022::state::Mint {
+        decimals: 6,
+        is_initialized: true,
+        ..Default::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::program_pack::Pack;
+    #[cfg(feature = "token-2022")]
+    use spl_token_2022::extension::{
+        BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+    };
+
+    /// Initialized Token-2022 token account data, optionally with CpiGuard locked
+    #[cfg(feature = "token-2022")]
+    fn token_2022_account_data(cpi_guard_locked: bool) -> Vec<u8> {
+        let extensions: &[ExtensionType] = if cpi_guard_locked {
+            &[ExtensionType::CpiGuard]
+        } else {
+            &[]
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(extensions)
+                .unwrap();
+        let mut data = vec![0u8; space];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Account>::unpack_uninitialized(
+                &mut data,
+            )
+            .unwrap();
+        if cpi_guard_locked {
+            let extension = state.init_extension::<CpiGuard>(true).unwrap();
+            extension.lock_cpi = true.into();
+        }
+        state.base = spl_token_2022::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: Pubkey::new_unique(),
+            state: spl_token_2022::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn pool_vault_layout_has_no_cpi_guard() {
+        // Vaults are created with a plain initialize_account, so no extensions
+        let key = Pubkey::new_unique();
+        let owner = spl_token_2022::ID;
+        let mut vault_account = TestAccount::new(key, owner, token_2022_account_data(false));
+        let vault_info = vault_account.info();
+        assert!(!token_account_has_cpi_guard(&vault_info));
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn locked_cpi_guard_is_detected() {
+        let key = Pubkey::new_unique();
+        let owner = spl_token_2022::ID;
+        let mut vault_account = TestAccount::new(key, owner, token_2022_account_data(true));
+        let vault_info = vault_account.info();
+        assert!(token_account_has_cpi_guard(&vault_info));
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn mint_extensions_lists_every_extension() {
+        use spl_token_2022::extension::{
+            mint_close_authority::MintCloseAuthority, transfer_fee::TransferFee,
+        };
+
+        let extensions = [
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::TransferHook,
+            ExtensionType::MintCloseAuthority,
+        ];
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&extensions)
+                .unwrap();
+        let mut data = vec![0u8; space];
+        let mut state =
+            StateWithExtensionsMut::<spl_token_2022::state::Mint>::unpack_uninitialized(&mut data)
+                .unwrap();
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: 0.into(),
+            transfer_fee_basis_points: 100.into(),
+        };
+        let transfer_fee = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        transfer_fee.older_transfer_fee = fee;
+        transfer_fee.newer_transfer_fee = fee;
+        let hook = state.init_extension::<TransferHook>(true).unwrap();
+        hook.program_id = Some(Pubkey::new_unique()).try_into().unwrap();
+        let close = state.init_extension::<MintCloseAuthority>(true).unwrap();
+        close.close_authority = Some(Pubkey::new_unique()).try_into().unwrap();
+        state.base = spl_token_2022::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let key = Pubkey::new_unique();
+        let owner = spl_token_2022::ID;
+        let mut mint_account = TestAccount::new(key, owner, data);
+        let mint = mint_account.info();
+        assert_eq!(mint_extensions(&mint), extensions.to_vec());
+
+        // A plain Token-2022 mint carries none
+        let mut plain_data = vec![0u8; spl_token_2022::state::Mint::LEN];
+        let plain_mint_state = spl_token_2022::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        spl_token_2022::state::Mint::pack(plain_mint_state, &mut plain_data).unwrap();
+        let mut plain_account = TestAccount::new(key, owner, plain_data);
+        let plain = plain_account.info();
+        assert!(mint_extensions(&plain).is_empty());
+    }
+
+    #[test]
+    fn spl_mint_has_no_transfer_hook() {
+        let key = Pubkey::new_unique();
+        let owner = spl_token::ID;
+        let mut mint_account = TestAccount::new(key, owner, vec![0u8; spl_token::state::Mint::LEN]);
+        let mint_info = mint_account.info();
+        assert!(!mint_has_transfer_hook(&mint_info));
+        #[cfg(feature = "token-2022")]
+        assert!(mint_extensions(&mint_info).is_empty());
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn pool_without_hook_mints_never_reads_the_mint() {
+        // A hooked mint passed without its hook program fails resolution...
+        let mut mint_account = TestAccount::new(
+            Pubkey::new_unique(),
+            spl_token_2022::ID,
+            hooked_mint_data(Pubkey::new_unique()),
+        );
+        let mint = mint_account.info();
+        let mut pool = Tradium {
+            has_any_hook_mints: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            resolve_pool_transfer_hook(&pool, &mint, None).unwrap_err(),
+            TradiumError::MissingTransferHookProgram.into()
+        );
+
+        // ...but a pool recorded as hook-free never looks at it
+        pool.has_any_hook_mints = false;
+        assert_eq!(
+            resolve_pool_transfer_hook(&pool, &mint, None).unwrap(),
+            HookResolution::NoHook
+        );
+
+        // Not even when the account is no mint at all
+        let mut garbage_account =
+            TestAccount::new(Pubkey::new_unique(), spl_token_2022::ID, vec![0xAB; 7]);
+        assert_eq!(
+            resolve_pool_transfer_hook(&pool, &garbage_account.info(), None).unwrap(),
+            HookResolution::NoHook
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn uninitialized_token_2022_mint_has_no_transfer_hook() {
+        let key = Pubkey::new_unique();
+        let owner = spl_token_2022::ID;
+        let mut mint_account =
+            TestAccount::new(key, owner, vec![0u8; spl_token_2022::state::Mint::LEN]);
+        let mint_info = mint_account.info();
+        assert!(!mint_has_transfer_hook(&mint_info));
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn resolve_hooked_mint_requires_matching_whitelisted_program() {
+        let hook_program_id = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let token_2022 = spl_token_2022::ID;
+        let mut mint_account =
+            TestAccount::new(mint_key, token_2022, hooked_mint_data(hook_program_id));
+        let mint_info = mint_account.info();
+        assert_eq!(
+            mint_transfer_hook_program_id(&mint_info),
+            Some(hook_program_id)
+        );
+
+        let loader = anchor_lang::solana_program::bpf_loader_upgradeable::ID;
+        let mut program_account = TestAccount::program(hook_program_id, loader);
+        let program_info = program_account.info();
+        let hook_program = UncheckedAccount::try_from(&program_info);
+
+        let mut whitelist = [Pubkey::default(); 10];
+        assert_eq!(
+            resolve_transfer_hook(&mint_info, Some(&hook_program), &whitelist, 0).unwrap_err(),
+            TradiumError::UnwhitelistedTransferHookProgram.into()
+        );
+        assert_eq!(
+            resolve_transfer_hook(&mint_info, None, &whitelist, 0).unwrap_err(),
+            TradiumError::MissingTransferHookProgram.into()
+        );
+
+        whitelist[0] = hook_program_id;
+        assert_eq!(
+            resolve_transfer_hook(&mint_info, Some(&hook_program), &whitelist, 1).unwrap(),
+            HookResolution::Hook(hook_program_id)
+        );
+
+        // A count corrupted past the whitelist's length is bounded, not a panic
+        whitelist[0] = Pubkey::default();
+        assert_eq!(
+            resolve_transfer_hook(&mint_info, Some(&hook_program), &whitelist, u8::MAX)
+                .unwrap_err(),
+            TradiumError::UnwhitelistedTransferHookProgram.into()
+        );
+        whitelist[9] = hook_program_id;
+        assert_eq!(
+            resolve_transfer_hook(&mint_info, Some(&hook_program), &whitelist, u8::MAX).unwrap(),
+            HookResolution::Hook(hook_program_id)
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn non_executable_hook_program_is_rejected() {
+        let hook_program_id = Pubkey::new_unique();
+        let mint_key = Pubkey::new_unique();
+        let token_2022 = spl_token_2022::ID;
+        let mut mint_account =
+            TestAccount::new(mint_key, token_2022, hooked_mint_data(hook_program_id));
+        let mint_info = mint_account.info();
+
+        // A data account at the hook program's address, e.g. a closed program
+        let system_program = anchor_lang::solana_program::system_program::ID;
+        let mut program_account = TestAccount::new(hook_program_id, system_program, vec![]);
+        let program_info = program_account.info();
+        let hook_program = UncheckedAccount::try_from(&program_info);
+
+        let mut whitelist = [Pubkey::default(); 10];
+        whitelist[0] = hook_program_id;
+        assert_eq!(
+            resolve_transfer_hook(&mint_info, Some(&hook_program), &whitelist, 1).unwrap_err(),
+            TradiumError::TransferHookProgramNotExecutable.into()
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn malformed_output_hook_accounts_fail_before_any_transfer() {
+        use anchor_lang::solana_program::hash::hashv;
+
+        let token_2022 = spl_token_2022::ID;
+        let loader = anchor_lang::solana_program::bpf_loader_upgradeable::ID;
+        let (input_hook_id, output_hook_id) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (input_mint_key, output_mint_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (from_key, to_key, authority_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let input_validation_key = get_extra_account_metas_address(&input_mint_key, &input_hook_id);
+        let output_validation_key =
+            get_extra_account_metas_address(&output_mint_key, &output_hook_id);
+
+        // An `ExtraAccountMetaList` declaring no extra accounts: the Execute
+        // discriminator, the TLV length and an empty slice
+        let mut input_validation_data =
+            hashv(&[b"spl-transfer-hook-interface:execute"]).to_bytes()[..8].to_vec();
+        input_validation_data.extend_from_slice(&4u32.to_le_bytes());
+        input_validation_data.extend_from_slice(&0u32.to_le_bytes());
+        // The output hook's validation account holds no list at all
+        let output_validation_data = vec![0xFF; 16];
+
+        let mut token_program_account = TestAccount::program(token_2022, loader);
+        let mut input_mint_account =
+            TestAccount::new(input_mint_key, token_2022, hooked_mint_data(input_hook_id));
+        let mut output_mint_account = TestAccount::new(
+            output_mint_key,
+            token_2022,
+            hooked_mint_data(output_hook_id),
+        );
+        let mut from_account = TestAccount::new(from_key, token_2022, vec![]);
+        let mut to_account = TestAccount::new(to_key, token_2022, vec![]);
+        let mut authority_account = TestAccount::new(authority_key, token_2022, vec![]);
+        let mut input_hook_account = TestAccount::program(input_hook_id, loader);
+        let mut output_hook_account = TestAccount::program(output_hook_id, loader);
+        let mut input_validation_account =
+            TestAccount::new(input_validation_key, input_hook_id, input_validation_data);
+        let mut output_validation_account = TestAccount::new(
+            output_validation_key,
+            output_hook_id,
+            output_validation_data,
+        );
+        let token_program = token_program_account.info();
+        let input_mint = input_mint_account.info();
+        let output_mint = output_mint_account.info();
+        let from = from_account.info();
+        let to = to_account.info();
+        let authority = authority_account.info();
+        let input_hook_info = input_hook_account.info();
+        let output_hook_info = output_hook_account.info();
+        let input_validation = input_validation_account.info();
+        let output_validation = output_validation_account.info();
+        let input_hook_program = UncheckedAccount::try_from(&input_hook_info);
+        let output_hook_program = UncheckedAccount::try_from(&output_hook_info);
+
+        let input_extra = [input_validation.clone()];
+        let output_extra = [input_validation.clone(), output_validation.clone()];
+
+        // The input side resolves, so only the output side can stop the swap
+        assert!(prevalidate_hook_transfer(
+            &token_program,
+            &from,
+            &to,
+            &input_mint,
+            &authority,
+            HookResolution::Hook(input_hook_id),
+            Some(&input_hook_program),
+            &input_extra,
+            1_000,
+        )
+        .is_ok());
+
+        // Its validation account missing, the output side fails up front
+        assert_eq!(
+            prevalidate_hook_transfer(
+                &token_program,
+                &to,
+                &from,
+                &output_mint,
+                &authority,
+                HookResolution::Hook(output_hook_id),
+                Some(&output_hook_program),
+                &input_extra,
+                1_000,
+            )
+            .unwrap_err(),
+            TradiumError::MissingTransferHookAccount.into()
+        );
+
+        // And so does one holding no valid meta list
+        assert!(prevalidate_hook_transfer(
+            &token_program,
+            &to,
+            &from,
+            &output_mint,
+            &authority,
+            HookResolution::Hook(output_hook_id),
+            Some(&output_hook_program),
+            &output_extra,
+            1_000,
+        )
+        .is_err());
+
+        // Unhooked transfers need no accounts
+        assert!(prevalidate_hook_transfer(
+            &token_program,
+            &from,
+            &to,
+            &output_mint,
+            &authority,
+            HookResolution::NoHook,
+            None,
+            &[],
+            1_000,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn frozen_soulbound_lp_cannot_be_transferred_but_can_be_burned() {
+        use spl_token::instruction as ix;
+        use spl_token::processor::Processor;
+
+        let token_program = spl_token::ID;
+        let pool_key = Pubkey::new_unique();
+        let user_key = Pubkey::new_unique();
+        let (mint_key, lp_key, other_key) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+
+        // LP mint with the pool as mint and freeze authority
+        let mut mint_data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: Some(pool_key).into(),
+            supply: 1_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: Some(pool_key).into(),
+        }
+        .pack_into_slice(&mut mint_data);
+        // The user's LP account, frozen after the deposit minted into it
+        let token_account = |amount, state| {
+            let mut data = vec![0u8; spl_token::state::Account::LEN];
+            spl_token::state::Account {
+                mint: mint_key,
+                owner: user_key,
+                amount,
+                state,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+        let mut mint_account = TestAccount::new(mint_key, token_program, mint_data);
+        let mut lp_account = TestAccount::new(
+            lp_key,
+            token_program,
+            token_account(1_000, spl_token::state::AccountState::Frozen),
+        );
+        let mut other_account = TestAccount::new(
+            other_key,
+            token_program,
+            token_account(0, spl_token::state::AccountState::Initialized),
+        );
+        let mut pool_account = TestAccount {
+            is_signer: true,
+            ..TestAccount::new(pool_key, crate::ID, vec![])
+        };
+        let mut user_account = TestAccount {
+            is_signer: true,
+            ..TestAccount::new(user_key, token_program, vec![])
+        };
+        let mint = mint_account.info();
+        let lp = lp_account.info();
+        let other = other_account.info();
+        let pool = pool_account.info();
+        let user = user_account.info();
+
+        // Wallet-to-wallet LP transfers are blocked
+        let transfer =
+            ix::transfer(&token_program, &lp_key, &other_key, &user_key, &[], 100).unwrap();
+        assert!(Processor::process(
+            &token_program,
+            &[lp.clone(), other.clone(), user.clone()],
+            &transfer.data
+        )
+        .is_err());
+
+        // Withdraw still works: the pool thaws, the user burns, the pool refreezes
+        let thaw = ix::thaw_account(&token_program, &lp_key, &mint_key, &pool_key, &[]).unwrap();
+        Processor::process(
+            &token_program,
+            &[lp.clone(), mint.clone(), pool.clone()],
+            &thaw.data,
+        )
+        .unwrap();
+        let burn = ix::burn(&token_program, &lp_key, &mint_key, &user_key, &[], 400).unwrap();
+        Processor::process(
+            &token_program,
+            &[lp.clone(), mint.clone(), user.clone()],
+            &burn.data,
+        )
+        .unwrap();
+        let freeze =
+            ix::freeze_account(&token_program, &lp_key, &mint_key, &pool_key, &[]).unwrap();
+        Processor::process(
+            &token_program,
+            &[lp.clone(), mint.clone(), pool.clone()],
+            &freeze.data,
+        )
+        .unwrap();
+
+        let lp_state = spl_token::state::Account::unpack(&lp.try_borrow_data().unwrap()).unwrap();
+        assert_eq!(lp_state.amount, 600);
+        assert!(lp_state.is_frozen());
     }
 }
@@ -1,9 +1,12 @@
+use crate::constants::POOL_SEED;
+use crate::curve;
 use crate::error::TradiumError;
+use crate::oracle;
+use crate::shared;
 use crate::state::*;
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::{self, AssociatedToken};
 use anchor_spl::token_interface::{Mint, TokenAccount as TokenInterfaceAccount, TokenInterface};
-use spl_token_2022::extension::transfer_hook::TransferHook;
-use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
 
 #[derive(Accounts)]
 pub struct Swap<'info> {
@@ -13,18 +16,20 @@ pub struct Swap<'info> {
     /// The AMM pool state
     #[account(
         mut,
-        seeds = [b"tradium", coin_vault.mint.as_ref(), pc_vault.mint.as_ref()],
-        bump
+        seeds = [POOL_SEED, pool.coin_vault_mint.as_ref(), pool.pc_vault_mint.as_ref()],
+        bump = pool.bump
     )]
     pub pool: Account<'info, Tradium>,
 
-    /// User's input token account
+    /// User's input token account; must be owned by `user`, who signs the
+    /// transfer as owner, not as a delegate
     #[account(mut)]
     pub user_input_token_account: InterfaceAccount<'info, TokenInterfaceAccount>,
 
-    /// User's output token account
+    /// CHECK: User's output token account; may not exist yet when `create_output_ata`
+    /// is set. Its owner program and mint are validated in the handler.
     #[account(mut)]
-    pub user_output_token_account: InterfaceAccount<'info, TokenInterfaceAccount>,
+    pub user_output_token_account: UncheckedAccount<'info>,
 
     /// Pool's coin vault (token A)
     #[account(
@@ -54,193 +59,642 @@ pub struct Swap<'info> {
     /// Token program for output token  
     pub output_token_program: Interface<'info, TokenInterface>,
 
-    /// CHECK: Optional, only required if coin_mint has a transfer hook
-    #[account(
-        constraint = validate_transfer_hook_program(
-            &coin_mint,
-            &coin_transfer_hook_program.to_account_info(),
-            &pool.whitelisted_transfer_hooks,
-            pool.num_whitelisted_hooks
-        ) @ TradiumError::InvalidTransferHookProgram
-    )]
+    /// CHECK: Optional, only required if coin_mint has a transfer hook; validated in the handler
     pub coin_transfer_hook_program: Option<UncheckedAccount<'info>>,
 
-    /// CHECK: Optional, only required if pc_mint has a transfer hook
-    #[account(
-        constraint = validate_transfer_hook_program(
-            &pc_mint,
-            &pc_transfer_hook_program.to_account_info(),
-            &pool.whitelisted_transfer_hooks,
-            pool.num_whitelisted_hooks
-        ) @ TradiumError::InvalidTransferHookProgram
-    )]
+    /// CHECK: Optional, only required if pc_mint has a transfer hook; validated in the handler
     pub pc_transfer_hook_program: Option<UncheckedAccount<'info>>,
+
+    /// Optional, only required to create the user's output ATA
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+
+    /// Optional, only required to create the user's output ATA
+    pub system_program: Option<Program<'info, System>>,
+
+    /// CHECK: Reference price feed; must be the pool's configured oracle, and is
+    /// required when the pool sets `max_oracle_deviation_bps`
+    pub price_oracle: Option<UncheckedAccount<'info>>,
 }
 
-pub fn swap(
-    mut ctx: Context<Swap>,
+pub fn handle_swap<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
     amount_in: u64,
     min_amount_out: u64,
     swap_direction: u8,
-) -> Result<()> {
+    create_output_ata: bool,
+    deadline: i64,
+) -> Result<SwapResult> {
+    // A swap that lands after its deadline was priced for a market that is gone
+    let now = Clock::get()?.unix_timestamp;
+    check_deadline(now, deadline)?;
+
     // Validate swap direction
     require!(swap_direction <= 1, TradiumError::InvalidSwapDirection);
 
     // Validate minimum input amount
     require!(amount_in > 0, TradiumError::InvalidInputAmount);
 
+    // Paused pools take no swaps
+    require!(
+        ctx.accounts.pool.is_active(now),
+        TradiumError::InvalidPoolState
+    );
+
+    auto_compound_before_swap(&mut ctx.accounts.pool);
+
     // Validate token program IDs match pool configuration
-    let (input_program_expected, output_program_expected) = if swap_direction == 0 {
+    check_swap_token_programs(
+        &ctx.accounts.pool,
+        &ctx.accounts.input_token_program.key(),
+        &ctx.accounts.output_token_program.key(),
+        swap_direction,
+    )?;
+
+    // The user signs the input transfer as its owner, never as a delegate
+    check_input_authority(
+        &ctx.accounts.user_input_token_account,
+        &ctx.accounts.user.key(),
+    )?;
+
+    // Create the user's output ATA if requested and it doesn't exist yet
+    if create_output_ata && ctx.accounts.user_output_token_account.data_is_empty() {
+        create_output_token_account(ctx.accounts, swap_direction)?;
+    }
+    validate_output_token_account(ctx.accounts, swap_direction)?;
+
+    // Only one large swap is allowed per slot window
+    let current_slot = Clock::get()?.slot;
+    if is_throttled_large_swap(&ctx.accounts.pool, amount_in, swap_direction, current_slot)? {
+        ctx.accounts.pool.last_large_swap_slot = current_slot;
+    }
+
+    // Execute the swap with transfers and state updates
+    execute_swap_transfers(ctx, amount_in, min_amount_out, swap_direction)
+}
+
+/// Swap with the minimum output given as a slippage tolerance in basis points
+/// from the quote at the current reserves, rather than as an absolute amount.
+pub fn handle_swap_with_slippage_bps<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+    amount_in: u64,
+    max_slippage_bps: u64,
+    swap_direction: u8,
+) -> Result<SwapResult> {
+    require!(swap_direction <= 1, TradiumError::InvalidSwapDirection);
+
+    // Quote against the reserves the swap will see
+    auto_compound_before_swap(&mut ctx.accounts.pool);
+
+    let (input_mint, output_mint) = swap_mints(ctx.accounts, swap_direction);
+    let min_amount_out = slippage_bps_min_amount_out(
+        &ctx.accounts.pool,
+        ctx.accounts.coin_vault.amount,
+        ctx.accounts.pc_vault.amount,
+        amount_in,
+        max_slippage_bps,
+        swap_direction,
+        &input_mint,
+        &output_mint,
+        Clock::get()?.epoch,
+    )?;
+
+    handle_swap(
+        ctx,
+        amount_in,
+        min_amount_out,
+        swap_direction,
+        false,
+        i64::MAX,
+    )
+}
+
+/// Minimum output for `swap_with_slippage_bps`: `max_slippage_bps` below
+/// what the swap would deliver now, quoted like the swap itself at the fee
+/// it will pay and net of both mints' transfer fees, so the bound is on the
+/// same amount the swap checks.
+#[allow(clippy::too_many_arguments)]
+pub fn slippage_bps_min_amount_out(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_in: u64,
+    max_slippage_bps: u64,
+    swap_direction: u8,
+    input_mint: &AccountInfo,
+    output_mint: &AccountInfo,
+    epoch: u64,
+) -> Result<u64> {
+    let quote = swap_preflight_with_transfer_fees(
+        pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        amount_in,
+        0,
+        swap_direction,
+        input_mint,
+        output_mint,
+        epoch,
+    )?;
+    curve::min_amount_out_from_bps(quote.amount_received, max_slippage_bps)
+}
+
+/// Swaps for exactly `amount_out` (or a rounding unit more), solving the curve
+/// backwards for the input. Fails with `ExcessiveInputAmount` if that input
+/// exceeds `max_amount_in`. Only the input actually used is transferred.
+pub fn handle_swap_exact_out<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+    amount_out: u64,
+    max_amount_in: u64,
+    swap_direction: u8,
+) -> Result<ExactOutSwapResult> {
+    require!(swap_direction <= 1, TradiumError::InvalidSwapDirection);
+    require!(amount_out > 0, TradiumError::InvalidAmount);
+
+    // Solve for the input against the reserves the swap will see
+    auto_compound_before_swap(&mut ctx.accounts.pool);
+
+    let (input_mint, output_mint) = swap_mints(ctx.accounts, swap_direction);
+    let amount_in_used = quote_amount_in_with_transfer_fees(
+        &ctx.accounts.pool,
+        ctx.accounts.coin_vault.amount,
+        ctx.accounts.pc_vault.amount,
+        amount_out,
+        swap_direction,
+        &input_mint,
+        &output_mint,
+        Clock::get()?.epoch,
+    )?;
+    let amount_in_unused = unused_max_amount_in(amount_in_used, max_amount_in)?;
+
+    let pool = ctx.accounts.pool.key();
+    let user = ctx.accounts.user.key();
+
+    // The regular exact-in path for the used input, with the requested output as its minimum
+    let swap_result = handle_swap(
+        ctx,
+        amount_in_used,
+        amount_out,
+        swap_direction,
+        false,
+        i64::MAX,
+    )?;
+
+    emit!(SwapExactOutEvent {
+        pool,
+        user,
+        amount_out: swap_result.amount_out,
+        amount_in_used,
+        max_amount_in,
+    });
+
+    Ok(ExactOutSwapResult {
+        amount_in_used,
+        amount_in_unused,
+        swap: swap_result,
+    })
+}
+
+/// How much of `max_amount_in` an exact-out swap leaves untouched, or
+/// `ExcessiveInputAmount` if the input it needs exceeds the maximum.
+fn unused_max_amount_in(amount_in_used: u64, max_amount_in: u64) -> Result<u64> {
+    max_amount_in
+        .checked_sub(amount_in_used)
+        .ok_or(TradiumError::ExcessiveInputAmount.into())
+}
+
+/// Input needed to receive `amount_out` from the pool's active reserves at
+/// the fee the next swap would pay.
+pub fn quote_amount_in(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_out: u64,
+    swap_direction: u8,
+) -> Result<u64> {
+    let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault_amount, pc_vault_amount)?;
+    let (reserve_in, reserve_out) = if swap_direction == 0 {
+        (coin_reserve, pc_reserve)
+    } else {
+        (pc_reserve, coin_reserve)
+    };
+    let (fee_numerator, fee_denominator) = pool.effective_swap_fee()?;
+    pool.swap_curve()?.amount_in(
+        amount_out,
+        reserve_in,
+        reserve_out,
+        fee_numerator,
+        fee_denominator,
+    )
+}
+
+/// Gross input the user must send so that, after both mints' transfer fees,
+/// `amount_out` arrives: the output is grossed up for the output mint's fee,
+/// and the curve input for the input mint's fee.
+#[allow(clippy::too_many_arguments)]
+pub fn quote_amount_in_with_transfer_fees(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_out: u64,
+    swap_direction: u8,
+    input_mint: &AccountInfo,
+    output_mint: &AccountInfo,
+    epoch: u64,
+) -> Result<u64> {
+    let gross_amount_out = amount_out
+        .checked_add(shared::mint_transfer_inverse_fee(
+            output_mint,
+            epoch,
+            amount_out,
+        )?)
+        .ok_or(TradiumError::MathOverflow)?;
+    let net_amount_in = quote_amount_in(
+        pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        gross_amount_out,
+        swap_direction,
+    )?;
+    net_amount_in
+        .checked_add(shared::mint_transfer_inverse_fee(
+            input_mint,
+            epoch,
+            net_amount_in,
+        )?)
+        .ok_or(TradiumError::MathOverflow.into())
+}
+
+/// Output for swapping `amount_in` against the pool's active reserves along
+/// its curve, at the fee the next swap would pay.
+pub fn quote_amount_out(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_in: u64,
+    swap_direction: u8,
+) -> Result<u64> {
+    let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault_amount, pc_vault_amount)?;
+    let (reserve_in, reserve_out) = if swap_direction == 0 {
         // Coin to PC swap
+        (coin_reserve, pc_reserve)
+    } else {
+        // PC to Coin swap
+        (pc_reserve, coin_reserve)
+    };
+    let (fee_numerator, fee_denominator) = pool.effective_swap_fee()?;
+    pool.swap_curve()?.amount_out(
+        amount_in,
+        reserve_in,
+        reserve_out,
+        fee_numerator,
+        fee_denominator,
+    )
+}
+
+/// Expected outcome of a swap that passes `swap_preflight`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SwapQuote {
+    pub amount_out: u64,
+    pub fee_amount: u64,
+}
+
+/// Runs the swap's validations against the given vault balances without
+/// moving tokens, returning the quote or the error the swap would fail with.
+pub fn swap_preflight(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_in: u64,
+    min_amount_out: u64,
+    swap_direction: u8,
+) -> Result<SwapQuote> {
+    require!(swap_direction <= 1, TradiumError::InvalidSwapDirection);
+    require!(amount_in > 0, TradiumError::InvalidInputAmount);
+
+    let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault_amount, pc_vault_amount)?;
+    require!(coin_reserve > 0 && pc_reserve > 0, TradiumError::EmptyPool);
+
+    let amount_out = quote_amount_out(
+        pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        amount_in,
+        swap_direction,
+    )?;
+    require!(amount_out > 0, TradiumError::ZeroSwapOutput);
+
+    // Check slippage protection
+    require!(amount_out >= min_amount_out, TradiumError::SlippageExceeded);
+
+    check_output_cap(pool, amount_out, swap_direction)?;
+
+    let (fee_numerator, fee_denominator) = pool.effective_swap_fee()?;
+    let fee_amount = amount_in
+        .checked_sub(curve::amount_in_after_fee(
+            amount_in,
+            fee_numerator,
+            fee_denominator,
+        )?)
+        .ok_or(TradiumError::MathOverflow)?;
+
+    Ok(SwapQuote {
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// A swap quote with the Token-2022 transfer fee of each leg applied.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct TransferFeeAdjustedQuote {
+    /// Input that lands in the vault after the input mint's transfer fee
+    pub net_amount_in: u64,
+    /// Output leaving the vault, priced from `net_amount_in`
+    pub amount_out: u64,
+    /// Output the user receives after the output mint's transfer fee
+    pub amount_received: u64,
+    pub fee_amount: u64,
+}
+
+/// `swap_preflight` for mints that may charge a transfer fee: the curve is
+/// priced from the input that actually reaches the vault, and slippage is
+/// checked against what the user actually receives.
+#[allow(clippy::too_many_arguments)]
+pub fn swap_preflight_with_transfer_fees(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_in: u64,
+    min_amount_out: u64,
+    swap_direction: u8,
+    input_mint: &AccountInfo,
+    output_mint: &AccountInfo,
+    epoch: u64,
+) -> Result<TransferFeeAdjustedQuote> {
+    let net_amount_in = amount_in
+        .checked_sub(shared::mint_transfer_fee(input_mint, epoch, amount_in)?)
+        .ok_or(TradiumError::MathOverflow)?;
+    let SwapQuote {
+        amount_out,
+        fee_amount,
+    } = swap_preflight(
+        pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        net_amount_in,
+        0,
+        swap_direction,
+    )?;
+    let amount_received = amount_out
+        .checked_sub(shared::mint_transfer_fee(output_mint, epoch, amount_out)?)
+        .ok_or(TradiumError::MathOverflow)?;
+
+    // Check slippage protection
+    require!(
+        amount_received >= min_amount_out,
+        TradiumError::SlippageExceeded
+    );
+
+    Ok(TransferFeeAdjustedQuote {
+        net_amount_in,
+        amount_out,
+        amount_received,
+        fee_amount,
+    })
+}
+
+/// Input and output mints of a swap in `swap_direction`.
+fn swap_mints<'info>(
+    accounts: &Swap<'info>,
+    swap_direction: u8,
+) -> (AccountInfo<'info>, AccountInfo<'info>) {
+    if swap_direction == 0 {
         (
-            ctx.accounts.pool.coin_token_program,
-            ctx.accounts.pool.pc_token_program,
+            accounts.coin_mint.to_account_info(),
+            accounts.pc_mint.to_account_info(),
         )
     } else {
-        // PC to Coin swap
         (
-            ctx.accounts.pool.pc_token_program,
-            ctx.accounts.pool.coin_token_program,
+            accounts.pc_mint.to_account_info(),
+            accounts.coin_mint.to_account_info(),
         )
+    }
+}
+
+fn create_output_token_account(accounts: &Swap, swap_direction: u8) -> Result<()> {
+    let associated_token_program = accounts
+        .associated_token_program
+        .as_ref()
+        .ok_or(TradiumError::MissingAssociatedTokenAccounts)?;
+    let system_program = accounts
+        .system_program
+        .as_ref()
+        .ok_or(TradiumError::MissingAssociatedTokenAccounts)?;
+    let output_mint = if swap_direction == 0 {
+        &accounts.pc_mint
+    } else {
+        &accounts.coin_mint
     };
 
-    require!(
-        ctx.accounts.input_token_program.key() == input_program_expected,
-        TradiumError::InvalidTokenProgram
+    associated_token::create(CpiContext::new(
+        associated_token_program.to_account_info(),
+        associated_token::Create {
+            payer: accounts.user.to_account_info(),
+            associated_token: accounts.user_output_token_account.to_account_info(),
+            authority: accounts.user.to_account_info(),
+            mint: output_mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: accounts.output_token_program.to_account_info(),
+        },
+    ))
+}
+
+/// Fails with `InvalidInputTokenAccount` unless `user` owns the input token
+/// account. The token program would also accept a delegate with a standing
+/// approval as the transfer authority, letting a swap spend an account the
+/// signer does not own; user-initiated swaps only spend the signer's own.
+pub(crate) fn check_input_authority(input: &TokenInterfaceAccount, user: &Pubkey) -> Result<()> {
+    require_keys_eq!(input.owner, *user, TradiumError::InvalidInputTokenAccount);
+    Ok(())
+}
+
+fn validate_output_token_account(accounts: &Swap, swap_direction: u8) -> Result<()> {
+    let output_mint = if swap_direction == 0 {
+        accounts.pc_mint.key()
+    } else {
+        accounts.coin_mint.key()
+    };
+
+    let account_info = accounts.user_output_token_account.to_account_info();
+    require_keys_eq!(
+        *account_info.owner,
+        accounts.output_token_program.key(),
+        TradiumError::InvalidOutputTokenAccount
     );
-    require!(
-        ctx.accounts.output_token_program.key() == output_program_expected,
-        TradiumError::InvalidTokenProgram
+    let output_account =
+        TokenInterfaceAccount::try_deserialize(&mut &account_info.try_borrow_data()?[..])?;
+    require_keys_eq!(
+        output_account.mint,
+        output_mint,
+        TradiumError::InvalidOutputTokenAccount
     );
 
-    // Execute the swap with transfers and state updates
-    execute_swap_transfers(ctx, amount_in, min_amount_out, swap_direction)?;
-
     Ok(())
 }
 
-fn execute_swap_transfers(
-    mut ctx: Context<Swap>,
+fn execute_swap_transfers<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
     amount_in: u64,
     minimum_amount_out: u64,
     swap_direction: u8,
-) -> Result<()> {
-    // Extract keys for seeds to avoid mutable borrow conflicts
-    let coin_vault_mint_key = ctx.accounts.coin_mint.key();
-    let pc_vault_mint_key = ctx.accounts.pc_mint.key();
-    let pool_nonce_slice: &[u8] = &ctx.accounts.pool.nonce;
-
-    // Get vault balances before swap
-    let coin_vault_balance = ctx.accounts.coin_vault.amount;
-    let pc_vault_balance = ctx.accounts.pc_vault.amount;
-
-    // Calculate amount_out based on swap direction
-    let amount_out = if swap_direction == 0 {
-        // Coin to PC swap - inline swap_coin_to_pc logic
-        let fee_numerator = ctx.accounts.pool.fees.swap_fee_numerator;
-        let fee_denominator = ctx.accounts.pool.fees.swap_fee_denominator;
-
-        // Apply fee to input amount
-        let amount_in_after_fee = amount_in
-            .checked_mul(
-                fee_denominator
-                    .checked_sub(fee_numerator)
-                    .ok_or(TradiumError::MathOverflow)?,
-            )
-            .ok_or(TradiumError::MathOverflow)?
-            .checked_div(fee_denominator)
-            .ok_or(TradiumError::MathOverflow)?;
-
-        // Calculate output amount: amount_out = (amount_in_after_fee * pc_balance) / (coin_balance + amount_in_after_fee)
-        let new_coin_balance = coin_vault_balance
-            .checked_add(amount_in_after_fee)
-            .ok_or(TradiumError::MathOverflow)?;
-        let calculated_amount_out = amount_in_after_fee
-            .checked_mul(pc_vault_balance)
-            .ok_or(TradiumError::MathOverflow)?
-            .checked_div(new_coin_balance)
-            .ok_or(TradiumError::MathOverflow)?;
-
-        // Ensure output amount doesn't exceed vault balance
-        require!(
-            calculated_amount_out <= pc_vault_balance,
-            TradiumError::InsufficientLiquidity
-        );
+) -> Result<SwapResult> {
+    // Vaults, reserves and fee before the swap, as its events report them
+    let before = SwapSnapshot::take(
+        &ctx.accounts.pool,
+        ctx.accounts.coin_vault.amount,
+        ctx.accounts.pc_vault.amount,
+    )?;
 
-        calculated_amount_out
-    } else {
-        // PC to Coin swap - inline swap_pc_to_coin logic
-        let fee_numerator = ctx.accounts.pool.fees.swap_fee_numerator;
-        let fee_denominator = ctx.accounts.pool.fees.swap_fee_denominator;
-
-        // Apply fee to input amount
-        let amount_in_after_fee = amount_in
-            .checked_mul(
-                fee_denominator
-                    .checked_sub(fee_numerator)
-                    .ok_or(TradiumError::MathOverflow)?,
-            )
-            .ok_or(TradiumError::MathOverflow)?
-            .checked_div(fee_denominator)
-            .ok_or(TradiumError::MathOverflow)?;
-
-        // Calculate output amount: amount_out = (amount_in_after_fee * coin_balance) / (pc_balance + amount_in_after_fee)
-        let new_pc_balance = pc_vault_balance
-            .checked_add(amount_in_after_fee)
-            .ok_or(TradiumError::MathOverflow)?;
-        let calculated_amount_out = amount_in_after_fee
-            .checked_mul(coin_vault_balance)
-            .ok_or(TradiumError::MathOverflow)?
-            .checked_div(new_pc_balance)
-            .ok_or(TradiumError::MathOverflow)?;
-
-        // Ensure output amount doesn't exceed vault balance
-        require!(
-            calculated_amount_out <= coin_vault_balance,
-            TradiumError::InsufficientLiquidity
-        );
+    let clock = Clock::get()?;
 
-        calculated_amount_out
-    };
+    // Calculate amount_out based on swap direction, with the same checks clients preflight.
+    // Transfer fees are netted out so the curve only sees what the vaults actually move.
+    let (input_mint, output_mint) = swap_mints(ctx.accounts, swap_direction);
 
-    // Check slippage protection
-    require!(
-        amount_out >= minimum_amount_out,
-        TradiumError::SlippageExceeded
-    );
+    // The output program matched the pool's config; the mint must agree with it too,
+    // or hook resolution (which probes the mint owner) and the transfer would disagree
+    check_mint_token_program(output_mint.owner, &ctx.accounts.output_token_program.key())?;
+
+    let TransferFeeAdjustedQuote {
+        net_amount_in,
+        amount_out,
+        amount_received,
+        fee_amount,
+    } = swap_preflight_with_transfer_fees(
+        &ctx.accounts.pool,
+        ctx.accounts.coin_vault.amount,
+        ctx.accounts.pc_vault.amount,
+        amount_in,
+        minimum_amount_out,
+        swap_direction,
+        &input_mint,
+        &output_mint,
+        clock.epoch,
+    )?;
+
+    // The owner's part of the fee is set aside; the rest, trade fee included, joins the reserves
+    let owner_fee = ctx.accounts.pool.fees.owner_fee_share(fee_amount)?;
+    let (coin_reserve_after, pc_reserve_after) = reserves_after(
+        before.coin_reserve,
+        before.pc_reserve,
+        net_amount_in
+            .checked_sub(owner_fee)
+            .ok_or(TradiumError::MathOverflow)?,
+        amount_out,
+        swap_direction,
+    )?;
+
+    // Cross-check the execution price against the oracle, when the pool configures one
+    oracle::check_swap_against_oracle(
+        &ctx.accounts.pool,
+        ctx.accounts.price_oracle.as_deref(),
+        curve::execution_price(net_amount_in, amount_out, swap_direction)?,
+        clock.slot,
+    )?;
+
+    // Resolve each mint's transfer hook once; the transfers reuse the result
+    let coin_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.coin_mint.to_account_info(),
+        ctx.accounts.coin_transfer_hook_program.as_ref(),
+    )?;
+    let pc_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.pc_mint.to_account_info(),
+        ctx.accounts.pc_transfer_hook_program.as_ref(),
+    )?;
+
+    // Resolve both transfers' hook accounts before either runs, so a malformed
+    // setup on the output side fails before the input has moved
+    let (input_vault, output_vault, input_hook, output_hook) = if swap_direction == 0 {
+        (
+            &ctx.accounts.coin_vault,
+            &ctx.accounts.pc_vault,
+            coin_hook,
+            pc_hook,
+        )
+    } else {
+        (
+            &ctx.accounts.pc_vault,
+            &ctx.accounts.coin_vault,
+            pc_hook,
+            coin_hook,
+        )
+    };
+    let (input_hook_program, output_hook_program) = if swap_direction == 0 {
+        (
+            ctx.accounts.coin_transfer_hook_program.as_ref(),
+            ctx.accounts.pc_transfer_hook_program.as_ref(),
+        )
+    } else {
+        (
+            ctx.accounts.pc_transfer_hook_program.as_ref(),
+            ctx.accounts.coin_transfer_hook_program.as_ref(),
+        )
+    };
+    shared::prevalidate_hook_transfer(
+        &ctx.accounts.input_token_program.to_account_info(),
+        &ctx.accounts.user_input_token_account.to_account_info(),
+        &input_vault.to_account_info(),
+        &input_mint,
+        &ctx.accounts.user.to_account_info(),
+        input_hook,
+        input_hook_program,
+        ctx.remaining_accounts,
+        amount_in,
+    )?;
+    shared::prevalidate_hook_transfer(
+        &ctx.accounts.output_token_program.to_account_info(),
+        &output_vault.to_account_info(),
+        &ctx.accounts.user_output_token_account.to_account_info(),
+        &output_mint,
+        &ctx.accounts.pool.to_account_info(),
+        output_hook,
+        output_hook_program,
+        ctx.remaining_accounts,
+        amount_out,
+    )?;
 
     // Construct signer seeds for pool-initiated transfers
-    let pool_seeds = &[
-        b"tradium",
-        coin_vault_mint_key.as_ref(),
-        pc_vault_mint_key.as_ref(),
-        pool_nonce_slice,
-    ];
+    let pool_seeds = ctx.accounts.pool.signer_seeds();
     let signer_seeds = &[&pool_seeds[..]];
 
     if swap_direction == 0 {
         // Coin to PC swap
 
         // Transfer input tokens (coin) from user to coin vault
-        transfer_tokens_with_hook_support(
+        shared::transfer_tokens_with_hook_support(
             &ctx.accounts.input_token_program,
             &ctx.accounts.user_input_token_account,
             &ctx.accounts.coin_vault,
+            &ctx.accounts.coin_mint.to_account_info(),
             &ctx.accounts.user.to_account_info(),
-            &ctx.accounts.coin_mint,
+            coin_hook,
             ctx.accounts.coin_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
             amount_in,
             None,
         )?;
 
         // Transfer output tokens (pc) from pc vault to user
-        transfer_tokens_with_hook_support(
+        shared::transfer_tokens_with_hook_support(
             &ctx.accounts.output_token_program,
             &ctx.accounts.pc_vault,
             &ctx.accounts.user_output_token_account,
+            &ctx.accounts.pc_mint.to_account_info(),
             &ctx.accounts.pool.to_account_info(),
-            &ctx.accounts.pc_mint,
+            pc_hook,
             ctx.accounts.pc_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
             amount_out,
             Some(signer_seeds),
         )?;
@@ -248,135 +702,1185 @@ fn execute_swap_transfers(
         // PC to Coin swap
 
         // Transfer input tokens (pc) from user to pc vault
-        transfer_tokens_with_hook_support(
+        shared::transfer_tokens_with_hook_support(
             &ctx.accounts.input_token_program,
             &ctx.accounts.user_input_token_account,
             &ctx.accounts.pc_vault,
+            &ctx.accounts.pc_mint.to_account_info(),
             &ctx.accounts.user.to_account_info(),
-            &ctx.accounts.pc_mint,
+            pc_hook,
             ctx.accounts.pc_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
             amount_in,
             None,
         )?;
 
         // Transfer output tokens (coin) from coin vault to user
-        transfer_tokens_with_hook_support(
+        shared::transfer_tokens_with_hook_support(
             &ctx.accounts.output_token_program,
             &ctx.accounts.coin_vault,
             &ctx.accounts.user_output_token_account,
+            &ctx.accounts.coin_mint.to_account_info(),
             &ctx.accounts.pool.to_account_info(),
-            &ctx.accounts.coin_mint,
+            coin_hook,
             ctx.accounts.coin_transfer_hook_program.as_ref(),
+            ctx.remaining_accounts,
             amount_out,
             Some(signer_seeds),
         )?;
     }
 
-    // Update pool nonce
-    ctx.accounts.pool.nonce[0] = ctx.accounts.pool.nonce[0]
-        .checked_add(1)
-        .ok_or(TradiumError::MathOverflow)?;
+    let timestamp = clock.unix_timestamp;
+    ctx.accounts
+        .pool
+        .accrue_owner_fee(swap_direction, owner_fee)?;
+    ctx.accounts.pool.state_data.record_swap_volume(
+        swap_direction,
+        net_amount_in,
+        amount_out,
+        fee_amount,
+    );
+    record_swap(&mut ctx.accounts.pool, timestamp);
+
+    let result = swap_result(
+        &ctx.accounts.pool,
+        amount_in,
+        amount_received,
+        swap_direction,
+        coin_reserve_after,
+        pc_reserve_after,
+    )?;
+
+    // Actual vault balances after the transfers, including any transfer fees
+    ctx.accounts.coin_vault.reload()?;
+    ctx.accounts.pc_vault.reload()?;
+    ctx.accounts
+        .pool
+        .record_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount);
+
+    emit!(swap_event(
+        ctx.accounts.pool.key(),
+        ctx.accounts.user.key(),
+        &before,
+        ctx.accounts.coin_vault.amount,
+        ctx.accounts.pc_vault.amount,
+        amount_in,
+        swap_direction,
+        &result,
+        timestamp,
+    ));
+    emit!(swap_receipt(
+        ctx.accounts.pool.key(),
+        ctx.accounts.user.key(),
+        &before,
+        amount_in,
+        swap_direction,
+        fee_amount,
+        &result,
+    )?);
 
-    msg!("Swap completed: {} -> {}", amount_in, amount_out);
+    msg!("Swap completed: {} -> {}", amount_in, amount_received);
 
+    Ok(result)
+}
+
+/// Pool state a swap is reported against, captured before the swap changes
+/// it. The fee is read up front because the swap may use up the last
+/// bootstrap swap, after which `effective_swap_fee` no longer matches.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct SwapSnapshot {
+    /// Raw vault balances, owed owner fees included
+    pub coin_vault: u64,
+    pub pc_vault: u64,
+    /// Active reserves the swap prices against
+    pub coin_reserve: u64,
+    pub pc_reserve: u64,
+    /// Fee this swap is charged
+    pub fee_numerator: u64,
+    pub fee_denominator: u64,
+}
+
+impl SwapSnapshot {
+    pub fn take(pool: &Tradium, coin_vault: u64, pc_vault: u64) -> Result<Self> {
+        let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault, pc_vault)?;
+        let (fee_numerator, fee_denominator) = pool.effective_swap_fee()?;
+        Ok(Self {
+            coin_vault,
+            pc_vault,
+            coin_reserve,
+            pc_reserve,
+            fee_numerator,
+            fee_denominator,
+        })
+    }
+}
+
+/// Return data of a swap of `amount_in` that paid the user `amount_out` and
+/// left the given active reserves.
+pub(crate) fn swap_result(
+    pool: &Tradium,
+    amount_in: u64,
+    amount_out: u64,
+    swap_direction: u8,
+    coin_reserve_after: u64,
+    pc_reserve_after: u64,
+) -> Result<SwapResult> {
+    Ok(SwapResult {
+        amount_out,
+        price_after: curve::spot_price(coin_reserve_after, pc_reserve_after)?,
+        execution_price: pool_execution_price(pool, amount_in, amount_out, swap_direction)?,
+        coin_reserve_after,
+        pc_reserve_after,
+    })
+}
+
+/// `SwapEvent` of a swap reported against `before`, with the vault balances
+/// reloaded after the transfers.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn swap_event(
+    pool: Pubkey,
+    user: Pubkey,
+    before: &SwapSnapshot,
+    coin_vault_after: u64,
+    pc_vault_after: u64,
+    amount_in: u64,
+    swap_direction: u8,
+    result: &SwapResult,
+    timestamp: i64,
+) -> SwapEvent {
+    SwapEvent {
+        pool,
+        user,
+        amount_in,
+        amount_out: result.amount_out,
+        swap_direction,
+        effective_fee_numerator: before.fee_numerator,
+        effective_fee_denominator: before.fee_denominator,
+        coin_vault_before: before.coin_vault,
+        pc_vault_before: before.pc_vault,
+        coin_vault_after,
+        pc_vault_after,
+        timestamp,
+        execution_price: result.execution_price,
+    }
+}
+
+/// `SwapReceiptEvent` of a swap reported against `before`.
+pub(crate) fn swap_receipt(
+    pool: Pubkey,
+    user: Pubkey,
+    before: &SwapSnapshot,
+    amount_in: u64,
+    swap_direction: u8,
+    fee_amount: u64,
+    result: &SwapResult,
+) -> Result<SwapReceiptEvent> {
+    Ok(SwapReceiptEvent {
+        pool,
+        user,
+        swap_direction,
+        amount_in,
+        amount_out: result.amount_out,
+        fee_amount,
+        price_before: curve::spot_price(before.coin_reserve, before.pc_reserve)?,
+        price_after: result.price_after,
+        coin_reserve_after: result.coin_reserve_after,
+        pc_reserve_after: result.pc_reserve_after,
+    })
+}
+
+/// On pools with `auto_compound_fees` set, folds the owed owner fees into the
+/// reserves so the swap prices against them. Returns what was compounded.
+pub(crate) fn auto_compound_before_swap(pool: &mut Tradium) -> (u64, u64) {
+    if !pool.auto_compound_fees {
+        return (0, 0);
+    }
+    let (coin_compounded, pc_compounded) = pool.compound_owed_fees();
+    if coin_compounded > 0 || pc_compounded > 0 {
+        msg!(
+            "Fees compounded before swap: coin {}, pc {}",
+            coin_compounded,
+            pc_compounded
+        );
+    }
+    (coin_compounded, pc_compounded)
+}
+
+/// Fails with `DeadlineExceeded` once `now` is past the caller's `deadline`;
+/// `i64::MAX` never expires.
+pub(crate) fn check_deadline(now: i64, deadline: i64) -> Result<()> {
+    require!(now <= deadline, TradiumError::DeadlineExceeded);
     Ok(())
 }
 
-// Inline the transfer_tokens_with_hook_support function
-fn transfer_tokens_with_hook_support<'info>(
-    token_program: &Interface<'info, TokenInterface>,
-    from: &InterfaceAccount<'info, TokenInterfaceAccount>,
-    to: &InterfaceAccount<'info, TokenInterfaceAccount>,
-    authority: &AccountInfo<'info>,
-    mint: &InterfaceAccount<'info, Mint>,
-    transfer_hook_program: Option<&UncheckedAccount<'info>>,
-    amount: u64,
-    signer_seeds: Option<&[&[&[u8]]]>,
+/// Pool bookkeeping after a swap: consumes one bootstrap swap, if any remain,
+/// and advances the counter and activity timestamp. The bump is left alone.
+pub(crate) fn record_swap(pool: &mut Tradium, now: i64) {
+    pool.bootstrap_swaps_remaining = pool.bootstrap_swaps_remaining.saturating_sub(1);
+    pool.advance_counter();
+    pool.record_activity(now);
+}
+
+/// What the user paid per whole coin at the pool's decimals, scaled by
+/// `curve::PRICE_SCALE`.
+pub(crate) fn pool_execution_price(
+    pool: &Tradium,
+    amount_in: u64,
+    amount_out: u64,
+    swap_direction: u8,
+) -> Result<u128> {
+    curve::scaled_execution_price(
+        amount_in,
+        amount_out,
+        swap_direction,
+        pool.coin_decimals,
+        pool.pc_decimals,
+    )
+}
+
+/// The input and output token programs must be the pool's programs for each
+/// side. In a pool whose mints share a program the same account may be passed
+/// for both; neither is writable, so the CPIs never alias mutable state.
+pub(crate) fn check_swap_token_programs(
+    pool: &Tradium,
+    input_token_program: &Pubkey,
+    output_token_program: &Pubkey,
+    swap_direction: u8,
 ) -> Result<()> {
-    let mut remaining_accounts = Vec::new();
-
-    // Check if mint has transfer hook extension
-    if mint.to_account_info().owner == &spl_token_2022::ID {
-        if let Ok(mint_data) = mint.to_account_info().try_borrow_data() {
-            if let Ok(mint_with_extensions) =
-                StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
-            {
-                if let Ok(transfer_hook) = mint_with_extensions.get_extension::<TransferHook>() {
-                    if let Some(hook_program_id) = Option::<Pubkey>::from(transfer_hook.program_id)
-                    {
-                        if let Some(hook_program) = transfer_hook_program {
-                            require!(
-                                hook_program.key() == hook_program_id,
-                                TradiumError::InvalidTransferHookProgram
-                            );
-                            remaining_accounts.push(hook_program.to_account_info());
-                        }
-                    }
-                }
-            }
-        }
+    let (input_program_expected, output_program_expected) = if swap_direction == 0 {
+        // Coin to PC swap
+        (pool.coin_token_program, pool.pc_token_program)
+    } else {
+        // PC to Coin swap
+        (pool.pc_token_program, pool.coin_token_program)
+    };
+
+    require_keys_eq!(
+        *input_token_program,
+        input_program_expected,
+        TradiumError::InvalidTokenProgram
+    );
+    require_keys_eq!(
+        *output_token_program,
+        output_program_expected,
+        TradiumError::InvalidTokenProgram
+    );
+    Ok(())
+}
+
+/// The mint must be owned by the token program its transfer is sent through.
+pub(crate) fn check_mint_token_program(mint_owner: &Pubkey, token_program: &Pubkey) -> Result<()> {
+    require_keys_eq!(
+        *mint_owner,
+        *token_program,
+        TradiumError::InvalidTokenProgram
+    );
+    Ok(())
+}
+
+/// Active reserves after a swap, given the part of the input that stays in them.
+pub(crate) fn reserves_after(
+    coin_reserve: u64,
+    pc_reserve: u64,
+    amount_in: u64,
+    amount_out: u64,
+    swap_direction: u8,
+) -> Result<(u64, u64)> {
+    if swap_direction == 0 {
+        Ok((
+            coin_reserve
+                .checked_add(amount_in)
+                .ok_or(TradiumError::MathOverflow)?,
+            pc_reserve
+                .checked_sub(amount_out)
+                .ok_or(TradiumError::MathOverflow)?,
+        ))
+    } else {
+        Ok((
+            coin_reserve
+                .checked_sub(amount_out)
+                .ok_or(TradiumError::MathOverflow)?,
+            pc_reserve
+                .checked_add(amount_in)
+                .ok_or(TradiumError::MathOverflow)?,
+        ))
     }
+}
 
-    let cpi_accounts = anchor_spl::token_interface::Transfer {
-        from: from.to_account_info(),
-        to: to.to_account_info(),
-        authority: authority.clone(),
+/// Returns whether the swap counts as large against the threshold for its
+/// input side, failing with `SwapThrottled` if another large swap happened
+/// within the pool's slot delay.
+pub(crate) fn is_throttled_large_swap(
+    pool: &Tradium,
+    amount_in: u64,
+    swap_direction: u8,
+    current_slot: u64,
+) -> Result<bool> {
+    let threshold = if swap_direction == 0 {
+        pool.large_swap_threshold_coin
+    } else {
+        pool.large_swap_threshold_pc
     };
+    if threshold == 0 || pool.large_swap_slot_delay == 0 || amount_in < threshold {
+        return Ok(false);
+    }
+    if pool.last_large_swap_slot != 0 {
+        let elapsed = current_slot.saturating_sub(pool.last_large_swap_slot);
+        require!(
+            elapsed >= pool.large_swap_slot_delay,
+            TradiumError::SwapThrottled
+        );
+    }
+    Ok(true)
+}
 
-    let cpi_ctx = if let Some(seeds) = signer_seeds {
-        CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, seeds)
-            .with_remaining_accounts(remaining_accounts)
+/// Fails with `OutputCapExceeded` if `amount_out` is above the pool's
+/// per-swap cap for the output side.
+pub(crate) fn check_output_cap(pool: &Tradium, amount_out: u64, swap_direction: u8) -> Result<()> {
+    let cap = if swap_direction == 0 {
+        pool.max_output_per_swap_pc
     } else {
-        CpiContext::new(token_program.to_account_info(), cpi_accounts)
-            .with_remaining_accounts(remaining_accounts)
+        pool.max_output_per_swap_coin
     };
+    require!(
+        cap == 0 || amount_out <= cap,
+        TradiumError::OutputCapExceeded
+    );
+    Ok(())
+}
 
-    anchor_spl::token_interface::transfer(cpi_ctx, amount)?;
+/// Swap outcome set as the instruction's return data. `price_after` is the
+/// post-swap spot price, pc per coin scaled by `curve::PRICE_SCALE`; the
+/// post-swap reserves let routers price the next hop. `execution_price` is
+/// the average price paid, see `curve::scaled_execution_price`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct SwapResult {
+    pub amount_out: u64,
+    pub price_after: u128,
+    pub execution_price: u128,
+    pub coin_reserve_after: u64,
+    pub pc_reserve_after: u64,
+}
 
-    Ok(())
+/// Return data of `swap_exact_out`: the input actually charged, what was
+/// left of `max_amount_in`, and the usual swap outcome.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ExactOutSwapResult {
+    pub amount_in_used: u64,
+    pub amount_in_unused: u64,
+    pub swap: SwapResult,
 }
 
-// Inline the validate_transfer_hook_program function
-fn validate_transfer_hook_program(
-    mint: &InterfaceAccount<Mint>,
-    transfer_hook_program: &AccountInfo,
-    whitelisted_hooks: &[Pubkey],
-    num_whitelisted: u8,
-) -> bool {
-    // If no transfer hook program is provided, it's valid (no hook required)
-    if transfer_hook_program.key() == Pubkey::default() {
-        return true;
-    }
-
-    // Check if the mint actually has a transfer hook
-    if mint.to_account_info().owner == &spl_token_2022::ID {
-        if let Ok(mint_data) = mint.to_account_info().try_borrow_data() {
-            if let Ok(mint_with_extensions) =
-                StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
-            {
-                if let Ok(transfer_hook) = mint_with_extensions.get_extension::<TransferHook>() {
-                    if let Some(hook_program_id) = Option::<Pubkey>::from(transfer_hook.program_id)
-                    {
-                        // Verify the provided program matches the mint's hook
-                        if transfer_hook_program.key() != hook_program_id {
-                            return false;
-                        }
-
-                        // Check if the hook program is whitelisted
-                        for i in 0..(num_whitelisted as usize) {
-                            if i < whitelisted_hooks.len()
-                                && whitelisted_hooks[i] == hook_program_id
-                            {
-                                return true;
-                            }
-                        }
-                        return false; // Hook program not whitelisted
-                    }
-                }
+#[event]
+pub struct SwapExactOutEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_out: u64,
+    pub amount_in_used: u64,
+    pub max_amount_in: u64,
+}
+
+#[event]
+pub struct SwapEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub amount_in: u64,
+    /// Output the user received, net of any output transfer fee
+    pub amount_out: u64,
+    pub swap_direction: u8,
+    pub effective_fee_numerator: u64,
+    pub effective_fee_denominator: u64,
+    /// Raw vault balances around the swap, owed owner fees included
+    pub coin_vault_before: u64,
+    pub pc_vault_before: u64,
+    pub coin_vault_after: u64,
+    pub pc_vault_after: u64,
+    pub timestamp: i64,
+    /// Whole pc per whole coin, scaled by `curve::PRICE_SCALE`
+    pub execution_price: u128,
+}
+
+/// Full execution receipt for a swap. Prices are pc per coin scaled by
+/// `curve::PRICE_SCALE`.
+#[event]
+pub struct SwapReceiptEvent {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub swap_direction: u8,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub price_before: u128,
+    pub price_after: u128,
+    pub coin_reserve_after: u64,
+    pub pc_reserve_after: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "token-2022")]
+    use crate::shared::TestAccount;
+
+    /// Runs `swap`'s pricing, accounting and reporting for `amount_in` into
+    /// the given raw vault balances, without the transfers
+    fn report_swap(
+        pool: &mut Tradium,
+        coin_vault: u64,
+        pc_vault: u64,
+        amount_in: u64,
+        swap_direction: u8,
+    ) -> (SwapSnapshot, SwapQuote, SwapResult) {
+        let before = SwapSnapshot::take(pool, coin_vault, pc_vault).unwrap();
+        let quote =
+            swap_preflight(pool, coin_vault, pc_vault, amount_in, 0, swap_direction).unwrap();
+        let owner_fee = pool.fees.owner_fee_share(quote.fee_amount).unwrap();
+        let (coin_reserve_after, pc_reserve_after) = reserves_after(
+            before.coin_reserve,
+            before.pc_reserve,
+            amount_in - owner_fee,
+            quote.amount_out,
+            swap_direction,
+        )
+        .unwrap();
+        pool.accrue_owner_fee(swap_direction, owner_fee).unwrap();
+        record_swap(pool, 1_700_000_000);
+        let result = swap_result(
+            pool,
+            amount_in,
+            quote.amount_out,
+            swap_direction,
+            coin_reserve_after,
+            pc_reserve_after,
+        )
+        .unwrap();
+        (before, quote, result)
+    }
+
+    #[test]
+    fn swap_event_reports_raw_vaults_around_the_swap() {
+        let mut pool = preflight_pool();
+        pool.coin_decimals = 6;
+        pool.pc_decimals = 6;
+        // Owed fees sit in the vaults but outside the reserves
+        pool.coin_fees_owed = 70;
+        let (before, quote, result) = report_swap(&mut pool, 1_000_070, 1_000_000, 10_000, 0);
+        assert_eq!(
+            (before.coin_reserve, before.pc_reserve),
+            (1_000_000, 1_000_000)
+        );
+
+        let (pool_key, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (coin_vault_after, pc_vault_after) = (1_010_070, 1_000_000 - quote.amount_out);
+        let event = swap_event(
+            pool_key,
+            user,
+            &before,
+            coin_vault_after,
+            pc_vault_after,
+            10_000,
+            0,
+            &result,
+            1_700_000_000,
+        );
+
+        let decoded = SwapEvent::try_from_slice(&event.try_to_vec().unwrap()).unwrap();
+        assert_eq!((decoded.pool, decoded.user), (pool_key, user));
+        assert_eq!((decoded.amount_in, decoded.amount_out), (10_000, 9_896));
+        assert_eq!(
+            (
+                decoded.effective_fee_numerator,
+                decoded.effective_fee_denominator
+            ),
+            (5, 10_000)
+        );
+        assert_eq!(
+            (decoded.coin_vault_before, decoded.pc_vault_before),
+            (1_000_070, 1_000_000)
+        );
+        assert_eq!(
+            (decoded.coin_vault_after, decoded.pc_vault_after),
+            (1_010_070, 990_104)
+        );
+        assert_eq!(decoded.timestamp, 1_700_000_000);
+        // 9_896 pc for 10_000 coin at equal decimals
+        assert_eq!(decoded.execution_price, 989_600_000_000);
+    }
+
+    #[test]
+    fn swap_event_reports_a_zero_fee_during_a_free_bootstrap() {
+        let mut pool = preflight_pool();
+        pool.fees.trade_fee_numerator = 30;
+        pool.fees.trade_fee_denominator = 10_000;
+        pool.bootstrap_swaps_remaining = 1;
+        pool.bootstrap_fee_numerator = 0;
+
+        // The bootstrap fee stands in for both the trade and the swap fee
+        let before = SwapSnapshot::take(&pool, 1_000_000, 1_000_000).unwrap();
+        let quote = swap_preflight(&pool, 1_000_000, 1_000_000, 10_000, 0, 0).unwrap();
+        assert_eq!(quote.fee_amount, 0);
+
+        // The swap uses up the last bootstrap swap before its event is built
+        record_swap(&mut pool, 1_700_000_000);
+        assert_eq!(pool.bootstrap_swaps_remaining, 0);
+        let (coin_reserve_after, pc_reserve_after) =
+            reserves_after(1_000_000, 1_000_000, 10_000, quote.amount_out, 0).unwrap();
+        let result = swap_result(
+            &pool,
+            10_000,
+            quote.amount_out,
+            0,
+            coin_reserve_after,
+            pc_reserve_after,
+        )
+        .unwrap();
+        let event = swap_event(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &before,
+            coin_reserve_after,
+            pc_reserve_after,
+            10_000,
+            0,
+            &result,
+            1_700_000_000,
+        );
+        assert_eq!(
+            (
+                event.effective_fee_numerator,
+                event.effective_fee_denominator
+            ),
+            (0, 10_000)
+        );
+
+        // The next swap is charged, and reported, the full fee again
+        let next = SwapSnapshot::take(&pool, coin_reserve_after, pc_reserve_after).unwrap();
+        assert_eq!((next.fee_numerator, next.fee_denominator), (35, 10_000));
+    }
+
+    #[test]
+    fn swap_receipt_prices_the_reserves_around_the_swap() {
+        let mut pool = preflight_pool();
+        let (before, quote, result) = report_swap(&mut pool, 1_000_000, 1_000_000, 10_000, 1);
+        let (pool_key, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let receipt = swap_receipt(
+            pool_key,
+            user,
+            &before,
+            10_000,
+            1,
+            quote.fee_amount,
+            &result,
+        )
+        .unwrap();
+
+        let decoded = SwapReceiptEvent::try_from_slice(&receipt.try_to_vec().unwrap()).unwrap();
+        assert_eq!((decoded.pool, decoded.user), (pool_key, user));
+        assert_eq!(decoded.swap_direction, 1);
+        assert_eq!((decoded.amount_in, decoded.amount_out), (10_000, 9_896));
+        assert_eq!(decoded.fee_amount, 5);
+        // Balanced reserves price at one pc per coin before the swap
+        assert_eq!(decoded.price_before, curve::PRICE_SCALE);
+        // The owner's fee is set aside, the rest of the pc joins the reserves
+        assert_eq!(
+            (decoded.coin_reserve_after, decoded.pc_reserve_after),
+            (990_104, 1_009_995)
+        );
+        assert_eq!(
+            decoded.price_after,
+            curve::spot_price(990_104, 1_009_995).unwrap()
+        );
+        assert!(decoded.price_after > decoded.price_before);
+    }
+
+    #[test]
+    fn second_large_swap_in_window_is_throttled() {
+        let pool = Tradium {
+            large_swap_threshold_coin: 1_000,
+            large_swap_slot_delay: 5,
+            last_large_swap_slot: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            is_throttled_large_swap(&pool, 1_000, 0, 100).unwrap_err(),
+            TradiumError::SwapThrottled.into()
+        );
+        assert_eq!(
+            is_throttled_large_swap(&pool, 5_000, 0, 104).unwrap_err(),
+            TradiumError::SwapThrottled.into()
+        );
+        assert!(is_throttled_large_swap(&pool, 1_000, 0, 105).unwrap());
+    }
+
+    #[test]
+    fn large_swap_threshold_follows_the_input_side() {
+        // 1 coin is worth 1_000 pc, so the thresholds match in value
+        let pool = Tradium {
+            large_swap_threshold_coin: 10,
+            large_swap_threshold_pc: 10_000,
+            large_swap_slot_delay: 5,
+            last_large_swap_slot: 100,
+            ..Default::default()
+        };
+        assert_eq!(
+            is_throttled_large_swap(&pool, 10, 0, 100).unwrap_err(),
+            TradiumError::SwapThrottled.into()
+        );
+        // The same raw amount of pc is a small swap
+        assert!(!is_throttled_large_swap(&pool, 10, 1, 100).unwrap());
+        assert_eq!(
+            is_throttled_large_swap(&pool, 10_000, 1, 100).unwrap_err(),
+            TradiumError::SwapThrottled.into()
+        );
+
+        // A zero threshold only disables its own side
+        let coin_only = Tradium {
+            large_swap_threshold_pc: 0,
+            ..pool
+        };
+        assert!(!is_throttled_large_swap(&coin_only, u64::MAX, 1, 100).unwrap());
+        assert!(is_throttled_large_swap(&coin_only, 10, 0, 105).unwrap());
+    }
+
+    #[test]
+    fn small_swap_in_same_slot_is_allowed() {
+        let pool = Tradium {
+            large_swap_threshold_coin: 1_000,
+            large_swap_slot_delay: 5,
+            last_large_swap_slot: 100,
+            ..Default::default()
+        };
+        assert!(!is_throttled_large_swap(&pool, 999, 0, 100).unwrap());
+
+        // Throttle disabled
+        let pool = Tradium {
+            last_large_swap_slot: 100,
+            ..Default::default()
+        };
+        assert!(!is_throttled_large_swap(&pool, u64::MAX, 0, 100).unwrap());
+    }
+
+    #[test]
+    fn swap_result_returns_amount_out_and_post_swap_price() {
+        // 10_000 coin into a 1_000_000 / 1_000_000 pool at the default fee
+        let mut pool = preflight_pool();
+        pool.coin_decimals = 6;
+        pool.pc_decimals = 6;
+        let (_, _, result) = report_swap(&mut pool, 1_000_000, 1_000_000, 10_000, 0);
+
+        let decoded = SwapResult::try_from_slice(&result.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded, result);
+        assert_eq!(result.amount_out, 9_896);
+        // The owner's 5 is set aside, the rest of the input joins the reserves
+        assert_eq!(
+            (result.coin_reserve_after, result.pc_reserve_after),
+            (1_009_995, 990_104)
+        );
+        assert_eq!(
+            result.price_after,
+            curve::spot_price(1_009_995, 990_104).unwrap()
+        );
+        // Under one pc per coin, and amount_out / amount_in within rounding
+        let price = result.execution_price;
+        assert!(price < curve::PRICE_SCALE);
+        assert!(price * 10_000 <= 9_896 * curve::PRICE_SCALE);
+        assert!(9_896 * curve::PRICE_SCALE < (price + 1) * 10_000);
+    }
+
+    #[test]
+    fn slippage_bps_matches_absolute_minimum() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 5;
+        pool.fees.swap_fee_denominator = 10_000;
+
+        let expected = quote_amount_out(&pool, 1_000_000, 1_000_000, 10_000, 0).unwrap();
+        assert_eq!(
+            expected,
+            curve::get_amount_out(10_000, 1_000_000, 1_000_000, 5, 10_000).unwrap()
+        );
+
+        // 0 bps is the exact quote, 1% is the quote less 1%, rounded down
+        assert_eq!(
+            curve::min_amount_out_from_bps(expected, 0).unwrap(),
+            expected
+        );
+        assert_eq!(
+            curve::min_amount_out_from_bps(expected, 100).unwrap(),
+            9_797
+        );
+        assert_eq!(curve::min_amount_out_from_bps(expected, 10_000).unwrap(), 0);
+        assert_eq!(
+            curve::min_amount_out_from_bps(expected, 10_001).unwrap_err(),
+            TradiumError::InvalidSlippageBps.into()
+        );
+    }
+
+    fn preflight_pool() -> Tradium {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 5;
+        pool.fees.swap_fee_denominator = 10_000;
+        pool
+    }
+
+    #[test]
+    fn trade_fee_stays_in_the_reserves_and_only_the_owner_fee_accrues() {
+        let mut pool = preflight_pool();
+        pool.fees.trade_fee_numerator = 30;
+        pool.fees.trade_fee_denominator = 10_000;
+
+        // 0.3% trade fee plus 0.05% swap fee on 10_000 in
+        let quote = swap_preflight(&pool, 1_000_000, 1_000_000, 10_000, 0, 0).unwrap();
+        assert_eq!(quote.fee_amount, 35);
+        let owner_fee = pool.fees.owner_fee_share(quote.fee_amount).unwrap();
+        assert_eq!(owner_fee, 5);
+
+        pool.accrue_owner_fee(0, owner_fee).unwrap();
+        assert_eq!((pool.coin_fees_owed, pool.pc_fees_owed), (5, 0));
+        let (coin_reserve_after, _) = reserves_after(
+            1_000_000,
+            1_000_000,
+            10_000 - owner_fee,
+            quote.amount_out,
+            0,
+        )
+        .unwrap();
+        assert_eq!(coin_reserve_after, 1_009_995);
+    }
+
+    #[test]
+    fn preflight_quotes_a_valid_swap() {
+        let pool = preflight_pool();
+        assert_eq!(
+            swap_preflight(&pool, 1_000_000, 1_000_000, 10_000, 9_896, 0).unwrap(),
+            SwapQuote {
+                amount_out: 9_896,
+                fee_amount: 5,
             }
+        );
+    }
+
+    #[test]
+    fn preflight_reports_each_failure_reason() {
+        let pool = preflight_pool();
+        let fails = |coin, pc, amount_in, min_out, direction| {
+            swap_preflight(&pool, coin, pc, amount_in, min_out, direction).unwrap_err()
+        };
+
+        assert_eq!(
+            fails(1_000_000, 1_000_000, 10_000, 0, 2),
+            TradiumError::InvalidSwapDirection.into()
+        );
+        assert_eq!(
+            fails(1_000_000, 1_000_000, 0, 0, 0),
+            TradiumError::InvalidInputAmount.into()
+        );
+        assert_eq!(
+            fails(0, 1_000_000, 10_000, 0, 0),
+            TradiumError::EmptyPool.into()
+        );
+        assert_eq!(
+            fails(1_000_000, 1_000, 1, 0, 0),
+            TradiumError::ZeroSwapOutput.into()
+        );
+        assert_eq!(
+            fails(1_000_000, 1_000_000, 10_000, 9_897, 0),
+            TradiumError::SlippageExceeded.into()
+        );
+
+        // Owed fees larger than the vault make the pool state unusable
+        let owing_pool = Tradium {
+            coin_fees_owed: 1,
+            ..preflight_pool()
+        };
+        assert_eq!(
+            swap_preflight(&owing_pool, 0, 1_000_000, 10_000, 0, 0).unwrap_err(),
+            TradiumError::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn output_cap_applies_to_the_output_side() {
+        let pool = Tradium {
+            max_output_per_swap_coin: 500,
+            max_output_per_swap_pc: 1_000,
+            ..Default::default()
+        };
+
+        // Coin to pc pays out pc
+        assert!(check_output_cap(&pool, 1_000, 0).is_ok());
+        assert_eq!(
+            check_output_cap(&pool, 1_001, 0).unwrap_err(),
+            TradiumError::OutputCapExceeded.into()
+        );
+
+        // Pc to coin pays out coin
+        assert!(check_output_cap(&pool, 500, 1).is_ok());
+        assert_eq!(
+            check_output_cap(&pool, 501, 1).unwrap_err(),
+            TradiumError::OutputCapExceeded.into()
+        );
+    }
+
+    #[test]
+    fn zero_output_cap_is_unlimited() {
+        let pool = Tradium::default();
+        assert!(check_output_cap(&pool, u64::MAX, 0).is_ok());
+        assert!(check_output_cap(&pool, u64::MAX, 1).is_ok());
+    }
+
+    #[test]
+    fn exact_out_quote_covers_the_output_in_both_directions() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+        let (coin_vault, pc_vault) = (2_000_000, 5_000_000);
+
+        for swap_direction in [0, 1] {
+            let amount_in =
+                quote_amount_in(&pool, coin_vault, pc_vault, 40_000, swap_direction).unwrap();
+            let amount_out =
+                quote_amount_out(&pool, coin_vault, pc_vault, amount_in, swap_direction).unwrap();
+            assert!(amount_out >= 40_000);
+            // The quoted input goes through preflight with the exact output as minimum
+            assert!(swap_preflight(
+                &pool,
+                coin_vault,
+                pc_vault,
+                amount_in,
+                40_000,
+                swap_direction
+            )
+            .is_ok());
         }
     }
 
-    // If we can't read the mint data or there's no hook, the program shouldn't be provided
-    transfer_hook_program.key() == Pubkey::default()
+    #[test]
+    fn exact_out_rejects_draining_the_output_vault() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_denominator = 10_000;
+
+        // Coin to pc drains the pc vault, pc to coin the coin vault
+        assert_eq!(
+            quote_amount_in(&pool, 2_000_000, 5_000_000, 5_000_000, 0).unwrap_err(),
+            TradiumError::InsufficientLiquidity.into()
+        );
+        assert_eq!(
+            quote_amount_in(&pool, 2_000_000, 5_000_000, 2_000_000, 1).unwrap_err(),
+            TradiumError::InsufficientLiquidity.into()
+        );
+    }
+
+    #[test]
+    fn pool_can_still_sign_after_two_swaps() {
+        let (coin_mint, pc_mint) =
+            crate::pda::canonical_mint_order(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let (pool_key, bump) = crate::pda::pool_pda(&coin_mint, &pc_mint);
+        let mut pool = Tradium {
+            coin_vault_mint: coin_mint,
+            pc_vault_mint: pc_mint,
+            bump,
+            nonce: [bump],
+            bootstrap_swaps_remaining: 1,
+            ..Default::default()
+        };
+
+        for now in [1_000, 1_001] {
+            record_swap(&mut pool, now);
+            assert_eq!(
+                Pubkey::create_program_address(&pool.signer_seeds(), &crate::ID).unwrap(),
+                pool_key
+            );
+        }
+        assert_eq!(pool.bump, bump);
+        assert_eq!(pool.counter, 2);
+        assert_eq!(pool.bootstrap_swaps_remaining, 0);
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn transfer_fees_are_netted_out_of_both_legs() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+        let (coin_vault, pc_vault) = (2_000_000, 5_000_000);
+
+        // Both mints charge 1% on every transfer
+        let token_2022 = spl_token_2022::ID;
+        let (coin_key, pc_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut coin_mint_account =
+            TestAccount::new(coin_key, token_2022, shared::transfer_fee_mint_data(100));
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account =
+            TestAccount::new(pc_key, token_2022, shared::transfer_fee_mint_data(100));
+        let pc_mint = pc_mint_account.info();
+
+        let quote = swap_preflight_with_transfer_fees(
+            &pool, coin_vault, pc_vault, 100_000, 0, 0, &coin_mint, &pc_mint, 0,
+        )
+        .unwrap();
+        assert_eq!(quote.net_amount_in, 99_000);
+        assert_eq!(
+            quote.amount_out,
+            quote_amount_out(&pool, coin_vault, pc_vault, 99_000, 0).unwrap()
+        );
+        assert_eq!(
+            quote.amount_received,
+            quote.amount_out - (quote.amount_out * 100).div_ceil(10_000)
+        );
+        // Pricing from the nominal input would pay out more than the vault receives
+        assert!(
+            quote_amount_out(&pool, coin_vault, pc_vault, 100_000, 0).unwrap() > quote.amount_out
+        );
+
+        // Slippage is checked against what the user actually receives
+        assert_eq!(
+            swap_preflight_with_transfer_fees(
+                &pool,
+                coin_vault,
+                pc_vault,
+                100_000,
+                quote.amount_received + 1,
+                0,
+                &coin_mint,
+                &pc_mint,
+                0,
+            )
+            .unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+
+        // Exact-out grosses up both legs so the requested amount still arrives
+        let amount_in = quote_amount_in_with_transfer_fees(
+            &pool, coin_vault, pc_vault, 40_000, 1, &pc_mint, &coin_mint, 0,
+        )
+        .unwrap();
+        assert!(quote_amount_in(&pool, coin_vault, pc_vault, 40_000, 1).unwrap() < amount_in);
+        let quote = swap_preflight_with_transfer_fees(
+            &pool, coin_vault, pc_vault, amount_in, 40_000, 1, &pc_mint, &coin_mint, 0,
+        )
+        .unwrap();
+        assert!(quote.amount_received >= 40_000);
+
+        // A bps tolerance is taken off what arrives, so even 0 bps passes
+        let quote = swap_preflight_with_transfer_fees(
+            &pool, coin_vault, pc_vault, 100_000, 0, 0, &coin_mint, &pc_mint, 0,
+        )
+        .unwrap();
+        let min_amount_out = slippage_bps_min_amount_out(
+            &pool, coin_vault, pc_vault, 100_000, 0, 0, &coin_mint, &pc_mint, 0,
+        )
+        .unwrap();
+        assert_eq!(min_amount_out, quote.amount_received);
+        assert!(swap_preflight_with_transfer_fees(
+            &pool,
+            coin_vault,
+            pc_vault,
+            100_000,
+            min_amount_out,
+            0,
+            &coin_mint,
+            &pc_mint,
+            0,
+        )
+        .is_ok());
+        assert_eq!(
+            slippage_bps_min_amount_out(
+                &pool, coin_vault, pc_vault, 100_000, 100, 0, &coin_mint, &pc_mint, 0,
+            )
+            .unwrap(),
+            curve::min_amount_out_from_bps(quote.amount_received, 100).unwrap()
+        );
+    }
+
+    #[test]
+    fn output_mint_must_match_output_token_program() {
+        use crate::constants::{SPL_TOKEN_2022_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID};
+
+        assert!(check_mint_token_program(&SPL_TOKEN_PROGRAM_ID, &SPL_TOKEN_PROGRAM_ID).is_ok());
+        assert!(
+            check_mint_token_program(&SPL_TOKEN_2022_PROGRAM_ID, &SPL_TOKEN_2022_PROGRAM_ID)
+                .is_ok()
+        );
+
+        // A Token-2022 mint routed through the SPL program, and the reverse
+        assert_eq!(
+            check_mint_token_program(&SPL_TOKEN_2022_PROGRAM_ID, &SPL_TOKEN_PROGRAM_ID)
+                .unwrap_err(),
+            TradiumError::InvalidTokenProgram.into()
+        );
+        assert_eq!(
+            check_mint_token_program(&SPL_TOKEN_PROGRAM_ID, &SPL_TOKEN_2022_PROGRAM_ID)
+                .unwrap_err(),
+            TradiumError::InvalidTokenProgram.into()
+        );
+    }
+
+    #[test]
+    fn token_programs_are_checked_per_side_in_same_and_mixed_pools() {
+        use crate::constants::{SPL_TOKEN_2022_PROGRAM_ID, SPL_TOKEN_PROGRAM_ID};
+
+        // Both sides SPL: one program account serves both legs
+        let same = Tradium {
+            coin_token_program: SPL_TOKEN_PROGRAM_ID,
+            pc_token_program: SPL_TOKEN_PROGRAM_ID,
+            ..Default::default()
+        };
+        for swap_direction in [0, 1] {
+            assert!(check_swap_token_programs(
+                &same,
+                &SPL_TOKEN_PROGRAM_ID,
+                &SPL_TOKEN_PROGRAM_ID,
+                swap_direction
+            )
+            .is_ok());
+        }
+
+        // Coin on SPL, pc on Token-2022: the programs follow the direction
+        let mixed = Tradium {
+            coin_token_program: SPL_TOKEN_PROGRAM_ID,
+            pc_token_program: SPL_TOKEN_2022_PROGRAM_ID,
+            ..Default::default()
+        };
+        assert!(check_swap_token_programs(
+            &mixed,
+            &SPL_TOKEN_PROGRAM_ID,
+            &SPL_TOKEN_2022_PROGRAM_ID,
+            0
+        )
+        .is_ok());
+        assert!(check_swap_token_programs(
+            &mixed,
+            &SPL_TOKEN_2022_PROGRAM_ID,
+            &SPL_TOKEN_PROGRAM_ID,
+            1
+        )
+        .is_ok());
+        assert_eq!(
+            check_swap_token_programs(&mixed, &SPL_TOKEN_PROGRAM_ID, &SPL_TOKEN_PROGRAM_ID, 0)
+                .unwrap_err(),
+            TradiumError::InvalidTokenProgram.into()
+        );
+        assert_eq!(
+            check_swap_token_programs(&mixed, &SPL_TOKEN_PROGRAM_ID, &SPL_TOKEN_2022_PROGRAM_ID, 1)
+                .unwrap_err(),
+            TradiumError::InvalidTokenProgram.into()
+        );
+    }
+
+    #[test]
+    fn exact_out_charges_the_computed_input_not_the_max() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+        let (coin_vault, pc_vault) = (2_000_000, 5_000_000);
+        let max_amount_in = 1_000_000;
+
+        let amount_in_used = quote_amount_in(&pool, coin_vault, pc_vault, 40_000, 0).unwrap();
+        assert!(amount_in_used < max_amount_in);
+        assert_eq!(
+            unused_max_amount_in(amount_in_used, max_amount_in).unwrap(),
+            max_amount_in - amount_in_used
+        );
+
+        // Exactly the needed input is fine, one unit less of headroom is not
+        assert_eq!(
+            unused_max_amount_in(amount_in_used, amount_in_used).unwrap(),
+            0
+        );
+        assert_eq!(
+            unused_max_amount_in(amount_in_used, amount_in_used - 1).unwrap_err(),
+            TradiumError::ExcessiveInputAmount.into()
+        );
+
+        let result = ExactOutSwapResult {
+            amount_in_used,
+            amount_in_unused: max_amount_in - amount_in_used,
+            swap: SwapResult {
+                amount_out: 40_000,
+                price_after: 0,
+                execution_price: 0,
+                coin_reserve_after: 0,
+                pc_reserve_after: 0,
+            },
+        };
+        let decoded = ExactOutSwapResult::try_from_slice(&result.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded, result);
+    }
+
+    #[test]
+    fn pre_swap_compounding_prices_against_the_compounded_reserves() {
+        let (coin_vault, pc_vault) = (1_020_000, 4_000_000);
+        let mut pool = preflight_pool();
+        pool.coin_fees_owed = 20_000;
+
+        // Off by default: the owed fees stay out of the reserves
+        let mut idle = pool.clone();
+        assert_eq!(auto_compound_before_swap(&mut idle), (0, 0));
+        let quote_before = quote_amount_out(&idle, coin_vault, pc_vault, 10_000, 0).unwrap();
+        assert_eq!(
+            quote_before,
+            curve::get_amount_out(10_000, 1_000_000, 4_000_000, 5, 10_000).unwrap()
+        );
+
+        pool.auto_compound_fees = true;
+        assert_eq!(auto_compound_before_swap(&mut pool), (20_000, 0));
+        assert_eq!(pool.coin_fees_owed, 0);
+
+        // The extra coin makes coin slightly cheaper, and the swap prices against it
+        let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault, pc_vault).unwrap();
+        assert_eq!((coin_reserve, pc_reserve), (coin_vault, pc_vault));
+        assert!(
+            curve::spot_price(coin_reserve, pc_reserve).unwrap()
+                < curve::spot_price(1_000_000, 4_000_000).unwrap()
+        );
+        let quote_after = quote_amount_out(&pool, coin_vault, pc_vault, 10_000, 0).unwrap();
+        assert_eq!(
+            quote_after,
+            curve::get_amount_out(10_000, coin_vault, pc_vault, 5, 10_000).unwrap()
+        );
+        assert!(quote_after < quote_before);
+
+        // Compounding again before the next swap has nothing left to fold in
+        assert_eq!(auto_compound_before_swap(&mut pool), (0, 0));
+    }
+
+    #[test]
+    fn swap_past_its_deadline_is_rejected() {
+        assert!(check_deadline(1_000, 1_000).is_ok());
+        assert!(check_deadline(1_000, i64::MAX).is_ok());
+        assert_eq!(
+            check_deadline(1_001, 1_000).unwrap_err(),
+            TradiumError::DeadlineExceeded.into()
+        );
+    }
+
+    #[test]
+    fn delegated_input_account_is_rejected() {
+        use anchor_lang::solana_program::program_pack::Pack;
+
+        let user = Pubkey::new_unique();
+        let input_account = |owner: Pubkey, delegate: Option<Pubkey>| {
+            let mut data = vec![0u8; spl_token::state::Account::LEN];
+            spl_token::state::Account {
+                mint: Pubkey::new_unique(),
+                owner,
+                amount: 1_000,
+                delegate: delegate.into(),
+                delegated_amount: if delegate.is_some() { 1_000 } else { 0 },
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            }
+            .pack_into_slice(&mut data);
+            TokenInterfaceAccount::try_deserialize(&mut &data[..]).unwrap()
+        };
+
+        assert!(check_input_authority(&input_account(user, None), &user).is_ok());
+
+        // The signer is only the delegate of someone else's account
+        assert_eq!(
+            check_input_authority(&input_account(Pubkey::new_unique(), Some(user)), &user)
+                .unwrap_err(),
+            TradiumError::InvalidInputTokenAccount.into()
+        );
+    }
 }
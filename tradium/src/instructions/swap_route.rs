@@ -0,0 +1,465 @@
+use crate::error::TradiumError;
+use crate::instructions::swap::{
+    auto_compound_before_swap, check_input_authority, is_throttled_large_swap, record_swap,
+    reserves_after, swap_event, swap_preflight_with_transfer_fees, swap_result, SwapResult,
+    SwapSnapshot, TransferFeeAdjustedQuote,
+};
+use crate::oracle;
+use crate::shared::{self, HookResolution};
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+
+/// Accounts per hop in `remaining_accounts`, in order: pool, coin vault,
+/// pc vault, coin mint, pc mint, user input account, user output account,
+/// input token program, output token program, price oracle. The oracle is
+/// only read when the pool sets `max_oracle_deviation_bps`; otherwise any
+/// account (e.g. the program id) fills the slot.
+pub const ROUTE_HOP_ACCOUNTS: usize = 10;
+
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    pub user: Signer<'info>,
+}
+
+/// Swaps `amount_in` through one pool per entry of `swap_directions`, each
+/// hop's output feeding the next. Every hop is checked against its own entry
+/// of `hop_min_amounts_out` (when given) as well as the route against
+/// `min_amount_out`, so a bad middle hop fails the route even if the end
+/// amount would pass. Returns each hop's result.
+pub fn handle_swap_route<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    amount_in: u64,
+    swap_directions: Vec<u8>,
+    hop_min_amounts_out: Vec<u64>,
+    min_amount_out: u64,
+) -> Result<Vec<SwapResult>> {
+    require!(amount_in > 0, TradiumError::InvalidInputAmount);
+    check_route_shape(
+        ctx.remaining_accounts.len(),
+        swap_directions.len(),
+        hop_min_amounts_out.len(),
+    )?;
+
+    let current_slot = Clock::get()?.slot;
+    let now = Clock::get()?.unix_timestamp;
+    let mut hop_amount_in = amount_in;
+    let mut results = Vec::with_capacity(swap_directions.len());
+
+    for (hop, (accounts, swap_direction)) in ctx
+        .remaining_accounts
+        .chunks(ROUTE_HOP_ACCOUNTS)
+        .zip(swap_directions)
+        .enumerate()
+    {
+        // Each hop spends exactly what the previous hop paid out
+        if hop > 0 {
+            require_keys_eq!(
+                accounts[5].key(),
+                ctx.remaining_accounts[(hop - 1) * ROUTE_HOP_ACCOUNTS + 6].key(),
+                TradiumError::InvalidRoute
+            );
+        }
+
+        let result = execute_route_hop(
+            &ctx.accounts.user,
+            accounts,
+            hop_amount_in,
+            hop_min_amounts_out.get(hop).copied().unwrap_or(0),
+            swap_direction,
+            current_slot,
+            now,
+        )
+        .inspect_err(|_| {
+            msg!("Route hop {} failed", hop);
+        })?;
+
+        hop_amount_in = result.amount_out;
+        results.push(result);
+    }
+
+    require!(
+        hop_amount_in >= min_amount_out,
+        TradiumError::SlippageExceeded
+    );
+
+    msg!("Route completed: {} -> {}", amount_in, hop_amount_in);
+
+    Ok(results)
+}
+
+/// Validates the account and argument counts of a route: at least one hop,
+/// `ROUTE_HOP_ACCOUNTS` accounts per hop, and per-hop minimums for every hop
+/// or none.
+fn check_route_shape(num_accounts: usize, num_hops: usize, num_hop_mins: usize) -> Result<()> {
+    require!(
+        num_hops > 0 && num_accounts == num_hops * ROUTE_HOP_ACCOUNTS,
+        TradiumError::InvalidRoute
+    );
+    require!(
+        num_hop_mins == 0 || num_hop_mins == num_hops,
+        TradiumError::InvalidRoute
+    );
+    Ok(())
+}
+
+/// The checks and accounting of one hop, without the transfers: preflight
+/// against the hop's own minimum, the output cap, and the post-swap reserves.
+/// Transfer fees are netted out as in `swap`: the curve is credited with the
+/// input that reaches the vault, and the result's `amount_out` is what the
+/// user receives, which is what the next hop spends. Also returns the quote,
+/// whose gross `amount_out` leaves the vault and whose fee is split as in `swap`.
+#[allow(clippy::too_many_arguments)]
+fn quote_route_hop(
+    pool: &Tradium,
+    coin_vault_amount: u64,
+    pc_vault_amount: u64,
+    amount_in: u64,
+    hop_min_amount_out: u64,
+    swap_direction: u8,
+    input_mint: &AccountInfo,
+    output_mint: &AccountInfo,
+    epoch: u64,
+) -> Result<(SwapResult, TransferFeeAdjustedQuote)> {
+    let quote = swap_preflight_with_transfer_fees(
+        pool,
+        coin_vault_amount,
+        pc_vault_amount,
+        amount_in,
+        hop_min_amount_out,
+        swap_direction,
+        input_mint,
+        output_mint,
+        epoch,
+    )?;
+
+    let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault_amount, pc_vault_amount)?;
+    let (coin_reserve_after, pc_reserve_after) = reserves_after(
+        coin_reserve,
+        pc_reserve,
+        quote
+            .net_amount_in
+            .checked_sub(pool.fees.owner_fee_share(quote.fee_amount)?)
+            .ok_or(TradiumError::MathOverflow)?,
+        quote.amount_out,
+        swap_direction,
+    )?;
+
+    Ok((
+        swap_result(
+            pool,
+            amount_in,
+            quote.amount_received,
+            swap_direction,
+            coin_reserve_after,
+            pc_reserve_after,
+        )?,
+        quote,
+    ))
+}
+
+fn execute_route_hop<'info>(
+    user: &Signer<'info>,
+    accounts: &'info [AccountInfo<'info>],
+    amount_in: u64,
+    hop_min_amount_out: u64,
+    swap_direction: u8,
+    current_slot: u64,
+    now: i64,
+) -> Result<SwapResult> {
+    require!(swap_direction <= 1, TradiumError::InvalidSwapDirection);
+
+    let mut pool = Account::<Tradium>::try_from(&accounts[0])?;
+    // Hop pools carry no seeds constraint; the stored bump must derive this one
+    pool.check_bump(accounts[0].key)?;
+    require!(pool.is_active(now), TradiumError::InvalidPoolState);
+    auto_compound_before_swap(&mut pool);
+    let mut coin_vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[1])?;
+    let mut pc_vault = InterfaceAccount::<TokenAccount>::try_from(&accounts[2])?;
+    let coin_mint = &accounts[3];
+    let pc_mint = &accounts[4];
+    let user_input = &accounts[5];
+    check_input_authority(
+        &*InterfaceAccount::<TokenAccount>::try_from(user_input)?,
+        user.key,
+    )?;
+    let user_output = &accounts[6];
+    let input_token_program = Interface::<TokenInterface>::try_from(&accounts[7])?;
+    let output_token_program = Interface::<TokenInterface>::try_from(&accounts[8])?;
+    let price_oracle = &accounts[9];
+
+    require_keys_eq!(
+        coin_vault.key(),
+        pool.coin_vault,
+        TradiumError::InvalidCoinVault
+    );
+    require_keys_eq!(pc_vault.key(), pool.pc_vault, TradiumError::InvalidPcVault);
+    require_keys_eq!(
+        coin_mint.key(),
+        pool.coin_vault_mint,
+        TradiumError::InvalidCoinMint
+    );
+    require_keys_eq!(
+        pc_mint.key(),
+        pool.pc_vault_mint,
+        TradiumError::InvalidPcMint
+    );
+
+    // Routes carry no hook program accounts, so hooked mints cannot be routed
+    require!(
+        !pool.has_any_hook_mints,
+        TradiumError::RouteHookMintUnsupported
+    );
+
+    let (input_vault, output_vault, input_mint, output_mint) = if swap_direction == 0 {
+        (&coin_vault, &pc_vault, coin_mint, pc_mint)
+    } else {
+        (&pc_vault, &coin_vault, pc_mint, coin_mint)
+    };
+    let (input_program_expected, output_program_expected) = if swap_direction == 0 {
+        (pool.coin_token_program, pool.pc_token_program)
+    } else {
+        (pool.pc_token_program, pool.coin_token_program)
+    };
+    require_keys_eq!(
+        input_token_program.key(),
+        input_program_expected,
+        TradiumError::InvalidTokenProgram
+    );
+    require_keys_eq!(
+        output_token_program.key(),
+        output_program_expected,
+        TradiumError::InvalidTokenProgram
+    );
+
+    // Vaults, reserves and fee before the hop, before the bootstrap count moves
+    let before = SwapSnapshot::take(&pool, coin_vault.amount, pc_vault.amount)?;
+
+    if is_throttled_large_swap(&pool, amount_in, swap_direction, current_slot)? {
+        pool.last_large_swap_slot = current_slot;
+    }
+
+    let (result, quote) = quote_route_hop(
+        &pool,
+        coin_vault.amount,
+        pc_vault.amount,
+        amount_in,
+        hop_min_amount_out,
+        swap_direction,
+        input_mint,
+        output_mint,
+        Clock::get()?.epoch,
+    )?;
+    oracle::check_swap_against_oracle(
+        &pool,
+        Some(price_oracle),
+        crate::curve::execution_price(quote.net_amount_in, quote.amount_out, swap_direction)?,
+        current_slot,
+    )?;
+
+    let pool_seeds = pool.signer_seeds();
+    let signer_seeds = &[&pool_seeds[..]];
+
+    shared::transfer_tokens_with_hook_support(
+        &input_token_program,
+        user_input,
+        input_vault,
+        input_mint,
+        &user.to_account_info(),
+        HookResolution::NoHook,
+        None,
+        &[],
+        amount_in,
+        None,
+    )?;
+    shared::transfer_tokens_with_hook_support(
+        &output_token_program,
+        output_vault,
+        user_output,
+        output_mint,
+        &pool.to_account_info(),
+        HookResolution::NoHook,
+        None,
+        &[],
+        quote.amount_out,
+        Some(signer_seeds),
+    )?;
+
+    let owner_fee = pool.fees.owner_fee_share(quote.fee_amount)?;
+    pool.accrue_owner_fee(swap_direction, owner_fee)?;
+    pool.state_data.record_swap_volume(
+        swap_direction,
+        quote.net_amount_in,
+        quote.amount_out,
+        quote.fee_amount,
+    );
+    record_swap(&mut pool, now);
+
+    coin_vault.reload()?;
+    pc_vault.reload()?;
+    pool.record_reserves(coin_vault.amount, pc_vault.amount);
+
+    emit!(swap_event(
+        pool.key(),
+        user.key(),
+        &before,
+        coin_vault.amount,
+        pc_vault.amount,
+        amount_in,
+        swap_direction,
+        &result,
+        now,
+    ));
+
+    // Not a typed context account, so the state is persisted explicitly
+    pool.exit(&crate::ID)?;
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::TestAccount;
+
+    fn pool_with_fee() -> Tradium {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 5;
+        pool.fees.swap_fee_denominator = 10_000;
+        pool
+    }
+
+    /// Quotes a route over `(pool, coin vault, pc vault, direction)` hops,
+    /// every pool trading `coin_mint` against `pc_mint`
+    fn quote_route<'info>(
+        hops: &[(Tradium, u64, u64, u8)],
+        amount_in: u64,
+        hop_min_amounts_out: &[u64],
+        coin_mint: &AccountInfo<'info>,
+        pc_mint: &AccountInfo<'info>,
+    ) -> Result<u64> {
+        let mut amount = amount_in;
+        for (hop, (pool, coin_vault, pc_vault, swap_direction)) in hops.iter().enumerate() {
+            let (input_mint, output_mint) = if *swap_direction == 0 {
+                (coin_mint, pc_mint)
+            } else {
+                (pc_mint, coin_mint)
+            };
+            amount = quote_route_hop(
+                pool,
+                *coin_vault,
+                *pc_vault,
+                amount,
+                hop_min_amounts_out.get(hop).copied().unwrap_or(0),
+                *swap_direction,
+                input_mint,
+                output_mint,
+                0,
+            )?
+            .0
+            .amount_out;
+        }
+        Ok(amount)
+    }
+
+    #[test]
+    fn failing_middle_hop_fails_the_route() {
+        let spl_token = spl_token::ID;
+        let (coin_key, pc_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut coin_mint_account = TestAccount::new(coin_key, spl_token, vec![]);
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account = TestAccount::new(pc_key, spl_token, vec![]);
+        let pc_mint = pc_mint_account.info();
+        let hops = [
+            (pool_with_fee(), 1_000_000, 1_000_000, 0),
+            // A thin middle pool
+            (pool_with_fee(), 50_000, 50_000, 0),
+            (pool_with_fee(), 1_000_000, 1_000_000, 0),
+        ];
+
+        // Without per-hop minimums the route goes through
+        let amount_out = quote_route(&hops, 10_000, &[], &coin_mint, &pc_mint).unwrap();
+        assert_eq!(amount_out, 8_184);
+
+        // The middle hop pays out 8_257 against a 9_000 minimum, even though
+        // the end amount would satisfy an end-to-end minimum of 8_000
+        assert_eq!(
+            quote_route(&hops, 10_000, &[9_000, 9_000, 8_000], &coin_mint, &pc_mint).unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+        assert_eq!(
+            quote_route(&hops, 10_000, &[9_000, 8_000, 8_000], &coin_mint, &pc_mint).unwrap(),
+            amount_out
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn each_hop_spends_what_arrived_after_transfer_fees() {
+        // Both mints charge 1% on every transfer
+        let token_2022 = spl_token_2022::ID;
+        let (coin_key, pc_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut coin_mint_account =
+            TestAccount::new(coin_key, token_2022, shared::transfer_fee_mint_data(100));
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account =
+            TestAccount::new(pc_key, token_2022, shared::transfer_fee_mint_data(100));
+        let pc_mint = pc_mint_account.info();
+
+        // The first hop prices from what reaches its vault and reports what
+        // reaches the user, net of both legs' fees
+        let pool = pool_with_fee();
+        let (result, quote) = quote_route_hop(
+            &pool, 1_000_000, 1_000_000, 10_000, 0, 0, &coin_mint, &pc_mint, 0,
+        )
+        .unwrap();
+        assert_eq!(quote.net_amount_in, 9_900);
+        assert_eq!(result.amount_out, quote.amount_received);
+        assert!(quote.amount_received < quote.amount_out);
+        assert_eq!(
+            (result.coin_reserve_after, result.pc_reserve_after),
+            (
+                1_000_000 + quote.net_amount_in - quote.fee_amount,
+                1_000_000 - quote.amount_out
+            )
+        );
+
+        // The second hop spends only that received amount
+        let (second, _) = quote_route_hop(
+            &pool,
+            1_000_000,
+            1_000_000,
+            result.amount_out,
+            0,
+            1,
+            &pc_mint,
+            &coin_mint,
+            0,
+        )
+        .unwrap();
+        let hops = [
+            (pool_with_fee(), 1_000_000, 1_000_000, 0),
+            (pool_with_fee(), 1_000_000, 1_000_000, 1),
+        ];
+        assert_eq!(
+            quote_route(&hops, 10_000, &[], &coin_mint, &pc_mint).unwrap(),
+            second.amount_out
+        );
+    }
+
+    #[test]
+    fn route_shape_is_validated() {
+        assert!(check_route_shape(2 * ROUTE_HOP_ACCOUNTS, 2, 0).is_ok());
+        assert!(check_route_shape(2 * ROUTE_HOP_ACCOUNTS, 2, 2).is_ok());
+        for (accounts, hops, mins) in [
+            (0, 0, 0),
+            (2 * ROUTE_HOP_ACCOUNTS - 1, 2, 0),
+            (2 * ROUTE_HOP_ACCOUNTS, 2, 1),
+        ] {
+            assert_eq!(
+                check_route_shape(accounts, hops, mins).unwrap_err(),
+                TradiumError::InvalidRoute.into()
+            );
+        }
+    }
+}
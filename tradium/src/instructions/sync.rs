@@ -0,0 +1,132 @@
+use crate::error::TradiumError;
+use crate::shared;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// Permissionless: the vault balances are the source of truth, so anyone may
+/// resync the pool's cached copy of them.
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.coin_vault @ TradiumError::InvalidCoinVault)]
+    pub coin_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(address = pool.pc_vault @ TradiumError::InvalidPcVault)]
+    pub pc_vault: InterfaceAccount<'info, TokenAccount>,
+}
+
+/// Writes the live vault balances into `state_data.pool_coin_amount` and
+/// `pool_pc_amount`, picking up tokens sent to the vaults directly or balance
+/// changes made out of band, e.g. by a rebasing mint.
+pub fn handle_sync(ctx: Context<SyncReserves>) -> Result<()> {
+    let coin_amount = ctx.accounts.coin_vault.amount;
+    let pc_amount = ctx.accounts.pc_vault.amount;
+    let (old_coin_amount, old_pc_amount) =
+        ctx.accounts.pool.record_reserves(coin_amount, pc_amount);
+
+    emit!(ReservesSynced {
+        pool: ctx.accounts.pool.key(),
+        old_coin_amount,
+        old_pc_amount,
+        coin_amount,
+        pc_amount,
+    });
+
+    msg!(
+        "Reserves synced: coin {} -> {}, pc {} -> {}",
+        old_coin_amount,
+        coin_amount,
+        old_pc_amount,
+        pc_amount
+    );
+
+    Ok(())
+}
+
+#[event]
+pub struct ReservesSynced {
+    pub pool: Pubkey,
+    pub old_coin_amount: u64,
+    pub old_pc_amount: u64,
+    pub coin_amount: u64,
+    pub pc_amount: u64,
+}
+
+/// Permissionless: the flag is derived from the pool's own mints alone.
+#[derive(Accounts)]
+pub struct SyncHookFlag<'info> {
+    #[account(mut)]
+    pub pool: Account<'info, Tradium>,
+
+    #[account(address = pool.coin_vault_mint @ TradiumError::InvalidCoinMint)]
+    pub coin_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(address = pool.pc_vault_mint @ TradiumError::InvalidPcMint)]
+    pub pc_mint: InterfaceAccount<'info, Mint>,
+}
+
+/// Re-probes both mints for a transfer hook and writes the result to
+/// `has_any_hook_mints`. Pools created before the flag existed read it as
+/// false, so one call migrates a hooked pool back to validating its hooks.
+pub fn handle_sync_hook_flag(ctx: Context<SyncHookFlag>) -> Result<()> {
+    let has_any_hook_mints = shared::pool_mints_have_hook(
+        &ctx.accounts.coin_mint.to_account_info(),
+        &ctx.accounts.pc_mint.to_account_info(),
+    );
+    ctx.accounts.pool.has_any_hook_mints = has_any_hook_mints;
+
+    msg!(
+        "Hook flag synced: has_any_hook_mints {}",
+        has_any_hook_mints
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "token-2022")]
+    use crate::shared::TestAccount;
+
+    #[test]
+    fn cached_reserves_follow_the_vaults() {
+        let mut pool = Tradium::default();
+        assert_eq!(pool.record_reserves(1_000, 4_000), (0, 0));
+
+        // A donation to the coin vault shows up on the next sync
+        assert_eq!(pool.record_reserves(1_500, 4_000), (1_000, 4_000));
+        assert_eq!(
+            (
+                pool.state_data.pool_coin_amount,
+                pool.state_data.pool_pc_amount
+            ),
+            (1_500, 4_000)
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn hook_flag_follows_the_pool_mints() {
+        let token_2022 = spl_token_2022::ID;
+        let (coin_key, pc_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut coin_mint_account =
+            TestAccount::new(coin_key, token_2022, shared::transfer_fee_mint_data(0));
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account = TestAccount::new(
+            pc_key,
+            token_2022,
+            shared::hooked_mint_data(Pubkey::new_unique()),
+        );
+        let pc_mint = pc_mint_account.info();
+
+        // A pool created before the flag existed reads it as false
+        assert!(!Tradium::default().has_any_hook_mints);
+        assert!(shared::pool_mints_have_hook(&coin_mint, &pc_mint));
+        assert!(shared::pool_mints_have_hook(&pc_mint, &coin_mint));
+        assert!(!shared::pool_mints_have_hook(&coin_mint, &coin_mint));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::instructions::validate_fee_config;
+use crate::pda;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+
+/// A vault is not owned by the pool
+pub const INVARIANT_VAULT_OWNER: u32 = 1 << 0;
+/// The vault accounts are not the pool's recorded vaults
+pub const INVARIANT_VAULT_ADDRESS: u32 = 1 << 1;
+/// A vault's mint differs from the pool's recorded mint
+pub const INVARIANT_VAULT_MINT: u32 = 1 << 2;
+/// The LP mint supply differs from `pool.lp_amount`, or the LP mint is not the pool's
+pub const INVARIANT_LP_SUPPLY: u32 = 1 << 3;
+/// The pool address or stored bump does not match the derived PDA
+pub const INVARIANT_BUMP: u32 = 1 << 4;
+/// The stored swap fee is out of bounds
+pub const INVARIANT_FEES: u32 = 1 << 5;
+/// A vault holds less than the fees owed to the owner
+pub const INVARIANT_OWED_FEES: u32 = 1 << 6;
+
+#[derive(Accounts)]
+pub struct VerifyInvariants<'info> {
+    pub pool: Account<'info, Tradium>,
+
+    // Deliberately unconstrained: mismatches are reported, not rejected
+    pub coin_vault: InterfaceAccount<'info, TokenAccount>,
+    pub pc_vault: InterfaceAccount<'info, TokenAccount>,
+    pub lp_mint: InterfaceAccount<'info, Mint>,
+}
+
+/// Checks the pool's invariants and returns a bitflag of the violated ones
+/// (`INVARIANT_*`) via return data; 0 means healthy.
+pub fn handle_verify_invariants(ctx: Context<VerifyInvariants>) -> Result<u32> {
+    let accounts = &ctx.accounts;
+    let violations = invariant_violations(
+        &accounts.pool,
+        &accounts.pool.key(),
+        &VaultSnapshot::of(&accounts.coin_vault),
+        &VaultSnapshot::of(&accounts.pc_vault),
+        &accounts.lp_mint.key(),
+        accounts.lp_mint.supply,
+    );
+
+    msg!("Invariant violations: {:#b}", violations);
+
+    Ok(violations)
+}
+
+/// The parts of a vault the invariants look at, independent of its token program
+#[derive(Clone, Copy, Debug)]
+struct VaultSnapshot {
+    key: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+}
+
+impl VaultSnapshot {
+    fn of(vault: &InterfaceAccount<TokenAccount>) -> Self {
+        Self {
+            key: vault.key(),
+            owner: vault.owner,
+            mint: vault.mint,
+            amount: vault.amount,
+        }
+    }
+}
+
+fn invariant_violations(
+    pool: &Tradium,
+    pool_key: &Pubkey,
+    coin_vault: &VaultSnapshot,
+    pc_vault: &VaultSnapshot,
+    lp_mint_key: &Pubkey,
+    lp_supply: u64,
+) -> u32 {
+    let mut violations = 0;
+
+    if coin_vault.owner != *pool_key || pc_vault.owner != *pool_key {
+        violations |= INVARIANT_VAULT_OWNER;
+    }
+    if coin_vault.key != pool.coin_vault || pc_vault.key != pool.pc_vault {
+        violations |= INVARIANT_VAULT_ADDRESS;
+    }
+    if coin_vault.mint != pool.coin_vault_mint || pc_vault.mint != pool.pc_vault_mint {
+        violations |= INVARIANT_VAULT_MINT;
+    }
+    if *lp_mint_key != pool.lp_mint || lp_supply != pool.lp_amount {
+        violations |= INVARIANT_LP_SUPPLY;
+    }
+    if pda::pool_pda(&pool.coin_vault_mint, &pool.pc_vault_mint) != (*pool_key, pool.bump) {
+        violations |= INVARIANT_BUMP;
+    }
+    if validate_fee_config(
+        pool.fees.trade_fee_numerator,
+        pool.fees.trade_fee_denominator,
+        pool.fees.swap_fee_numerator,
+        pool.fees.swap_fee_denominator,
+    )
+    .is_err()
+    {
+        violations |= INVARIANT_FEES;
+    }
+    if pool
+        .active_reserves(coin_vault.amount, pc_vault.amount)
+        .is_err()
+    {
+        violations |= INVARIANT_OWED_FEES;
+    }
+
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::*;
+
+    struct Fixture {
+        pool: Tradium,
+        pool_key: Pubkey,
+        coin_vault: VaultSnapshot,
+        pc_vault: VaultSnapshot,
+    }
+
+    fn healthy_pool() -> Fixture {
+        let coin_mint = Pubkey::new_unique();
+        let pc_mint = Pubkey::new_unique();
+        let (pool_key, bump) = pda::pool_pda(&coin_mint, &pc_mint);
+        let vault = |key, mint| VaultSnapshot {
+            key,
+            owner: pool_key,
+            mint,
+            amount: 1_000_000,
+        };
+        let mut pool = Tradium {
+            bump,
+            coin_vault_mint: coin_mint,
+            pc_vault_mint: pc_mint,
+            coin_vault: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+            lp_mint: pda::lp_mint_pda(&pool_key),
+            lp_amount: 1_000_000,
+            ..Default::default()
+        };
+        pool.fees.trade_fee_numerator = DEFAULT_TRADE_FEE;
+        pool.fees.trade_fee_denominator = FEE_DENOMINATOR;
+        pool.fees.swap_fee_numerator = DEFAULT_OWNER_FEE;
+        pool.fees.swap_fee_denominator = FEE_DENOMINATOR;
+        Fixture {
+            coin_vault: vault(pool.coin_vault, coin_mint),
+            pc_vault: vault(pool.pc_vault, pc_mint),
+            pool,
+            pool_key,
+        }
+    }
+
+    fn violations(f: &Fixture, lp_supply: u64) -> u32 {
+        invariant_violations(
+            &f.pool,
+            &f.pool_key,
+            &f.coin_vault,
+            &f.pc_vault,
+            &f.pool.lp_mint,
+            lp_supply,
+        )
+    }
+
+    #[test]
+    fn healthy_pool_has_no_violations() {
+        let f = healthy_pool();
+        assert_eq!(violations(&f, 1_000_000), 0);
+    }
+
+    #[test]
+    fn each_corruption_sets_its_flag() {
+        // LP minted outside the pool's accounting
+        let f = healthy_pool();
+        assert_eq!(violations(&f, 1_000_001), INVARIANT_LP_SUPPLY);
+
+        // Vault drained below the fees owed to the owner
+        let mut f = healthy_pool();
+        f.pool.coin_fees_owed = 1_000_001;
+        assert_eq!(violations(&f, 1_000_000), INVARIANT_OWED_FEES);
+
+        // Vault handed to another owner
+        let mut f = healthy_pool();
+        f.pc_vault.owner = Pubkey::new_unique();
+        assert_eq!(violations(&f, 1_000_000), INVARIANT_VAULT_OWNER);
+
+        // Stored bump drifted from the derived one
+        let mut f = healthy_pool();
+        f.pool.bump = f.pool.bump.wrapping_add(1);
+        assert_eq!(violations(&f, 1_000_000), INVARIANT_BUMP);
+
+        // Each fee within bounds, but together above `MAX_FEE_BPS`
+        let mut f = healthy_pool();
+        f.pool.fees.trade_fee_numerator = MAX_FEE_BPS;
+        assert_eq!(violations(&f, 1_000_000), INVARIANT_FEES);
+
+        let mut f = healthy_pool();
+        f.pool.fees.swap_fee_denominator = 0;
+        f.coin_vault.mint = Pubkey::new_unique();
+        assert_eq!(
+            violations(&f, 1_000_000),
+            INVARIANT_FEES | INVARIANT_VAULT_MINT
+        );
+    }
+}
@@ -1,12 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, Token, TokenAccount};
 use anchor_spl::token_interface::{
-    Mint as MintInterface, TokenAccount as TokenAccountInterface, TokenInterface,
+    self, Burn, Mint as MintInterface, TokenAccount as TokenAccountInterface, TokenInterface,
 };
-use spl_token_2022::extension::transfer_hook::TransferHook;
-use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
 
+use crate::constants::POOL_SEED;
+use crate::curve;
 use crate::error::TradiumError;
+use crate::instructions::swap::{
+    check_mint_token_program, check_output_cap, is_throttled_large_swap, record_swap,
+};
+use crate::oracle;
+use crate::pda;
 use crate::shared;
 use crate::state::*;
 
@@ -16,22 +20,21 @@ pub struct Withdraw<'info> {
     #[account(
         mut,
         seeds = [
-            b"tradium",
+            POOL_SEED,
             coin_vault_mint.key().as_ref(),
             pc_vault_mint.key().as_ref()
         ],
-        bump
+        bump = pool.bump
     )]
     pub pool: Account<'info, Tradium>,
 
     /// User authority
     pub user_authority: Signer<'info>,
 
-    /// User's LP token account
+    /// User's LP token account; need not be the ATA, its owner is checked in the handler
     #[account(
         mut,
-        associated_token::mint = lp_mint,
-        associated_token::authority = user_authority
+        token::mint = lp_mint
     )]
     pub user_lp_account: InterfaceAccount<'info, TokenAccountInterface>,
 
@@ -89,225 +92,693 @@ pub struct Withdraw<'info> {
     /// PC token program (Token or Token2022)
     pub pc_token_program_id: Interface<'info, TokenInterface>,
 
-    /// CHECK: Optional, only required if coin_mint has a transfer hook
-    #[account(
-        constraint = shared::validate_transfer_hook_program(
-            &coin_vault_mint,
-            &coin_transfer_hook_program.to_account_info(),
-            &pool.whitelisted_transfer_hooks,
-            pool.num_whitelisted_hooks
-        ) @ TradiumError::InvalidTransferHookProgram
-    )]
+    /// CHECK: Optional, only required if coin_mint has a transfer hook; validated in the handler
     pub coin_transfer_hook_program: Option<UncheckedAccount<'info>>,
 
-    /// CHECK: Optional, only required if pc_mint has a transfer hook
-    #[account(
-        constraint = shared::validate_transfer_hook_program(
-            &pc_vault_mint,
-            &pc_transfer_hook_program.to_account_info(),
-            &pool.whitelisted_transfer_hooks,
-            pool.num_whitelisted_hooks
-        ) @ TradiumError::InvalidTransferHookProgram
-    )]
+    /// CHECK: Optional, only required if pc_mint has a transfer hook; validated in the handler
     pub pc_transfer_hook_program: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: Reference price feed for a single-sided exit's swap; must be the
+    /// pool's configured oracle, and is required when the pool sets
+    /// `max_oracle_deviation_bps`
+    pub price_oracle: Option<UncheckedAccount<'info>>,
+}
+
+/// Burns `lp_amount` LP for its share of both reserves, failing if less than
+/// `min_coin_out` coin or `min_pc_out` pc would arrive.
+pub fn handle_withdraw<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+    lp_amount: u64,
+    min_coin_out: u64,
+    min_pc_out: u64,
+) -> Result<WithdrawResult> {
+    check_withdraw_request(ctx.accounts, lp_amount)?;
+
+    // Get current reserves; fees owed to the owner are not the LPs' to withdraw
+    let (coin_vault_balance, pc_vault_balance) = ctx
+        .accounts
+        .pool
+        .active_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount)?;
+    let total_lp_supply = ctx.accounts.lp_mint.supply;
+
+    require!(total_lp_supply > 0, TradiumError::EmptyPool);
+    // Only minted LP can be burned; the locked LP is never in circulation
+    require!(
+        lp_amount <= total_lp_supply,
+        TradiumError::InsufficientBalance
+    );
+
+    // Calculate withdrawal amounts proportionally against the supply including the
+    // locked LP, so its backing stays; and what arrives after transfer fees
+    let quote = quote_withdraw(
+        coin_vault_balance,
+        pc_vault_balance,
+        lp_amount,
+        ctx.accounts.pool.claim_supply(total_lp_supply)?,
+        &ctx.accounts.coin_vault_mint.to_account_info(),
+        &ctx.accounts.pc_vault_mint.to_account_info(),
+        Clock::get()?.epoch,
+    )?;
+    let (coin_amount, pc_amount) = (quote.coin_amount, quote.pc_amount);
+
+    // Validate minimum withdrawal amounts, net of transfer fees
+    require!(
+        quote.coin_received > 0,
+        TradiumError::InsufficientWithdrawal
+    );
+    require!(quote.pc_received > 0, TradiumError::InsufficientWithdrawal);
+    check_min_withdraw_out(&quote, min_coin_out, min_pc_out)?;
+
+    // Enforce the reserve floors, except for the last minted LP exiting
+    let is_full_exit = lp_amount == total_lp_supply;
+    check_reserve_floor(
+        coin_vault_balance,
+        coin_amount,
+        ctx.accounts.pool.min_coin_reserve,
+        is_full_exit,
+    )?;
+    check_reserve_floor(
+        pc_vault_balance,
+        pc_amount,
+        ctx.accounts.pool.min_pc_reserve,
+        is_full_exit,
+    )?;
+
+    // Resolve each mint's transfer hook once; the transfers reuse the result
+    let coin_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.coin_vault_mint.to_account_info(),
+        ctx.accounts.coin_transfer_hook_program.as_ref(),
+    )?;
+    let pc_hook = shared::resolve_pool_transfer_hook(
+        &ctx.accounts.pool,
+        &ctx.accounts.pc_vault_mint.to_account_info(),
+        ctx.accounts.pc_transfer_hook_program.as_ref(),
+    )?;
+
+    let pool_account_info = ctx.accounts.pool.to_account_info();
+
+    // Define the common signer seeds for the pool-signed CPIs
+    let cpi_seeds = ctx.accounts.pool.signer_seeds();
+    let signer_seeds = &[&cpi_seeds[..]];
+
+    burn_user_lp(ctx.accounts, lp_amount, signer_seeds)?;
+
+    // Transfer coin tokens from vault to user with hook support
+    shared::transfer_tokens_with_hook_support(
+        &ctx.accounts.coin_token_program_id,
+        &ctx.accounts.coin_vault,
+        &ctx.accounts.user_coin_account,
+        &ctx.accounts.coin_vault_mint.to_account_info(),
+        &pool_account_info,
+        coin_hook,
+        ctx.accounts.coin_transfer_hook_program.as_ref(),
+        ctx.remaining_accounts,
+        coin_amount,
+        Some(signer_seeds),
+    )?;
+
+    // Transfer PC tokens from vault to user with hook support
+    shared::transfer_tokens_with_hook_support(
+        &ctx.accounts.pc_token_program_id,
+        &ctx.accounts.pc_vault,
+        &ctx.accounts.user_pc_account,
+        &ctx.accounts.pc_vault_mint.to_account_info(),
+        &pool_account_info,
+        pc_hook,
+        ctx.accounts.pc_transfer_hook_program.as_ref(),
+        ctx.remaining_accounts,
+        pc_amount,
+        Some(signer_seeds),
+    )?;
+
+    // Track the burn; saturating so pools that drifted before can still exit
+    ctx.accounts.pool.lp_amount = ctx.accounts.pool.lp_amount.saturating_sub(lp_amount);
+    ctx.accounts
+        .pool
+        .state_data
+        .record_withdrawal(coin_amount, pc_amount);
+    ctx.accounts.coin_vault.reload()?;
+    ctx.accounts.pc_vault.reload()?;
+    ctx.accounts
+        .pool
+        .record_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount);
+
+    let timestamp = Clock::get()?.unix_timestamp;
+    ctx.accounts.pool.record_activity(timestamp);
+
+    let result = WithdrawResult::from_quote(lp_amount, &quote);
+    emit!(result.event(
+        ctx.accounts.pool.key(),
+        ctx.accounts.user_authority.key(),
+        timestamp
+    ));
+
+    msg!(
+        "Withdrawal completed: LP burned: {}, Coin withdrawn: {}, PC withdrawn: {}",
+        lp_amount,
+        coin_amount,
+        pc_amount
+    );
+
+    Ok(result)
 }
 
-pub fn withdraw(ctx: Context<Withdraw>, lp_amount: u64) -> Result<()> {
+/// Burns `lp_amount` LP and pays its whole share out in one asset: coin when
+/// `zero_for_coin`, else pc. The other leg never leaves its vault; it is sold
+/// into the pool along the curve at the swap fee, which is split between the
+/// LPs and the owner as on a swap. That sale is a swap, so unlike `withdraw`
+/// it needs an active pool and passes the swap's guards. Fails if less than
+/// `min_out` would arrive.
+pub fn handle_withdraw_single_sided<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+    lp_amount: u64,
+    zero_for_coin: bool,
+    min_out: u64,
+) -> Result<WithdrawResult> {
+    check_withdraw_request(ctx.accounts, lp_amount)?;
+
+    // Get current reserves; fees owed to the owner are not the LPs' to withdraw
+    let (coin_vault_balance, pc_vault_balance) = ctx
+        .accounts
+        .pool
+        .active_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount)?;
+    let total_lp_supply = ctx.accounts.lp_mint.supply;
+
+    require!(total_lp_supply > 0, TradiumError::EmptyPool);
+    require!(
+        lp_amount <= total_lp_supply,
+        TradiumError::InsufficientBalance
+    );
+
+    // Same proportional share as `withdraw`, with the unwanted leg swapped
+    let quote = quote_single_sided_withdraw(
+        &ctx.accounts.pool,
+        coin_vault_balance,
+        pc_vault_balance,
+        lp_amount,
+        ctx.accounts.pool.claim_supply(total_lp_supply)?,
+        zero_for_coin,
+        min_out,
+    )?;
+
+    let clock = Clock::get()?;
+    if check_single_sided_swap(
+        &ctx.accounts.pool,
+        ctx.accounts.price_oracle.as_deref(),
+        &quote,
+        zero_for_coin,
+        clock.unix_timestamp,
+        clock.slot,
+    )? {
+        ctx.accounts.pool.last_large_swap_slot = clock.slot;
+    }
+
+    // Only the wanted asset leaves the pool; min_out holds net of its transfer fee
+    let (output_vault_balance, output_min_reserve, output_mint) = if zero_for_coin {
+        (
+            coin_vault_balance,
+            ctx.accounts.pool.min_coin_reserve,
+            ctx.accounts.coin_vault_mint.to_account_info(),
+        )
+    } else {
+        (
+            pc_vault_balance,
+            ctx.accounts.pool.min_pc_reserve,
+            ctx.accounts.pc_vault_mint.to_account_info(),
+        )
+    };
+    let amount_received = quote
+        .amount_out
+        .checked_sub(shared::mint_transfer_fee(
+            &output_mint,
+            clock.epoch,
+            quote.amount_out,
+        )?)
+        .ok_or(TradiumError::MathOverflow)?;
+    check_single_sided_out(amount_received, min_out)?;
+    check_reserve_floor(
+        output_vault_balance,
+        quote.amount_out,
+        output_min_reserve,
+        lp_amount == total_lp_supply,
+    )?;
+
+    // Resolve the output mint's transfer hook before anything moves
+    let output_hook_program = if zero_for_coin {
+        ctx.accounts.coin_transfer_hook_program.as_ref()
+    } else {
+        ctx.accounts.pc_transfer_hook_program.as_ref()
+    };
+    let output_hook =
+        shared::resolve_pool_transfer_hook(&ctx.accounts.pool, &output_mint, output_hook_program)?;
+
+    let pool_account_info = ctx.accounts.pool.to_account_info();
+    let cpi_seeds = ctx.accounts.pool.signer_seeds();
+    let signer_seeds = &[&cpi_seeds[..]];
+
+    burn_user_lp(ctx.accounts, lp_amount, signer_seeds)?;
+
+    if zero_for_coin {
+        shared::transfer_tokens_with_hook_support(
+            &ctx.accounts.coin_token_program_id,
+            &ctx.accounts.coin_vault,
+            &ctx.accounts.user_coin_account,
+            &output_mint,
+            &pool_account_info,
+            output_hook,
+            output_hook_program,
+            ctx.remaining_accounts,
+            quote.amount_out,
+            Some(signer_seeds),
+        )?;
+    } else {
+        shared::transfer_tokens_with_hook_support(
+            &ctx.accounts.pc_token_program_id,
+            &ctx.accounts.pc_vault,
+            &ctx.accounts.user_pc_account,
+            &output_mint,
+            &pool_account_info,
+            output_hook,
+            output_hook_program,
+            ctx.remaining_accounts,
+            quote.amount_out,
+            Some(signer_seeds),
+        )?;
+    }
+
+    // Account for it as a withdraw followed by a swap of the unwanted leg
+    let (swap_direction, swapped, swap_amount_out) = quote.swapped_leg(zero_for_coin)?;
+    ctx.accounts.pool.lp_amount = ctx.accounts.pool.lp_amount.saturating_sub(lp_amount);
+    ctx.accounts
+        .pool
+        .state_data
+        .record_withdrawal(quote.coin_amount, quote.pc_amount);
+    let owner_fee = ctx.accounts.pool.fees.owner_fee_share(quote.fee_amount)?;
+    ctx.accounts
+        .pool
+        .accrue_owner_fee(swap_direction, owner_fee)?;
+    ctx.accounts.pool.state_data.record_swap_volume(
+        swap_direction,
+        swapped,
+        swap_amount_out,
+        quote.fee_amount,
+    );
+    ctx.accounts.coin_vault.reload()?;
+    ctx.accounts.pc_vault.reload()?;
+    ctx.accounts
+        .pool
+        .record_reserves(ctx.accounts.coin_vault.amount, ctx.accounts.pc_vault.amount);
+
+    let timestamp = clock.unix_timestamp;
+    if swapped > 0 {
+        record_swap(&mut ctx.accounts.pool, timestamp);
+    } else {
+        ctx.accounts.pool.record_activity(timestamp);
+    }
+
+    let result = WithdrawResult::single_sided(lp_amount, &quote, zero_for_coin);
+    emit!(result.event(
+        ctx.accounts.pool.key(),
+        ctx.accounts.user_authority.key(),
+        timestamp
+    ));
+
+    msg!(
+        "Single-sided withdrawal completed: LP burned: {}, Coin withdrawn: {}, PC withdrawn: {}",
+        lp_amount,
+        result.coin_out,
+        result.pc_out
+    );
+
+    Ok(result)
+}
+
+/// Checks shared by both withdrawals: a nonzero amount the signer owns and
+/// holds, and the pool's own token programs and canonical LP mint.
+fn check_withdraw_request(accounts: &Withdraw, lp_amount: u64) -> Result<()> {
     // Validate minimum withdrawal amount
     require!(lp_amount > 0, TradiumError::InvalidAmount);
 
+    // No status check: withdrawals stay open while paused so LPs can always exit
+
+    // The burn is authorized by user_authority, so it must own the LP account
+    check_lp_account_owner(
+        &accounts.user_lp_account.owner,
+        &accounts.user_authority.key(),
+    )?;
+
     // Validate user has sufficient LP tokens
     require!(
-        ctx.accounts.user_lp_account.amount >= lp_amount,
+        accounts.user_lp_account.amount >= lp_amount,
         TradiumError::InsufficientBalance
     );
 
     // Validate token program IDs match pool configuration
     require!(
-        ctx.accounts.coin_token_program_id.key() == ctx.accounts.pool.coin_token_program,
+        accounts.coin_token_program_id.key() == accounts.pool.coin_token_program,
         TradiumError::InvalidCoinTokenProgram
     );
     require!(
-        ctx.accounts.pc_token_program_id.key() == ctx.accounts.pool.pc_token_program,
+        accounts.pc_token_program_id.key() == accounts.pool.pc_token_program,
         TradiumError::InvalidPcTokenProgram
     );
 
-    // Get current vault balances
-    let coin_vault_balance = ctx.accounts.coin_vault.amount;
-    let pc_vault_balance = ctx.accounts.pc_vault.amount;
-    let total_lp_supply = ctx.accounts.lp_mint.supply;
+    // Validate the LP mint is the pool's canonical PDA, not just the stored key
+    require!(
+        pda::is_canonical_lp_mint(&accounts.pool.key(), &accounts.lp_mint.key()),
+        TradiumError::InvalidLpMint
+    );
 
-    require!(total_lp_supply > 0, TradiumError::EmptyPool);
+    Ok(())
+}
+
+/// Burns `lp_amount` of the user's LP, thawing and refreezing a soulbound
+/// LP account around the burn.
+fn burn_user_lp<'info>(
+    accounts: &Withdraw<'info>,
+    lp_amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let pool_account_info = accounts.pool.to_account_info();
+
+    // Soulbound LP accounts are frozen; thaw for the burn and refreeze after
+    let soulbound_lp = accounts.pool.soulbound_lp && accounts.user_lp_account.is_frozen();
+    if soulbound_lp {
+        shared::set_lp_account_frozen(
+            &accounts.lp_token_program_id.to_account_info(),
+            &accounts.user_lp_account.to_account_info(),
+            &accounts.lp_mint.to_account_info(),
+            &pool_account_info,
+            signer_seeds,
+            false,
+        )?;
+    }
+
+    // Burn LP tokens from user through the LP mint's own program; burns never
+    // run a Token-2022 transfer hook, so a hooked LP mint needs no extra accounts
+    check_mint_token_program(
+        accounts.lp_mint.to_account_info().owner,
+        &accounts.lp_token_program_id.key(),
+    )?;
+    let burn_ctx = CpiContext::new(
+        accounts.lp_token_program_id.to_account_info(),
+        Burn {
+            mint: accounts.lp_mint.to_account_info(),
+            from: accounts.user_lp_account.to_account_info(),
+            authority: accounts.user_authority.to_account_info(),
+        },
+    );
+    token_interface::burn(burn_ctx, lp_amount)?;
+
+    if soulbound_lp {
+        shared::set_lp_account_frozen(
+            &accounts.lp_token_program_id.to_account_info(),
+            &accounts.user_lp_account.to_account_info(),
+            &accounts.lp_mint.to_account_info(),
+            &pool_account_info,
+            signer_seeds,
+            true,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Rejects an LP account (ATA or not) that the signing authority does not own.
+fn check_lp_account_owner(lp_account_owner: &Pubkey, user_authority: &Pubkey) -> Result<()> {
+    require_keys_eq!(
+        *lp_account_owner,
+        *user_authority,
+        TradiumError::InvalidLpAccountOwner
+    );
+    Ok(())
+}
+
+/// Withdrawal preview: the proportional amounts leaving the vaults and what
+/// the LP actually receives after any output transfer fees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WithdrawQuote {
+    pub coin_amount: u64,
+    pub pc_amount: u64,
+    pub coin_received: u64,
+    pub pc_received: u64,
+}
+
+/// Fails with `SlippageExceeded` if less than `min_coin_out` or `min_pc_out`
+/// would arrive, e.g. because a swap moved the reserves since it was quoted.
+fn check_min_withdraw_out(quote: &WithdrawQuote, min_coin_out: u64, min_pc_out: u64) -> Result<()> {
+    require!(
+        quote.coin_received >= min_coin_out && quote.pc_received >= min_pc_out,
+        TradiumError::SlippageExceeded
+    );
+    Ok(())
+}
 
-    // Calculate withdrawal amounts proportionally
-    let coin_amount = (coin_vault_balance as u128)
+/// Quotes a withdrawal of `lp_amount` against the active reserves, netting
+/// out the Token-2022 transfer fee each output mint charges in `epoch`.
+pub fn quote_withdraw(
+    coin_reserve: u64,
+    pc_reserve: u64,
+    lp_amount: u64,
+    total_lp_supply: u64,
+    coin_mint: &AccountInfo,
+    pc_mint: &AccountInfo,
+    epoch: u64,
+) -> Result<WithdrawQuote> {
+    let (coin_amount, pc_amount) =
+        withdraw_amounts(coin_reserve, pc_reserve, lp_amount, total_lp_supply)?;
+    let coin_fee = shared::mint_transfer_fee(coin_mint, epoch, coin_amount)?;
+    let pc_fee = shared::mint_transfer_fee(pc_mint, epoch, pc_amount)?;
+
+    Ok(WithdrawQuote {
+        coin_amount,
+        pc_amount,
+        coin_received: coin_amount
+            .checked_sub(coin_fee)
+            .ok_or(TradiumError::MathOverflow)?,
+        pc_received: pc_amount
+            .checked_sub(pc_fee)
+            .ok_or(TradiumError::MathOverflow)?,
+    })
+}
+
+/// Proportional share of the active reserves for `lp_amount`. A full exit
+/// takes all active reserves, leaving only the owed fees in the vaults; with
+/// `total_lp_supply` from `Tradium::claim_supply` the locked LP's share stays.
+pub(crate) fn withdraw_amounts(
+    coin_reserve: u64,
+    pc_reserve: u64,
+    lp_amount: u64,
+    total_lp_supply: u64,
+) -> Result<(u64, u64)> {
+    let coin_amount = (coin_reserve as u128)
         .checked_mul(lp_amount as u128)
         .ok_or(TradiumError::MathOverflow)?
         .checked_div(total_lp_supply as u128)
         .ok_or(TradiumError::MathOverflow)? as u64;
 
-    let pc_amount = (pc_vault_balance as u128)
+    let pc_amount = (pc_reserve as u128)
         .checked_mul(lp_amount as u128)
         .ok_or(TradiumError::MathOverflow)?
         .checked_div(total_lp_supply as u128)
         .ok_or(TradiumError::MathOverflow)? as u64;
 
-    // Validate minimum withdrawal amounts
-    require!(coin_amount > 0, TradiumError::InsufficientWithdrawal);
-    require!(pc_amount > 0, TradiumError::InsufficientWithdrawal);
-
-    // Burn LP tokens from user
-    let burn_ctx = CpiContext::new(
-        ctx.accounts.lp_token_program_id.to_account_info(),
-        Burn {
-            mint: ctx.accounts.lp_mint.to_account_info(),
-            from: ctx.accounts.user_lp_account.to_account_info(),
-            authority: ctx.accounts.user_authority.to_account_info(),
-        },
-    );
-    token::burn(burn_ctx, lp_amount)?;
-
-    let pool_account_info = ctx.accounts.pool.to_account_info();
+    Ok((coin_amount, pc_amount))
+}
 
-    let coin_mint_key_ref: &[u8] = ctx.accounts.coin_vault_mint.to_account_info().key.as_ref();
-    let pc_mint_key_ref: &[u8] = ctx.accounts.pc_vault_mint.to_account_info().key.as_ref();
-    let bump_seed_ref: &[u8] = &ctx.accounts.pool.nonce;
-
-    // Define the common signer seeds for both transfers
-    let cpi_seeds = &[
-        &b"tradium"[..],
-        coin_mint_key_ref,
-        pc_mint_key_ref,
-        bump_seed_ref,
-    ];
-    let signer_seeds = &[&cpi_seeds[..]];
+/// Single-sided exit preview: the proportional share leaving the vaults, with
+/// the unwanted leg swapped into the wanted asset along the pool curve.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SingleSidedWithdrawQuote {
+    pub coin_amount: u64,
+    pub pc_amount: u64,
+    /// Swap fee charged on the swapped leg
+    pub fee_amount: u64,
+    /// Total of the wanted asset paid out
+    pub amount_out: u64,
+}
 
-    // --- Transfer coin tokens from vault to user with hook support ---
-    let mut remaining_accounts_coin: Vec<AccountInfo> = Vec::new();
-    if ctx.accounts.coin_vault_mint.to_account_info().owner == &spl_token_2022::ID {
-        if let Ok(mint_data_with_extensions) =
-            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(
-                &ctx.accounts.coin_vault_mint.to_account_info().data.borrow(),
-            )
-        {
-            if let Ok(_transfer_hook_extension) =
-                mint_data_with_extensions.get_extension::<TransferHook>()
-            {
-                if let Some(hook_program_acc) = ctx.accounts.coin_transfer_hook_program.as_ref() {
-                    remaining_accounts_coin.push(hook_program_acc.to_account_info());
-                } else {
-                    return Err(TradiumError::InvalidTransferHookProgram.into());
-                }
-            }
-        }
+impl SingleSidedWithdrawQuote {
+    /// The swap inside the exit: its direction, the unwanted leg sold into the
+    /// pool and what that sale bought of the wanted asset.
+    pub fn swapped_leg(&self, receive_coin: bool) -> Result<(u8, u64, u64)> {
+        let (swap_direction, swapped, kept) = if receive_coin {
+            (1, self.pc_amount, self.coin_amount)
+        } else {
+            (0, self.coin_amount, self.pc_amount)
+        };
+        let bought = self
+            .amount_out
+            .checked_sub(kept)
+            .ok_or(TradiumError::MathOverflow)?;
+        Ok((swap_direction, swapped, bought))
     }
+}
 
-    let transfer_accounts_coin = anchor_spl::token_interface::Transfer {
-        from: ctx.accounts.coin_vault.to_account_info(),
-        to: ctx.accounts.user_coin_account.to_account_info(),
-        authority: pool_account_info.clone(),
-    };
+/// Guards of `swap` applied to the sale inside a single-sided exit: the pool
+/// must be active, and the sale must stay within the output cap, the oracle
+/// band and the large-swap throttle. Returns whether it counts as large.
+pub(crate) fn check_single_sided_swap(
+    pool: &Tradium,
+    price_oracle: Option<&AccountInfo>,
+    quote: &SingleSidedWithdrawQuote,
+    receive_coin: bool,
+    now: i64,
+    current_slot: u64,
+) -> Result<bool> {
+    // Paused pools take no swaps, even inside a withdrawal
+    require!(pool.is_active(now), TradiumError::InvalidPoolState);
 
-    let transfer_ctx_coin = CpiContext::new_with_signer(
-        ctx.accounts.coin_token_program_id.to_account_info(),
-        transfer_accounts_coin,
-        signer_seeds,
-    )
-    .with_remaining_accounts(remaining_accounts_coin);
-
-    anchor_spl::token_interface::transfer(transfer_ctx_coin, coin_amount)?;
-
-    // --- Transfer PC tokens from vault to user with hook support ---
-    let mut remaining_accounts_pc: Vec<AccountInfo> = Vec::new();
-    if ctx.accounts.pc_vault_mint.to_account_info().owner == &spl_token_2022::ID {
-        if let Ok(mint_data_with_extensions) =
-            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(
-                &ctx.accounts.pc_vault_mint.to_account_info().data.borrow(),
-            )
-        {
-            if let Ok(_transfer_hook_extension) =
-                mint_data_with_extensions.get_extension::<TransferHook>()
-            {
-                if let Some(hook_program_acc) = ctx.accounts.pc_transfer_hook_program.as_ref() {
-                    remaining_accounts_pc.push(hook_program_acc.to_account_info());
-                } else {
-                    return Err(TradiumError::InvalidTransferHookProgram.into());
-                }
-            }
-        }
+    let (swap_direction, swapped, bought) = quote.swapped_leg(receive_coin)?;
+    if swapped == 0 || bought == 0 {
+        return Ok(false);
     }
+    check_output_cap(pool, bought, swap_direction)?;
+    oracle::check_swap_against_oracle(
+        pool,
+        price_oracle,
+        curve::execution_price(swapped, bought, swap_direction)?,
+        current_slot,
+    )?;
+    is_throttled_large_swap(pool, swapped, swap_direction, current_slot)
+}
 
-    let transfer_accounts_pc = anchor_spl::token_interface::Transfer {
-        from: ctx.accounts.pc_vault.to_account_info(),
-        to: ctx.accounts.user_pc_account.to_account_info(),
-        authority: pool_account_info,
+/// Quotes exiting `lp_amount` entirely into coin (`receive_coin`) or pc. The
+/// other leg is swapped at the reserves left after the proportional share is
+/// taken and at the fee the next swap would pay, like a withdraw followed by a
+/// swap. Fails unless something arrives and it is at least `min_out`, so tiny
+/// LP amounts are never burned for nothing.
+pub fn quote_single_sided_withdraw(
+    pool: &Tradium,
+    coin_reserve: u64,
+    pc_reserve: u64,
+    lp_amount: u64,
+    total_lp_supply: u64,
+    receive_coin: bool,
+    min_out: u64,
+) -> Result<SingleSidedWithdrawQuote> {
+    let (coin_amount, pc_amount) =
+        withdraw_amounts(coin_reserve, pc_reserve, lp_amount, total_lp_supply)?;
+    // The swap sees the reserves the proportional share leaves behind
+    let coin_left = coin_reserve
+        .checked_sub(coin_amount)
+        .ok_or(TradiumError::MathOverflow)?;
+    let pc_left = pc_reserve
+        .checked_sub(pc_amount)
+        .ok_or(TradiumError::MathOverflow)?;
+    let (kept, swapped, reserve_in, reserve_out) = if receive_coin {
+        (coin_amount, pc_amount, pc_left, coin_left)
+    } else {
+        (pc_amount, coin_amount, coin_left, pc_left)
     };
 
-    let transfer_ctx_pc = CpiContext::new_with_signer(
-        ctx.accounts.pc_token_program_id.to_account_info(),
-        transfer_accounts_pc,
-        signer_seeds,
-    )
-    .with_remaining_accounts(remaining_accounts_pc);
-
-    anchor_spl::token_interface::transfer(transfer_ctx_pc, pc_amount)?;
+    let (swap_amount_out, fee_amount) = if swapped == 0 {
+        (0, 0)
+    } else {
+        let (fee_numerator, fee_denominator) = pool.effective_swap_fee()?;
+        (
+            pool.swap_curve()?.amount_out(
+                swapped,
+                reserve_in,
+                reserve_out,
+                fee_numerator,
+                fee_denominator,
+            )?,
+            swapped
+                .checked_sub(curve::amount_in_after_fee(
+                    swapped,
+                    fee_numerator,
+                    fee_denominator,
+                )?)
+                .ok_or(TradiumError::MathOverflow)?,
+        )
+    };
+    let amount_out = kept
+        .checked_add(swap_amount_out)
+        .ok_or(TradiumError::MathOverflow)?;
+    check_single_sided_out(amount_out, min_out)?;
 
-    msg!(
-        "Withdrawal completed: LP burned: {}, Coin withdrawn: {}, PC withdrawn: {}",
-        lp_amount,
+    Ok(SingleSidedWithdrawQuote {
         coin_amount,
-        pc_amount
-    );
+        pc_amount,
+        fee_amount,
+        amount_out,
+    })
+}
 
+/// A single-sided exit must pay out something, and at least `min_out`.
+fn check_single_sided_out(amount_out: u64, min_out: u64) -> Result<()> {
+    require!(amount_out > 0, TradiumError::InsufficientWithdrawal);
+    require!(amount_out >= min_out, TradiumError::SlippageExceeded);
     Ok(())
 }
 
-fn validate_transfer_hook_program<'a>(
-    mint: &InterfaceAccount<MintInterface>,
-    transfer_hook_program: &'a AccountInfo<'a>,
-    whitelisted_hooks: &[Pubkey],
-    num_whitelisted: u8,
-) -> bool {
-    // Check if mint has transfer hook extension
-    let mint_info = mint.to_account_info();
-    let mint_data = mint_info.data.borrow();
-
-    // For Token-2022 mints, check for transfer hook extension
-    if mint_info.owner == &spl_token_2022::ID {
-        match StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data) {
-            Ok(mint_with_extensions) => {
-                if let Ok(transfer_hook_account) =
-                    mint_with_extensions.get_extension::<TransferHook>()
-                {
-                    // Mint has transfer hook - validate the provided program
-                    let hook_program_id =
-                        if let Some(pubkey) = transfer_hook_account.program_id.into() {
-                            pubkey
-                        } else {
-                            return false;
-                        };
-
-                    // Check if the hook program matches the mint's hook
-                    if transfer_hook_program.key() != hook_program_id {
-                        return false;
-                    }
-
-                    // Check if the hook program is whitelisted
-                    for i in 0..(num_whitelisted as usize) {
-                        if whitelisted_hooks[i] == hook_program_id {
-                            return true;
-                        }
-                    }
-                    return false;
-                } else {
-                    return false;
-                }
-            }
-            Err(_) => return false,
+/// Rejects a withdrawal that would leave the vault below `min_reserve`.
+/// The last LP exiting the pool is always allowed to take everything.
+pub fn check_reserve_floor(
+    vault_balance: u64,
+    amount_out: u64,
+    min_reserve: u64,
+    is_full_exit: bool,
+) -> Result<()> {
+    if is_full_exit || min_reserve == 0 {
+        return Ok(());
+    }
+    let remaining = vault_balance
+        .checked_sub(amount_out)
+        .ok_or(TradiumError::MathOverflow)?;
+    require!(remaining >= min_reserve, TradiumError::ReserveFloorBreached);
+    Ok(())
+}
+
+/// Amounts settled by a withdrawal, set as the instruction's return data
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct WithdrawResult {
+    pub lp_burned: u64,
+    pub coin_out: u64,
+    pub pc_out: u64,
+}
+
+impl WithdrawResult {
+    /// Both legs of a proportional withdrawal, as they leave the vaults.
+    pub fn from_quote(lp_burned: u64, quote: &WithdrawQuote) -> Self {
+        Self {
+            lp_burned,
+            coin_out: quote.coin_amount,
+            pc_out: quote.pc_amount,
+        }
+    }
+
+    /// A single-sided exit paying `quote.amount_out` in coin when
+    /// `receive_coin`, else in pc.
+    pub fn single_sided(
+        lp_burned: u64,
+        quote: &SingleSidedWithdrawQuote,
+        receive_coin: bool,
+    ) -> Self {
+        let (coin_out, pc_out) = if receive_coin {
+            (quote.amount_out, 0)
+        } else {
+            (0, quote.amount_out)
+        };
+        Self {
+            lp_burned,
+            coin_out,
+            pc_out,
+        }
+    }
+
+    /// The `WithdrawalEvent` reporting this withdrawal.
+    pub fn event(&self, pool: Pubkey, user: Pubkey, timestamp: i64) -> WithdrawalEvent {
+        WithdrawalEvent {
+            pool,
+            user,
+            lp_amount: self.lp_burned,
+            coin_amount: self.coin_out,
+            pc_amount: self.pc_out,
+            timestamp,
         }
-    } else {
-        // Regular SPL token but transfer hook program was provided - invalid
-        return false;
     }
 }
 
@@ -320,3 +791,359 @@ pub struct WithdrawalEvent {
     pub pc_amount: u64,
     pub timestamp: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::TestAccount;
+
+    #[test]
+    fn withdraw_down_to_floor_is_allowed() {
+        assert!(check_reserve_floor(1_000, 900, 100, false).is_ok());
+    }
+
+    #[test]
+    fn withdraw_below_floor_is_rejected() {
+        assert_eq!(
+            check_reserve_floor(1_000, 901, 100, false).unwrap_err(),
+            TradiumError::ReserveFloorBreached.into()
+        );
+    }
+
+    #[test]
+    fn full_exit_and_unset_floor_ignore_the_floor() {
+        assert!(check_reserve_floor(1_000, 1_000, 100, true).is_ok());
+        assert!(check_reserve_floor(1_000, 1_000, 0, false).is_ok());
+    }
+
+    #[test]
+    fn withdrawal_event_reports_the_proportional_share() {
+        let mut coin_mint_account = TestAccount::new(Pubkey::new_unique(), spl_token::ID, vec![]);
+        let mut pc_mint_account = TestAccount::new(Pubkey::new_unique(), spl_token::ID, vec![]);
+        // 500 of 10_000 LP against 100_000 coin and 400_000 pc
+        let quote = quote_withdraw(
+            100_000,
+            400_000,
+            500,
+            10_000,
+            &coin_mint_account.info(),
+            &pc_mint_account.info(),
+            0,
+        )
+        .unwrap();
+        let result = WithdrawResult::from_quote(500, &quote);
+        assert_eq!(
+            result,
+            WithdrawResult {
+                lp_burned: 500,
+                coin_out: 5_000,
+                pc_out: 20_000,
+            }
+        );
+
+        let (pool, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let event = result.event(pool, user, 1_700_000_000);
+        let decoded = WithdrawalEvent::try_from_slice(&event.try_to_vec().unwrap()).unwrap();
+        assert_eq!((decoded.pool, decoded.user), (pool, user));
+        assert_eq!(decoded.lp_amount, 500);
+        assert_eq!((decoded.coin_amount, decoded.pc_amount), (5_000, 20_000));
+        assert_eq!(decoded.timestamp, 1_700_000_000);
+    }
+
+    #[test]
+    fn single_sided_result_pays_only_the_wanted_asset() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+
+        for receive_coin in [true, false] {
+            let quote = quote_single_sided_withdraw(
+                &pool,
+                100_000,
+                400_000,
+                10_000,
+                100_000,
+                receive_coin,
+                0,
+            )
+            .unwrap();
+            let result = WithdrawResult::single_sided(10_000, &quote, receive_coin);
+            let expected = if receive_coin {
+                (quote.amount_out, 0)
+            } else {
+                (0, quote.amount_out)
+            };
+            assert_eq!((result.coin_out, result.pc_out), expected);
+            assert_eq!(result.lp_burned, 10_000);
+
+            // Anchor sets the borsh encoding of the handler's return value
+            let data = result.try_to_vec().unwrap();
+            assert_eq!(data.len(), 24);
+            assert_eq!(WithdrawResult::try_from_slice(&data).unwrap(), result);
+        }
+    }
+
+    #[test]
+    fn full_exit_leaves_exactly_the_owed_fees() {
+        let pool = Tradium {
+            coin_fees_owed: 300,
+            pc_fees_owed: 1_200,
+            ..Default::default()
+        };
+        let (coin_vault, pc_vault) = (100_300, 401_200);
+
+        let (coin_reserve, pc_reserve) = pool.active_reserves(coin_vault, pc_vault).unwrap();
+        let (coin_out, pc_out) =
+            withdraw_amounts(coin_reserve, pc_reserve, 200_000, 200_000).unwrap();
+        assert_eq!((coin_out, pc_out), (100_000, 400_000));
+
+        // The owed fees are all that is left, and still fully collectable
+        let (coin_left, pc_left) = (coin_vault - coin_out, pc_vault - pc_out);
+        assert_eq!((coin_left, pc_left), (300, 1_200));
+        assert_eq!(pool.active_reserves(coin_left, pc_left).unwrap(), (0, 0));
+    }
+
+    #[test]
+    fn non_ata_lp_account_must_be_owned_by_signer() {
+        let signer = Pubkey::new_unique();
+        // A non-ATA LP account owned by the signer is accepted
+        let lp_account = spl_token::state::Account {
+            mint: Pubkey::new_unique(),
+            owner: signer,
+            amount: 1_000,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        assert!(check_lp_account_owner(&lp_account.owner, &signer).is_ok());
+
+        assert_eq!(
+            check_lp_account_owner(&lp_account.owner, &Pubkey::new_unique()).unwrap_err(),
+            TradiumError::InvalidLpAccountOwner.into()
+        );
+    }
+
+    #[cfg(feature = "token-2022")]
+    #[test]
+    fn quote_withdraw_nets_out_transfer_fee() {
+        use anchor_lang::solana_program::program_pack::Pack;
+
+        let token_2022 = spl_token_2022::ID;
+        // 1%
+        let mut coin_mint_account = TestAccount::new(
+            Pubkey::new_unique(),
+            token_2022,
+            shared::transfer_fee_mint_data(100),
+        );
+        let coin_mint = coin_mint_account.info();
+        let mut pc_mint_account = TestAccount::new(
+            Pubkey::new_unique(),
+            spl_token::ID,
+            vec![0u8; spl_token::state::Mint::LEN],
+        );
+        let pc_mint = pc_mint_account.info();
+
+        let quote =
+            quote_withdraw(100_000, 400_000, 1_000, 2_000, &coin_mint, &pc_mint, 0).unwrap();
+        assert_eq!(
+            quote,
+            WithdrawQuote {
+                coin_amount: 50_000,
+                pc_amount: 200_000,
+                coin_received: 49_500,
+                pc_received: 200_000,
+            }
+        );
+    }
+
+    #[test]
+    fn withdrawing_down_to_the_lock_boundary_leaves_the_locked_share() {
+        let pool = Tradium {
+            locked_lp: 1_000,
+            ..Default::default()
+        };
+        let minted = 9_000;
+        let claim_supply = pool.claim_supply(minted).unwrap();
+        assert_eq!(claim_supply, 10_000);
+
+        // All but one minted LP, then the last one: the locked tenth stays behind
+        let (coin, pc) = withdraw_amounts(100_000, 400_000, minted - 1, claim_supply).unwrap();
+        assert_eq!((coin, pc), (89_990, 359_960));
+        let (coin_reserve, pc_reserve) = (100_000 - coin, 400_000 - pc);
+        let (coin, pc) =
+            withdraw_amounts(coin_reserve, pc_reserve, 1, claim_supply - (minted - 1)).unwrap();
+        assert_eq!((coin, pc), (10, 40));
+        assert_eq!((coin_reserve - coin, pc_reserve - pc), (10_000, 40_000));
+    }
+
+    #[test]
+    fn withdraw_after_reserve_shift_fails_min_outs() {
+        let quote_at = |coin_reserve, pc_reserve| {
+            let (coin_amount, pc_amount) =
+                withdraw_amounts(coin_reserve, pc_reserve, 1_000, 10_000).unwrap();
+            WithdrawQuote {
+                coin_amount,
+                pc_amount,
+                coin_received: coin_amount,
+                pc_received: pc_amount,
+            }
+        };
+
+        // Quoted at 100_000/400_000, the LP expects 10_000 coin and 40_000 pc
+        let quoted = quote_at(100_000, 400_000);
+        assert!(check_min_withdraw_out(&quoted, 10_000, 40_000).is_ok());
+
+        // A front-running swap selling pc leaves less coin behind
+        let shifted = quote_at(90_000, 445_000);
+        assert!(check_min_withdraw_out(&shifted, 0, 40_000).is_ok());
+        assert_eq!(
+            check_min_withdraw_out(&shifted, 9_900, 40_000).unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+        assert_eq!(
+            check_min_withdraw_out(&quote_at(110_000, 364_000), 9_900, 39_600).unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+    }
+
+    #[test]
+    fn tiny_single_sided_withdraw_reverts() {
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+
+        // One LP of a million is worth no whole unit of either reserve
+        for receive_coin in [true, false] {
+            assert_eq!(
+                quote_single_sided_withdraw(&pool, 100_000, 400_000, 1, 1_000_000, receive_coin, 0)
+                    .unwrap_err(),
+                TradiumError::InsufficientWithdrawal.into()
+            );
+        }
+
+        // A real share pays out, but still honours min_out
+        let quote =
+            quote_single_sided_withdraw(&pool, 100_000, 400_000, 10_000, 100_000, true, 0).unwrap();
+        assert_eq!((quote.coin_amount, quote.pc_amount), (10_000, 40_000));
+        assert_eq!(quote.fee_amount, 100);
+        assert!(quote.amount_out > quote.coin_amount);
+        assert_eq!(
+            quote_single_sided_withdraw(
+                &pool,
+                100_000,
+                400_000,
+                10_000,
+                100_000,
+                true,
+                quote.amount_out + 1
+            )
+            .unwrap_err(),
+            TradiumError::SlippageExceeded.into()
+        );
+    }
+
+    #[test]
+    fn single_sided_swap_passes_the_swap_guards() {
+        use crate::constants::{POOL_STATUS_ACTIVE, POOL_STATUS_PAUSED};
+
+        let mut pool = Tradium {
+            status: POOL_STATUS_ACTIVE,
+            ..Default::default()
+        };
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+        let quote =
+            quote_single_sided_withdraw(&pool, 100_000, 400_000, 10_000, 100_000, true, 0).unwrap();
+        let (swap_direction, swapped, bought) = quote.swapped_leg(true).unwrap();
+        assert_eq!((swap_direction, swapped), (1, 40_000));
+        assert_eq!(bought, quote.amount_out - quote.coin_amount);
+        assert!(!check_single_sided_swap(&pool, None, &quote, true, 0, 100).unwrap());
+
+        // A paused pool still takes plain withdrawals, but not this swap
+        let mut paused = pool.clone();
+        paused.status = POOL_STATUS_PAUSED;
+        paused.pause_expiry = i64::MAX;
+        assert_eq!(
+            check_single_sided_swap(&paused, None, &quote, true, 0, 100).unwrap_err(),
+            TradiumError::InvalidPoolState.into()
+        );
+
+        // The bought coin counts against the coin output cap
+        let mut capped = pool.clone();
+        capped.max_output_per_swap_coin = bought - 1;
+        assert_eq!(
+            check_single_sided_swap(&capped, None, &quote, true, 0, 100).unwrap_err(),
+            TradiumError::OutputCapExceeded.into()
+        );
+
+        // A deviation limit needs the oracle
+        let mut guarded = pool.clone();
+        guarded.max_oracle_deviation_bps = 100;
+        assert_eq!(
+            check_single_sided_swap(&guarded, None, &quote, true, 0, 100).unwrap_err(),
+            TradiumError::PriceOracleRequired.into()
+        );
+
+        // The swapped pc is a large swap, throttled like any other
+        let mut throttled = pool.clone();
+        throttled.large_swap_threshold_pc = swapped;
+        throttled.large_swap_slot_delay = 10;
+        assert!(check_single_sided_swap(&throttled, None, &quote, true, 0, 100).unwrap());
+        throttled.last_large_swap_slot = 95;
+        assert_eq!(
+            check_single_sided_swap(&throttled, None, &quote, true, 0, 100).unwrap_err(),
+            TradiumError::SwapThrottled.into()
+        );
+    }
+
+    #[test]
+    fn single_sided_withdraw_matches_withdraw_then_swap() {
+        use crate::instructions::swap::swap_preflight;
+
+        let mut pool = Tradium::default();
+        pool.fees.swap_fee_numerator = 25;
+        pool.fees.swap_fee_denominator = 10_000;
+        // Fees already owed sit in the vaults but outside the reserves
+        pool.coin_fees_owed = 70;
+        pool.pc_fees_owed = 30;
+        let (coin_reserve, pc_reserve) = (1_000_000, 4_000_000);
+        let (lp_amount, total_lp_supply) = (50_000, 2_000_000);
+
+        for zero_for_coin in [true, false] {
+            let quote = quote_single_sided_withdraw(
+                &pool,
+                coin_reserve,
+                pc_reserve,
+                lp_amount,
+                total_lp_supply,
+                zero_for_coin,
+                0,
+            )
+            .unwrap();
+
+            // Withdraw both legs, then swap the unwanted one against what is left
+            let (coin_amount, pc_amount) =
+                withdraw_amounts(coin_reserve, pc_reserve, lp_amount, total_lp_supply).unwrap();
+            assert_eq!(
+                (quote.coin_amount, quote.pc_amount),
+                (coin_amount, pc_amount)
+            );
+            let (swap_in, swap_direction, kept) = if zero_for_coin {
+                (pc_amount, 1, coin_amount)
+            } else {
+                (coin_amount, 0, pc_amount)
+            };
+            let swap = swap_preflight(
+                &pool,
+                coin_reserve - coin_amount + pool.coin_fees_owed,
+                pc_reserve - pc_amount + pool.pc_fees_owed,
+                swap_in,
+                0,
+                swap_direction,
+            )
+            .unwrap();
+
+            assert_eq!(quote.amount_out, kept + swap.amount_out);
+            assert_eq!(quote.fee_amount, swap.fee_amount);
+        }
+    }
+}
@@ -3,46 +3,338 @@ use anchor_lang::prelude::*;
 pub mod constants;
 pub use constants::*;
 
+pub mod curve;
+
 pub mod error;
-use crate::error::TradiumError;
 
 pub mod instructions;
 pub use instructions::*;
 
-use crate::shared;
+pub mod oracle;
+
+pub mod pda;
 
 pub mod state;
 pub use state::*;
 
 declare_id!("B2Sk1Fk1UCorbCvwkWRAZAp75DnNULia9ceuFHYgV1cK");
 
-#[program]
-pub mod tradium {
+// `#[program]` emits its IDL instruction handlers beside the program module,
+// and those still call the deprecated `AccountInfo::realloc`. Wrapping the
+// macro keeps the allow on the generated code only.
+#[allow(deprecated)]
+mod program_entry {
     use super::*;
 
-    pub fn initialize_pool(
-        ctx: Context<InitializePool>,
-        bump: u8,
-        initial_coin_amount: u64,
-        initial_pc_amount: u64,
-    ) -> Result<()> {
-        instructions::initialize_pool(ctx, initial_coin_amount, initial_pc_amount)
-    }
+    #[program]
+    pub mod tradium {
+        use super::*;
 
-    pub fn deposit(ctx: Context<Deposit>, amount_coin: u64, amount_pc: u64) -> Result<()> {
-        instructions::deposit(ctx, amount_coin, amount_pc)
-    }
+        #[allow(clippy::too_many_arguments, unused_variables)]
+        pub fn initialize_pool(
+            ctx: Context<InitializePool>,
+            bump: u8,
+            initial_coin_amount: u64,
+            initial_pc_amount: u64,
+            bootstrap_swaps: u64,
+            bootstrap_fee_numerator: u64,
+            soulbound_lp: bool,
+            owner: Option<Pubkey>,
+            fee_recipient: Option<Pubkey>,
+            curve_type: u8,
+            amp_coefficient: u64,
+            liquidity_lock: Option<u64>,
+        ) -> Result<()> {
+            instructions::handle_initialize_pool(
+                ctx,
+                initial_coin_amount,
+                initial_pc_amount,
+                bootstrap_swaps,
+                bootstrap_fee_numerator,
+                soulbound_lp,
+                owner,
+                fee_recipient,
+                curve_type,
+                amp_coefficient,
+                liquidity_lock,
+            )
+        }
 
-    pub fn withdraw(ctx: Context<Withdraw>, lp_amount: u64) -> Result<()> {
-        instructions::withdraw(ctx, lp_amount)
-    }
+        pub fn deposit<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+            amount_coin: u64,
+            amount_pc: u64,
+            min_lp_out: u64,
+        ) -> Result<DepositResult> {
+            instructions::handle_deposit(ctx, amount_coin, amount_pc, min_lp_out)
+        }
+
+        pub fn withdraw<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+            lp_amount: u64,
+            min_coin_out: u64,
+            min_pc_out: u64,
+        ) -> Result<WithdrawResult> {
+            instructions::handle_withdraw(ctx, lp_amount, min_coin_out, min_pc_out)
+        }
+
+        pub fn swap<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+            amount_in: u64,
+            min_amount_out: u64,
+            swap_direction: u8,
+            create_output_ata: bool,
+            deadline: i64,
+        ) -> Result<SwapResult> {
+            instructions::handle_swap(
+                ctx,
+                amount_in,
+                min_amount_out,
+                swap_direction,
+                create_output_ata,
+                deadline,
+            )
+        }
+
+        pub fn get_whitelisted_hooks(ctx: Context<GetWhitelistedHooks>) -> Result<Vec<Pubkey>> {
+            instructions::handle_get_whitelisted_hooks(ctx)
+        }
+
+        pub fn set_fees(
+            ctx: Context<SetFees>,
+            swap_fee_numerator: u64,
+            swap_fee_denominator: u64,
+        ) -> Result<()> {
+            instructions::handle_set_fees(ctx, swap_fee_numerator, swap_fee_denominator)
+        }
+
+        pub fn set_sys_decimal_value(
+            ctx: Context<SetSysDecimalValue>,
+            sys_decimal_value: u64,
+        ) -> Result<()> {
+            instructions::handle_set_sys_decimal_value(ctx, sys_decimal_value)
+        }
+
+        pub fn set_reserve_floors(
+            ctx: Context<SetReserveFloors>,
+            min_coin_reserve: u64,
+            min_pc_reserve: u64,
+        ) -> Result<()> {
+            instructions::handle_set_reserve_floors(ctx, min_coin_reserve, min_pc_reserve)
+        }
+
+        pub fn deposit_balanced<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+            amount_coin: u64,
+            max_pc: u64,
+        ) -> Result<DepositResult> {
+            instructions::handle_deposit_balanced(ctx, amount_coin, max_pc)
+        }
+
+        pub fn set_swap_throttle(
+            ctx: Context<SetSwapThrottle>,
+            threshold_coin: u64,
+            threshold_pc: u64,
+            slot_delay: u64,
+        ) -> Result<()> {
+            instructions::handle_set_swap_throttle(ctx, threshold_coin, threshold_pc, slot_delay)
+        }
+
+        pub fn compound_fees(ctx: Context<CompoundFees>) -> Result<()> {
+            instructions::handle_compound_fees(ctx)
+        }
+
+        pub fn verify_invariants(ctx: Context<VerifyInvariants>) -> Result<u32> {
+            instructions::handle_verify_invariants(ctx)
+        }
+
+        pub fn flash_loan<'info>(
+            ctx: Context<'_, '_, 'info, 'info, FlashLoan<'info>>,
+            amount: u64,
+        ) -> Result<()> {
+            instructions::handle_flash_loan(ctx, amount)
+        }
+
+        pub fn swap_with_slippage_bps<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+            amount_in: u64,
+            max_slippage_bps: u64,
+            swap_direction: u8,
+        ) -> Result<SwapResult> {
+            instructions::handle_swap_with_slippage_bps(
+                ctx,
+                amount_in,
+                max_slippage_bps,
+                swap_direction,
+            )
+        }
+
+        pub fn set_price_oracle(
+            ctx: Context<SetPriceOracle>,
+            price_oracle: Pubkey,
+            max_oracle_deviation_bps: u64,
+        ) -> Result<()> {
+            instructions::handle_set_price_oracle(ctx, price_oracle, max_oracle_deviation_bps)
+        }
+
+        pub fn get_swap_quote(
+            ctx: Context<GetSwapQuote>,
+            amount_in: u64,
+            swap_direction: u8,
+        ) -> Result<TransferFeeAdjustedQuote> {
+            instructions::handle_get_swap_quote(ctx, amount_in, swap_direction)
+        }
+
+        pub fn set_max_output_per_swap(
+            ctx: Context<SetMaxOutputPerSwap>,
+            max_output_coin: u64,
+            max_output_pc: u64,
+        ) -> Result<()> {
+            instructions::handle_set_max_output_per_swap(ctx, max_output_coin, max_output_pc)
+        }
+
+        pub fn add_whitelisted_hook(
+            ctx: Context<UpdateWhitelistedHooks>,
+            hook: Pubkey,
+        ) -> Result<()> {
+            instructions::handle_add_whitelisted_hook(ctx, hook)
+        }
+
+        pub fn remove_whitelisted_hook(
+            ctx: Context<UpdateWhitelistedHooks>,
+            hook: Pubkey,
+            force: bool,
+        ) -> Result<()> {
+            instructions::handle_remove_whitelisted_hook(ctx, hook, force)
+        }
+
+        pub fn get_activity(ctx: Context<GetActivity>) -> Result<PoolActivity> {
+            instructions::handle_get_activity(ctx)
+        }
+
+        pub fn swap_exact_out<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Swap<'info>>,
+            amount_out: u64,
+            max_amount_in: u64,
+            swap_direction: u8,
+        ) -> Result<ExactOutSwapResult> {
+            instructions::handle_swap_exact_out(ctx, amount_out, max_amount_in, swap_direction)
+        }
+
+        pub fn swap_route<'info>(
+            ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+            amount_in: u64,
+            swap_directions: Vec<u8>,
+            hop_min_amounts_out: Vec<u64>,
+            min_amount_out: u64,
+        ) -> Result<Vec<SwapResult>> {
+            instructions::handle_swap_route(
+                ctx,
+                amount_in,
+                swap_directions,
+                hop_min_amounts_out,
+                min_amount_out,
+            )
+        }
+
+        pub fn set_max_deposit(
+            ctx: Context<SetMaxDeposit>,
+            max_deposit_coin: u64,
+            max_deposit_pc: u64,
+        ) -> Result<()> {
+            instructions::handle_set_max_deposit(ctx, max_deposit_coin, max_deposit_pc)
+        }
+
+        pub fn reconcile_lp_amount(ctx: Context<ReconcileLpAmount>) -> Result<()> {
+            instructions::handle_reconcile_lp_amount(ctx)
+        }
+
+        pub fn collect_protocol_fees<'info>(
+            ctx: Context<'_, '_, 'info, 'info, CollectProtocolFees<'info>>,
+        ) -> Result<()> {
+            instructions::handle_collect_protocol_fees(ctx)
+        }
+
+        pub fn update_fees(
+            ctx: Context<UpdateFees>,
+            trade_fee_numerator: u64,
+            trade_fee_denominator: u64,
+            swap_fee_numerator: u64,
+            swap_fee_denominator: u64,
+        ) -> Result<()> {
+            instructions::handle_update_fees(
+                ctx,
+                trade_fee_numerator,
+                trade_fee_denominator,
+                swap_fee_numerator,
+                swap_fee_denominator,
+            )
+        }
+
+        pub fn set_pool_status(ctx: Context<SetPoolStatus>, status: u64) -> Result<()> {
+            instructions::handle_set_pool_status(ctx, status)
+        }
+
+        pub fn find_pool(
+            ctx: Context<FindPool>,
+            mint_a: Pubkey,
+            mint_b: Pubkey,
+        ) -> Result<PoolLookup> {
+            instructions::handle_find_pool(ctx, mint_a, mint_b)
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        pub fn quote_swap(
+            ctx: Context<QuoteSwap>,
+            amount_in: u64,
+            swap_direction: u8,
+            coin_vault_amount: u64,
+            pc_vault_amount: u64,
+            fees: Fees,
+            curve_type: u8,
+            amp_coefficient: u64,
+        ) -> Result<TransferFeeAdjustedQuote> {
+            instructions::handle_quote_swap(
+                ctx,
+                amount_in,
+                swap_direction,
+                coin_vault_amount,
+                pc_vault_amount,
+                fees,
+                curve_type,
+                amp_coefficient,
+            )
+        }
+
+        pub fn set_auto_compound(ctx: Context<SetAutoCompound>, enabled: bool) -> Result<()> {
+            instructions::handle_set_auto_compound(ctx, enabled)
+        }
+
+        pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+            instructions::handle_sync(ctx)
+        }
+
+        pub fn skim<'info>(ctx: Context<'_, '_, 'info, 'info, Skim<'info>>) -> Result<()> {
+            instructions::handle_skim(ctx)
+        }
+
+        pub fn withdraw_single_sided<'info>(
+            ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>,
+            lp_amount: u64,
+            zero_for_coin: bool,
+            min_out: u64,
+        ) -> Result<WithdrawResult> {
+            instructions::handle_withdraw_single_sided(ctx, lp_amount, zero_for_coin, min_out)
+        }
+
+        pub fn sync_hook_flag(ctx: Context<SyncHookFlag>) -> Result<()> {
+            instructions::handle_sync_hook_flag(ctx)
+        }
 
-    pub fn swap(
-        ctx: Context<Swap>,
-        amount_in: u64,
-        min_amount_out: u64,
-        swap_direction: u8,
-    ) -> Result<()> {
-        instructions::swap(ctx, amount_in, min_amount_out, swap_direction)
+        pub fn enable_auto_unpause(ctx: Context<EnableAutoUnpause>) -> Result<()> {
+            instructions::handle_enable_auto_unpause(ctx)
+        }
     }
 }
+pub use program_entry::tradium;
+pub use program_entry::*;
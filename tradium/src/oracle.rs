@@ -0,0 +1,166 @@
+//! Optional external price reference for swaps.
+
+use crate::constants::MAX_ORACLE_AGE_SLOTS;
+use crate::error::TradiumError;
+use crate::state::Tradium;
+use anchor_lang::prelude::*;
+
+/// Layout the configured oracle account must start with: the reference price
+/// of one coin base unit in pc base units, scaled by `PRICE_SCALE`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub struct OraclePrice {
+    pub price: u128,
+    pub publish_slot: u64,
+}
+
+/// Checks a swap's execution price against the pool's oracle. A pool with a
+/// deviation limit configured cannot be swapped without its oracle.
+pub fn check_swap_against_oracle(
+    pool: &Tradium,
+    oracle: Option<&AccountInfo>,
+    execution_price: u128,
+    current_slot: u64,
+) -> Result<()> {
+    if pool.max_oracle_deviation_bps == 0 {
+        return Ok(());
+    }
+    let oracle = oracle.ok_or(TradiumError::PriceOracleRequired)?;
+    let oracle_price = read_oracle_price(pool, oracle, current_slot)?;
+    check_oracle_deviation(execution_price, oracle_price, pool.max_oracle_deviation_bps)
+}
+
+/// Reads the reference price from `oracle`, which must be the pool's
+/// configured `price_oracle` and published within `MAX_ORACLE_AGE_SLOTS`.
+pub fn read_oracle_price(pool: &Tradium, oracle: &AccountInfo, current_slot: u64) -> Result<u128> {
+    require_keys_eq!(
+        oracle.key(),
+        pool.price_oracle,
+        TradiumError::InvalidPriceOracle
+    );
+    let data = oracle.try_borrow_data()?;
+    let feed =
+        OraclePrice::deserialize(&mut &data[..]).map_err(|_| TradiumError::InvalidPriceOracle)?;
+    require!(feed.price > 0, TradiumError::InvalidPriceOracle);
+    require!(
+        current_slot.saturating_sub(feed.publish_slot) <= MAX_ORACLE_AGE_SLOTS,
+        TradiumError::StalePriceOracle
+    );
+    Ok(feed.price)
+}
+
+/// Rejects an execution price more than `max_deviation_bps` away from the
+/// oracle price, in either direction.
+pub fn check_oracle_deviation(
+    execution_price: u128,
+    oracle_price: u128,
+    max_deviation_bps: u64,
+) -> Result<()> {
+    let deviation_bps = execution_price
+        .abs_diff(oracle_price)
+        .checked_mul(crate::constants::FEE_DENOMINATOR as u128)
+        .ok_or(TradiumError::MathOverflow)?
+        / oracle_price;
+    require!(
+        deviation_bps <= max_deviation_bps as u128,
+        TradiumError::OracleDeviationExceeded
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::PRICE_SCALE;
+    use crate::shared::TestAccount;
+
+    fn mock_feed(price: u128) -> Vec<u8> {
+        OraclePrice {
+            price,
+            publish_slot: 1,
+        }
+        .try_to_vec()
+        .unwrap()
+    }
+
+    #[test]
+    fn mock_oracle_feed_is_validated() {
+        let oracle_key = Pubkey::new_unique();
+        let pool = Tradium {
+            price_oracle: oracle_key,
+            ..Default::default()
+        };
+        let owner = Pubkey::new_unique();
+        let mut oracle_account = TestAccount::new(oracle_key, owner, mock_feed(2 * PRICE_SCALE));
+        let oracle = oracle_account.info();
+        assert_eq!(
+            read_oracle_price(&pool, &oracle, 1).unwrap(),
+            2 * PRICE_SCALE
+        );
+
+        let other_pool = Tradium {
+            price_oracle: Pubkey::new_unique(),
+            ..Default::default()
+        };
+        assert_eq!(
+            read_oracle_price(&other_pool, &oracle, 1).unwrap_err(),
+            TradiumError::InvalidPriceOracle.into()
+        );
+    }
+
+    #[test]
+    fn configured_deviation_requires_a_fresh_oracle() {
+        let oracle_key = Pubkey::new_unique();
+        let mut pool = Tradium {
+            price_oracle: oracle_key,
+            ..Default::default()
+        };
+        let owner = Pubkey::new_unique();
+        let mut oracle_account = TestAccount::new(oracle_key, owner, mock_feed(PRICE_SCALE));
+        let oracle = oracle_account.info();
+
+        // Without a limit the oracle is neither needed nor read
+        assert!(check_swap_against_oracle(&pool, None, 2 * PRICE_SCALE, 1).is_ok());
+
+        pool.max_oracle_deviation_bps = 100;
+        assert_eq!(
+            check_swap_against_oracle(&pool, None, PRICE_SCALE, 1).unwrap_err(),
+            TradiumError::PriceOracleRequired.into()
+        );
+        assert!(check_swap_against_oracle(&pool, Some(&oracle), PRICE_SCALE, 1).is_ok());
+        assert_eq!(
+            check_swap_against_oracle(&pool, Some(&oracle), 2 * PRICE_SCALE, 1).unwrap_err(),
+            TradiumError::OracleDeviationExceeded.into()
+        );
+
+        // The feed was published at slot 1
+        assert!(check_swap_against_oracle(
+            &pool,
+            Some(&oracle),
+            PRICE_SCALE,
+            1 + MAX_ORACLE_AGE_SLOTS
+        )
+        .is_ok());
+        assert_eq!(
+            check_swap_against_oracle(&pool, Some(&oracle), PRICE_SCALE, 2 + MAX_ORACLE_AGE_SLOTS)
+                .unwrap_err(),
+            TradiumError::StalePriceOracle.into()
+        );
+    }
+
+    #[test]
+    fn deviation_at_and_beyond_threshold() {
+        let oracle_price = PRICE_SCALE;
+        // Exactly 1% away on either side passes a 100 bps limit
+        assert!(check_oracle_deviation(PRICE_SCALE * 99 / 100, oracle_price, 100).is_ok());
+        assert!(check_oracle_deviation(PRICE_SCALE * 101 / 100, oracle_price, 100).is_ok());
+        // Just beyond it does not
+        assert_eq!(
+            check_oracle_deviation(PRICE_SCALE * 102 / 100, oracle_price, 100).unwrap_err(),
+            TradiumError::OracleDeviationExceeded.into()
+        );
+        assert_eq!(
+            check_oracle_deviation(PRICE_SCALE * 98 / 100, oracle_price, 100).unwrap_err(),
+            TradiumError::OracleDeviationExceeded.into()
+        );
+    }
+}
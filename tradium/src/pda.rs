@@ -0,0 +1,101 @@
+use crate::constants::*;
+use crate::error::TradiumError;
+use anchor_lang::prelude::*;
+
+/// Orders a mint pair canonically: the lexicographically smaller pubkey is
+/// the coin mint, so a pair maps to the same pool in either order.
+pub fn canonical_mint_order(mint_a: &Pubkey, mint_b: &Pubkey) -> (Pubkey, Pubkey) {
+    if mint_b < mint_a {
+        (*mint_b, *mint_a)
+    } else {
+        (*mint_a, *mint_b)
+    }
+}
+
+/// Fails with `DuplicateMint` if both sides of a pair are the same mint.
+pub fn check_distinct_mints(mint_a: &Pubkey, mint_b: &Pubkey) -> Result<()> {
+    require_keys_neq!(*mint_a, *mint_b, TradiumError::DuplicateMint);
+    Ok(())
+}
+
+/// Canonical pool PDA for a mint pair, with its bump. The pair is ordered
+/// with `canonical_mint_order` first.
+pub fn pool_pda(coin_mint: &Pubkey, pc_mint: &Pubkey) -> (Pubkey, u8) {
+    let (coin_mint, pc_mint) = canonical_mint_order(coin_mint, pc_mint);
+    Pubkey::find_program_address(
+        &[POOL_SEED, coin_mint.as_ref(), pc_mint.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Canonical LP mint PDA for a pool (`[LP_MINT_SEED, pool]`).
+pub fn lp_mint_pda(pool: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[LP_MINT_SEED, pool.as_ref()], &crate::ID).0
+}
+
+/// Returns true if `lp_mint` is the canonical LP mint PDA of `pool`.
+pub fn is_canonical_lp_mint(pool: &Pubkey, lp_mint: &Pubkey) -> bool {
+    lp_mint_pda(pool) == *lp_mint
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_lp_mint_is_accepted() {
+        let pool = Pubkey::new_unique();
+        let lp_mint = lp_mint_pda(&pool);
+        assert!(is_canonical_lp_mint(&pool, &lp_mint));
+        assert!(!lp_mint.is_on_curve());
+    }
+
+    #[test]
+    fn non_canonical_lp_mint_is_rejected() {
+        let pool = Pubkey::new_unique();
+        let attacker_mint = Pubkey::new_unique();
+        assert!(!is_canonical_lp_mint(&pool, &attacker_mint));
+
+        // The LP mint of another pool is not canonical for this one either
+        let other_pool = Pubkey::new_unique();
+        assert!(!is_canonical_lp_mint(&pool, &lp_mint_pda(&other_pool)));
+    }
+
+    #[test]
+    fn input_mint_equal_to_lp_mint_pda_is_detected() {
+        let (pool, _) = pool_pda(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let colliding_mint = lp_mint_pda(&pool);
+        assert!(is_canonical_lp_mint(&pool, &colliding_mint));
+        assert!(!is_canonical_lp_mint(&pool, &Pubkey::new_unique()));
+    }
+
+    #[test]
+    fn pair_maps_to_one_pool_in_either_order() {
+        let mint_a = Pubkey::new_unique();
+        let mint_b = Pubkey::new_unique();
+
+        let ordered = canonical_mint_order(&mint_a, &mint_b);
+        assert_eq!(ordered, canonical_mint_order(&mint_b, &mint_a));
+        assert!(ordered.0 < ordered.1);
+
+        assert_eq!(pool_pda(&mint_a, &mint_b), pool_pda(&mint_b, &mint_a));
+        assert_eq!(
+            pool_pda(&mint_a, &mint_b).0,
+            Pubkey::find_program_address(
+                &[POOL_SEED, ordered.0.as_ref(), ordered.1.as_ref()],
+                &crate::ID
+            )
+            .0
+        );
+    }
+
+    #[test]
+    fn same_mint_twice_is_a_duplicate_mint() {
+        let mint = Pubkey::new_unique();
+        assert!(check_distinct_mints(&mint, &Pubkey::new_unique()).is_ok());
+        assert_eq!(
+            check_distinct_mints(&mint, &mint).unwrap_err(),
+            TradiumError::DuplicateMint.into()
+        );
+    }
+}
@@ -1,25 +1,37 @@
 // programs/state/tradium.rs
+use crate::constants::{
+    MAX_PAUSE_DURATION, MAX_WHITELISTED_HOOKS, MIN_LIQUIDITY, POOL_SEED, POOL_STATUS_ACTIVE,
+    POOL_STATUS_PAUSED,
+};
+use crate::curve::SwapCurve;
+use crate::error::TradiumError;
 use anchor_lang::prelude::*;
 
 pub const MAX_ORDER_LIMIT: usize = 10;
-pub const MAX_WHITELISTED_HOOKS: usize = 10;
 
 #[account]
 #[derive(Default, PartialEq, Debug, InitSpace)] // Add InitSpace here
 pub struct Tradium {
     pub status: u64,
     pub nonce: [u8; 1],
-    pub order_num: u64,
-    pub depth: u64,
+    /// Amplification coefficient of a stable pool, see `curve_type`; formerly
+    /// the unused `order_num`
+    pub amp_coefficient: u64,
+    /// LP the first deposit locks for good, see `first_deposit_lock`;
+    /// formerly the unused `depth`
+    pub liquidity_lock: u64,
     pub coin_decimals: u64,
     pub pc_decimals: u64,
     pub state: u64,
     pub reset_flag: u64,
-    pub min_size: u64,
-    pub vol_max_cut_ratio: u64,
-    pub amount_wave: u64,
-    pub coin_lot_size: u64,
-    pub pc_lot_size: u64,
+    /// Receives the protocol fees; formerly the unused `min_size`,
+    /// `vol_max_cut_ratio`, `amount_wave` and `coin_lot_size`. Unset on pools
+    /// created before it existed, see `protocol_fee_recipient`
+    pub fee_recipient: Pubkey,
+    /// LP permanently locked in the pool. It counts towards the supply every
+    /// claim is computed against but is never minted, so it can never be
+    /// burned and its share of the reserves stays. Formerly `pc_lot_size`
+    pub locked_lp: u64,
     pub min_price_multiplier: u64,
     pub max_price_multiplier: u64,
     pub sys_decimal_value: u64,
@@ -30,20 +42,290 @@ pub struct Tradium {
     pub coin_vault_mint: Pubkey,
     pub pc_vault_mint: Pubkey,
     pub lp_mint: Pubkey,
-    pub open_orders: Pubkey,
-    pub market: Pubkey,
-    pub market_program: Pubkey,
-    pub target_orders: Pubkey,
+    /// Swaps left in the bootstrap period, which pay `bootstrap_fee_numerator`
+    pub bootstrap_swaps_remaining: u64,
+    /// Fee numerator (over `fees.swap_fee_denominator`) charged instead of the
+    /// trade and swap fees during the bootstrap period
+    pub bootstrap_fee_numerator: u64,
+    /// Set while a flash loan is outstanding, so it cannot be nested
+    pub flash_loan_locked: bool,
+    /// Set at init if the caller passed the mints in non-canonical order and
+    /// their coin/pc roles were swapped (see `pda::canonical_mint_order`)
+    pub mints_reversed: bool,
+    /// LP accounts are kept frozen by the pool, so LP can only be minted and
+    /// burned through deposit/withdraw, never transferred
+    pub soulbound_lp: bool,
+    /// Canonical bump of the pool PDA, set once at init and never mutated.
+    /// `nonce` keeps a copy for older clients but is not used for signing.
+    pub bump: u8,
+    /// Owed owner fees are compounded into the reserves before every swap,
+    /// so swaps price against them; formerly the first `padding4` byte
+    pub auto_compound_fees: bool,
+    /// Pricing curve, set once at init (`CURVE_TYPE_*`): constant product (0,
+    /// the default) or StableSwap; formerly the second `padding4` byte
+    pub curve_type: u8,
+    /// Set if either pool mint carries a transfer hook; when false, transfer
+    /// hook validation is skipped entirely. Set at init and backfilled for
+    /// older pools by `sync_hook_flag`; formerly the third `padding4` byte
+    pub has_any_hook_mints: bool,
+    /// Pauses lapse after `MAX_PAUSE_DURATION` unless renewed, see
+    /// `is_active`. Off by default and, once enabled, never disabled again;
+    /// formerly the last `padding4` byte
+    pub auto_unpause: bool,
+    /// Anti-replay counter advanced by every swap and deposit. It is purely
+    /// informational, so it wraps at `u64::MAX` rather than failing.
+    pub counter: u64,
+    /// Reference price feed swaps may be checked against (see `oracle`)
+    pub price_oracle: Pubkey,
+    /// Max deviation of a swap's execution price from the oracle (0 = no check)
+    pub max_oracle_deviation_bps: u64,
+    /// Max coin a single swap may pay out (0 = unlimited)
+    pub max_output_per_swap_coin: u64,
+    /// Max pc a single swap may pay out (0 = unlimited)
+    pub max_output_per_swap_pc: u64,
+    /// Unix timestamp of pool creation
+    pub created_at: i64,
+    /// Unix timestamp of the most recent deposit, withdrawal, swap or flash loan
+    pub last_activity_ts: i64,
+    /// Max coin a single deposit may add (0 = unlimited)
+    pub max_deposit_coin: u64,
+    /// Max pc a single deposit may add (0 = unlimited)
+    pub max_deposit_pc: u64,
+    /// Unix timestamp at which the current pause lapses and the pool is
+    /// active again; formerly the last reserved padding slot
+    pub pause_expiry: i64,
     pub coin_token_program: Pubkey,
     pub pc_token_program: Pubkey,
     pub whitelisted_transfer_hooks: [Pubkey; MAX_WHITELISTED_HOOKS],
     pub num_whitelisted_hooks: u8,
-    pub padding1: [u64; 6],
+    /// Withdrawals may not push the coin vault below this (0 = no floor)
+    pub min_coin_reserve: u64,
+    /// Withdrawals may not push the pc vault below this (0 = no floor)
+    pub min_pc_reserve: u64,
+    /// Coin-in swaps with `amount_in` at or above this are throttled (0 = no
+    /// throttle)
+    pub large_swap_threshold_coin: u64,
+    /// Slots that must pass after a large swap before the next one
+    pub large_swap_slot_delay: u64,
+    /// Slot of the most recent large swap
+    pub last_large_swap_slot: u64,
+    /// Owner fees held in the coin vault but excluded from the active reserves
+    pub coin_fees_owed: u64,
     pub amm_owner: Pubkey,
     pub lp_amount: u64,
-    pub client_order_id: u64,
+    /// Pc-in swaps with `amount_in` at or above this are throttled (0 = no
+    /// throttle); formerly the unused `client_order_id`
+    pub large_swap_threshold_pc: u64,
     pub recent_epoch: u64,
-    pub padding2: u64,
+    /// Owner fees held in the pc vault but excluded from the active reserves
+    pub pc_fees_owed: u64,
+}
+
+/// Bytes every existing pool account was allocated after its discriminator,
+/// summed over the original layout rather than taken from the current one.
+/// New fields take the place of unused ones rather than growing the layout,
+/// so no existing account ever needs a realloc.
+pub const TRADIUM_ALLOCATED_SPACE: usize = 8 // status
+    + 1 // nonce
+    + 14 * 8 // order_num through sys_decimal_value
+    + 64 // fees
+    + 684 // state_data
+    + 11 * 32 // coin_vault through pc_token_program
+    + MAX_WHITELISTED_HOOKS * 32 // whitelisted_transfer_hooks
+    + 1 // num_whitelisted_hooks
+    + 6 * 8 // padding1
+    + 32 // amm_owner
+    + 4 * 8; // lp_amount, client_order_id, recent_epoch, padding2
+const _: () = assert!(Tradium::INIT_SPACE == TRADIUM_ALLOCATED_SPACE);
+
+impl Tradium {
+    /// The whitelisted hooks actually in use, `whitelisted_transfer_hooks[..num_whitelisted_hooks]`
+    pub fn active_whitelisted_hooks(&self) -> &[Pubkey] {
+        let len = (self.num_whitelisted_hooks as usize).min(self.whitelisted_transfer_hooks.len());
+        &self.whitelisted_transfer_hooks[..len]
+    }
+
+    /// Fee charged on the next swap, as `(numerator, denominator)`: the
+    /// reduced bootstrap fee while bootstrap swaps remain, the trade fee plus
+    /// the swap fee after.
+    pub fn effective_swap_fee(&self) -> Result<(u64, u64)> {
+        if self.bootstrap_swaps_remaining > 0 {
+            Ok((self.bootstrap_fee_numerator, self.fees.swap_fee_denominator))
+        } else {
+            self.fees.total_swap_fee()
+        }
+    }
+
+    /// The pool's pricing curve, from `curve_type` and `amp_coefficient`.
+    pub fn swap_curve(&self) -> Result<SwapCurve> {
+        SwapCurve::from_config(self.curve_type, self.amp_coefficient)
+    }
+
+    /// Advances the anti-replay counter, wrapping at the ceiling so a busy
+    /// pool can never be bricked by it. The bump is never touched.
+    pub fn advance_counter(&mut self) {
+        self.counter = self.counter.wrapping_add(1);
+    }
+
+    /// Seeds the pool PDA signs with: `[POOL_SEED, coin_mint, pc_mint, bump]`.
+    pub fn signer_seeds(&self) -> [&[u8]; 4] {
+        [
+            POOL_SEED,
+            self.coin_vault_mint.as_ref(),
+            self.pc_vault_mint.as_ref(),
+            std::slice::from_ref(&self.bump),
+        ]
+    }
+
+    /// Fails with `BumpMismatch` unless the signer seeds, stored bump included,
+    /// derive `pool_key`.
+    pub fn check_bump(&self, pool_key: &Pubkey) -> Result<()> {
+        let derived = Pubkey::create_program_address(&self.signer_seeds(), &crate::ID)
+            .map_err(|_| TradiumError::BumpMismatch)?;
+        require_keys_eq!(derived, *pool_key, TradiumError::BumpMismatch);
+        Ok(())
+    }
+
+    /// Records pool activity at `now`; the timestamp never moves backwards.
+    pub fn record_activity(&mut self, now: i64) {
+        self.last_activity_ts = self.last_activity_ts.max(now);
+    }
+
+    /// Owner of the token accounts `collect_protocol_fees` pays out to: the
+    /// fee recipient, or the owner on pools that never set one.
+    pub fn protocol_fee_recipient(&self) -> Pubkey {
+        if self.fee_recipient == Pubkey::default() {
+            self.amm_owner
+        } else {
+            self.fee_recipient
+        }
+    }
+
+    /// Supply LP claims on the reserves are computed against: the minted LP
+    /// plus the locked LP, whose share no withdrawal can take.
+    pub fn claim_supply(&self, lp_mint_supply: u64) -> Result<u64> {
+        lp_mint_supply
+            .checked_add(self.locked_lp)
+            .ok_or(TradiumError::MathOverflow.into())
+    }
+
+    /// Records the vault balances the pool accounts for, `state_data`'s
+    /// `pool_coin_amount` and `pool_pc_amount`, returning the previous ones.
+    /// Every instruction that moves a vault balance records the result, so
+    /// whatever a vault holds beyond them arrived outside the pool: the
+    /// surplus `skim` may sweep.
+    pub fn record_reserves(&mut self, coin_vault_amount: u64, pc_vault_amount: u64) -> (u64, u64) {
+        (
+            std::mem::replace(&mut self.state_data.pool_coin_amount, coin_vault_amount),
+            std::mem::replace(&mut self.state_data.pool_pc_amount, pc_vault_amount),
+        )
+    }
+
+    /// LP the first deposit locks: the pool's configured lock, or
+    /// `MIN_LIQUIDITY` on pools created before it was configurable.
+    pub fn first_deposit_lock(&self) -> u64 {
+        if self.liquidity_lock == 0 {
+            MIN_LIQUIDITY
+        } else {
+            self.liquidity_lock
+        }
+    }
+
+    /// Pauses the pool, recording `now + MAX_PAUSE_DURATION` as the expiry.
+    /// Pausing again renews it, so with `auto_unpause` an owner can never lock
+    /// the pool indefinitely by inaction.
+    pub fn pause(&mut self, now: i64) -> Result<()> {
+        self.status = POOL_STATUS_PAUSED;
+        self.pause_expiry = now
+            .checked_add(MAX_PAUSE_DURATION)
+            .ok_or(TradiumError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Whether the pool is active at `now`: explicitly so, or paused with the
+    /// pause lapsed on an `auto_unpause` pool.
+    pub fn is_active(&self, now: i64) -> bool {
+        match self.status {
+            POOL_STATUS_ACTIVE => true,
+            POOL_STATUS_PAUSED => self.auto_unpause && now >= self.pause_expiry,
+            _ => false,
+        }
+    }
+
+    /// Vault balances minus the fees owed to the owner: the reserves the curve
+    /// prices against and LPs have a claim on.
+    pub fn active_reserves(
+        &self,
+        coin_vault_amount: u64,
+        pc_vault_amount: u64,
+    ) -> Result<(u64, u64)> {
+        Ok((
+            coin_vault_amount
+                .checked_sub(self.coin_fees_owed)
+                .ok_or(TradiumError::InsufficientLiquidity)?,
+            pc_vault_amount
+                .checked_sub(self.pc_fees_owed)
+                .ok_or(TradiumError::InsufficientLiquidity)?,
+        ))
+    }
+
+    /// Folds the owed owner fees back into the active reserves.
+    pub fn compound_owed_fees(&mut self) -> (u64, u64) {
+        self.take_owed_fees()
+    }
+
+    /// Zeroes the owed owner fees, returning what was owed.
+    pub fn take_owed_fees(&mut self) -> (u64, u64) {
+        let owed = (self.coin_fees_owed, self.pc_fees_owed);
+        self.coin_fees_owed = 0;
+        self.pc_fees_owed = 0;
+        owed
+    }
+
+    /// Sets the owner's part of a swap fee, paid in the input token, aside.
+    /// See `Fees::owner_fee_share`.
+    pub fn accrue_owner_fee(&mut self, swap_direction: u8, fee_amount: u64) -> Result<()> {
+        let fees_owed = if swap_direction == 0 {
+            &mut self.coin_fees_owed
+        } else {
+            &mut self.pc_fees_owed
+        };
+        *fees_owed = fees_owed
+            .checked_add(fee_amount)
+            .ok_or(TradiumError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// Number of decimals encoded by `sys_decimal_value` (a power of ten).
+    /// An unset (zero) value is treated as zero decimals.
+    pub fn sys_decimals(&self) -> Result<u64> {
+        sys_decimals_from_value(self.sys_decimal_value)
+    }
+}
+
+/// Number of decimals encoded by a `sys_decimal_value` of `10^n`; zero is
+/// treated as unset, i.e. zero decimals.
+pub fn sys_decimals_from_value(sys_decimal_value: u64) -> Result<u64> {
+    if sys_decimal_value == 0 {
+        return Ok(0);
+    }
+    power_of_ten_exponent(sys_decimal_value)
+        .map(u64::from)
+        .ok_or(TradiumError::InvalidSysDecimalValue.into())
+}
+
+/// Returns `n` if `value == 10^n`, otherwise `None`.
+pub fn power_of_ten_exponent(value: u64) -> Option<u32> {
+    if value == 0 {
+        return None;
+    }
+    let mut remaining = value;
+    let mut exponent = 0;
+    while remaining.is_multiple_of(10) {
+        remaining /= 10;
+        exponent += 1;
+    }
+    (remaining == 1).then_some(exponent)
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Debug, InitSpace)] // Change AnchorSize to InitSpace
@@ -58,6 +340,65 @@ pub struct Fees {
     pub swap_fee_denominator: u64,
 }
 
+impl Fees {
+    /// Fee a swap pays, as `(numerator, denominator)`: the trade fee, which
+    /// stays in the reserves for LPs, plus the swap fee, which is owed to the
+    /// owner.
+    pub fn total_swap_fee(&self) -> Result<(u64, u64)> {
+        if self.trade_fee_numerator == 0 {
+            return Ok((self.swap_fee_numerator, self.swap_fee_denominator));
+        }
+        if self.swap_fee_numerator == 0 {
+            return Ok((self.trade_fee_numerator, self.trade_fee_denominator));
+        }
+        if self.trade_fee_denominator == self.swap_fee_denominator {
+            let numerator = self
+                .trade_fee_numerator
+                .checked_add(self.swap_fee_numerator)
+                .ok_or(TradiumError::MathOverflow)?;
+            return Ok((numerator, self.swap_fee_denominator));
+        }
+        let (trade_weight, swap_weight) = self.fee_weights();
+        let numerator = trade_weight
+            .checked_add(swap_weight)
+            .ok_or(TradiumError::MathOverflow)?;
+        let denominator = self.trade_fee_denominator as u128 * self.swap_fee_denominator as u128;
+        Ok((
+            u64::try_from(numerator).map_err(|_| TradiumError::MathOverflow)?,
+            u64::try_from(denominator).map_err(|_| TradiumError::MathOverflow)?,
+        ))
+    }
+
+    /// The owner's part of a charged swap fee: the swap fee's share of
+    /// `total_swap_fee`, rounded down. The rest is the trade fee.
+    pub fn owner_fee_share(&self, fee_amount: u64) -> Result<u64> {
+        let (trade_weight, swap_weight) = self.fee_weights();
+        let total_weight = trade_weight
+            .checked_add(swap_weight)
+            .ok_or(TradiumError::MathOverflow)?;
+        if total_weight == 0 {
+            return Ok(0);
+        }
+        let share = (fee_amount as u128)
+            .checked_mul(swap_weight)
+            .ok_or(TradiumError::MathOverflow)?
+            / total_weight;
+        // At most `fee_amount`, so this always narrows back
+        u64::try_from(share).map_err(|_| TradiumError::MathOverflow.into())
+    }
+
+    /// The trade and swap fees over their common denominator.
+    fn fee_weights(&self) -> (u128, u128) {
+        if self.trade_fee_numerator == 0 {
+            return (0, self.swap_fee_numerator as u128);
+        }
+        (
+            self.trade_fee_numerator as u128 * self.swap_fee_denominator as u128,
+            self.swap_fee_numerator as u128 * self.trade_fee_denominator as u128,
+        )
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Default, PartialEq, Debug, InitSpace)] // Change AnchorSize to InitSpace
 pub struct StateData {
     pub initialized: bool,
@@ -101,3 +442,424 @@ pub struct StateData {
     pub pool_lp_amount: u64,
     pub padding: [u64; 3],
 }
+
+impl StateData {
+    /// Adds a deposit into the vaults to the running deposit totals.
+    pub fn record_deposit(&mut self, coin_amount: u64, pc_amount: u64) {
+        self.pool_total_deposit_coin = self.pool_total_deposit_coin.saturating_add(coin_amount);
+        self.pool_total_deposit_pc = self.pool_total_deposit_pc.saturating_add(pc_amount);
+    }
+
+    /// Takes a withdrawal out of the running deposit totals, which therefore
+    /// track deposits net of withdrawals. Swaps and fees move the reserves
+    /// too, so withdrawals can exceed what was deposited; the totals floor at
+    /// zero rather than failing the exit.
+    pub fn record_withdrawal(&mut self, coin_amount: u64, pc_amount: u64) {
+        self.pool_total_deposit_coin = self.pool_total_deposit_coin.saturating_sub(coin_amount);
+        self.pool_total_deposit_pc = self.pool_total_deposit_pc.saturating_sub(pc_amount);
+    }
+
+    /// Records a swap's vault-side amounts: what entered the input vault,
+    /// what left the output vault and the fee taken, as the last swap in that
+    /// direction and into the lifetime totals. Totals saturate rather than
+    /// fail, so analytics can never block a swap.
+    pub fn record_swap_volume(
+        &mut self,
+        swap_direction: u8,
+        amount_in: u64,
+        amount_out: u64,
+        fee_amount: u64,
+    ) {
+        if swap_direction == 0 {
+            self.swap_coin_in_amount = amount_in;
+            self.swap_pc_out_amount = amount_out;
+            self.swap_coin_to_pc_fee = fee_amount;
+            self.swap_coin_in_amount_total =
+                self.swap_coin_in_amount_total.saturating_add(amount_in);
+            self.swap_pc_out_amount_total =
+                self.swap_pc_out_amount_total.saturating_add(amount_out);
+            self.swap_coin_to_pc_fee_total =
+                self.swap_coin_to_pc_fee_total.saturating_add(fee_amount);
+        } else {
+            self.swap_pc_in_amount = amount_in;
+            self.swap_coin_out_amount = amount_out;
+            self.swap_pc_to_coin_fee = fee_amount;
+            self.swap_pc_in_amount_total = self.swap_pc_in_amount_total.saturating_add(amount_in);
+            self.swap_coin_out_amount_total =
+                self.swap_coin_out_amount_total.saturating_add(amount_out);
+            self.swap_pc_to_coin_fee_total =
+                self.swap_pc_to_coin_fee_total.saturating_add(fee_amount);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_whitelisted_hooks_round_trip() {
+        let hook_a = Pubkey::new_unique();
+        let hook_b = Pubkey::new_unique();
+        let mut pool = Tradium::default();
+        pool.whitelisted_transfer_hooks[0] = hook_a;
+        pool.whitelisted_transfer_hooks[1] = hook_b;
+        pool.num_whitelisted_hooks = 2;
+
+        // Same encoding the get_whitelisted_hooks return data uses
+        let data = pool
+            .active_whitelisted_hooks()
+            .to_vec()
+            .try_to_vec()
+            .unwrap();
+        let decoded = Vec::<Pubkey>::try_from_slice(&data).unwrap();
+        assert_eq!(decoded, vec![hook_a, hook_b]);
+    }
+
+    #[test]
+    fn power_of_ten_exponent_only_accepts_powers_of_ten() {
+        assert_eq!(power_of_ten_exponent(1), Some(0));
+        assert_eq!(power_of_ten_exponent(1_000_000), Some(6));
+        assert_eq!(power_of_ten_exponent(10_000_000_000_000_000_000), Some(19));
+        assert_eq!(power_of_ten_exponent(0), None);
+        assert_eq!(power_of_ten_exponent(6), None);
+        assert_eq!(power_of_ten_exponent(1_000_001), None);
+    }
+
+    #[test]
+    fn active_whitelisted_hooks_clamps_corrupted_count() {
+        let pool = Tradium {
+            num_whitelisted_hooks: u8::MAX,
+            ..Default::default()
+        };
+        assert_eq!(pool.active_whitelisted_hooks().len(), MAX_WHITELISTED_HOOKS);
+    }
+
+    #[test]
+    fn pause_lapses_after_the_timelock() {
+        let mut pool = Tradium {
+            status: POOL_STATUS_ACTIVE,
+            auto_unpause: true,
+            ..Default::default()
+        };
+        assert!(pool.is_active(1_000));
+
+        pool.pause(1_000).unwrap();
+        assert!(!pool.is_active(1_000));
+        assert!(!pool.is_active(1_000 + MAX_PAUSE_DURATION - 1));
+        assert!(pool.is_active(1_000 + MAX_PAUSE_DURATION));
+
+        // Re-pausing renews the timelock from the new pause
+        pool.pause(2_000).unwrap();
+        assert!(!pool.is_active(1_000 + MAX_PAUSE_DURATION));
+        assert!(pool.is_active(2_000 + MAX_PAUSE_DURATION));
+
+        // An uninitialized pool is never active
+        assert!(!Tradium::default().is_active(0));
+    }
+
+    #[test]
+    fn pause_holds_without_auto_unpause() {
+        let mut pool = Tradium {
+            status: POOL_STATUS_ACTIVE,
+            ..Default::default()
+        };
+        pool.pause(1_000).unwrap();
+        assert!(!pool.is_active(1_000 + MAX_PAUSE_DURATION));
+        assert!(!pool.is_active(i64::MAX));
+
+        // Opting in mid-pause honours the expiry recorded when it was paused
+        pool.auto_unpause = true;
+        assert!(!pool.is_active(1_000 + MAX_PAUSE_DURATION - 1));
+        assert!(pool.is_active(1_000 + MAX_PAUSE_DURATION));
+    }
+
+    #[test]
+    fn tradium_layout_size_is_pinned() {
+        // Existing pool accounts were allocated at 8 + 1654 bytes. Any field
+        // change (e.g. moving the nonce counter into padding) must keep this.
+        assert_eq!(Tradium::INIT_SPACE, 1654);
+        assert_eq!(TRADIUM_ALLOCATED_SPACE, 1654);
+        assert_eq!(StateData::INIT_SPACE, 684);
+        assert_eq!(Fees::INIT_SPACE, 64);
+
+        // The serialized account matches the allocated space exactly
+        let data = Tradium::default().try_to_vec().unwrap();
+        assert_eq!(data.len(), Tradium::INIT_SPACE);
+
+        // `nonce` is a single byte at offset 8, right after `status`
+        let pool = Tradium {
+            nonce: [0xAB],
+            ..Default::default()
+        };
+        let data = pool.try_to_vec().unwrap();
+        assert_eq!(data[8], 0xAB);
+        assert_eq!(data[9..17], [0u8; 8]);
+
+        // Repurposed fields keep the offsets of the fields they replaced:
+        // `amp_coefficient` is the old `order_num`, `liquidity_lock` `depth`
+        let pool = Tradium {
+            amp_coefficient: 0x0101_0101_0101_0101,
+            liquidity_lock: 0x0202_0202_0202_0202,
+            ..Default::default()
+        };
+        let data = pool.try_to_vec().unwrap();
+        assert_eq!(data[9..17], [1u8; 8]);
+        assert_eq!(data[17..25], [2u8; 8]);
+
+        // A field inserted rather than repurposed shifts everything after it;
+        // the tail keeps the original offsets, `min_coin_reserve` the first
+        // `padding1` word, `large_swap_threshold_pc` the old `client_order_id`
+        // and `pc_fees_owed` the old `padding2`
+        let owner = Pubkey::new_from_array([3u8; 32]);
+        let pool = Tradium {
+            has_any_hook_mints: true,
+            auto_unpause: true,
+            num_whitelisted_hooks: 4,
+            min_coin_reserve: 0x0505_0505_0505_0505,
+            amm_owner: owner,
+            large_swap_threshold_pc: 0x0707_0707_0707_0707,
+            pc_fees_owed: 0x0606_0606_0606_0606,
+            ..Default::default()
+        };
+        let data = pool.try_to_vec().unwrap();
+        assert_eq!(data[1051], 1);
+        assert_eq!(data[1052], 1);
+        assert_eq!(data[1541], 4);
+        assert_eq!(data[1542..1550], [5u8; 8]);
+        assert_eq!(data[1590..1622], [3u8; 32]);
+        assert_eq!(data[1630..1638], [7u8; 8]);
+        assert_eq!(data[1646..1654], [6u8; 8]);
+    }
+
+    #[test]
+    fn compounding_owed_fees_raises_lp_value() {
+        let mut pool = Tradium {
+            coin_fees_owed: 1_000,
+            pc_fees_owed: 4_000,
+            ..Default::default()
+        };
+        let (coin_vault, pc_vault, lp_supply) = (101_000, 404_000, 200_000);
+
+        let (coin, pc) = pool.active_reserves(coin_vault, pc_vault).unwrap();
+        assert_eq!((coin, pc), (100_000, 400_000));
+        let price_before = crate::curve::lp_price_in_pc(coin, pc, lp_supply).unwrap();
+
+        assert_eq!(pool.compound_owed_fees(), (1_000, 4_000));
+        assert_eq!((pool.coin_fees_owed, pool.pc_fees_owed), (0, 0));
+
+        let (coin, pc) = pool.active_reserves(coin_vault, pc_vault).unwrap();
+        assert_eq!((coin, pc), (coin_vault, pc_vault));
+        let price_after = crate::curve::lp_price_in_pc(coin, pc, lp_supply).unwrap();
+        assert!(price_after > price_before);
+    }
+
+    #[test]
+    fn swap_fees_accrue_to_the_input_side_until_taken() {
+        let mut pool = Tradium::default();
+        pool.accrue_owner_fee(0, 30).unwrap();
+        pool.accrue_owner_fee(1, 50).unwrap();
+        pool.accrue_owner_fee(0, 20).unwrap();
+        assert_eq!((pool.coin_fees_owed, pool.pc_fees_owed), (50, 50));
+
+        // Owed fees stay out of the reserves the curve prices against
+        assert_eq!(pool.active_reserves(1_050, 2_050).unwrap(), (1_000, 2_000));
+
+        assert_eq!(pool.take_owed_fees(), (50, 50));
+        assert_eq!((pool.coin_fees_owed, pool.pc_fees_owed), (0, 0));
+
+        pool.coin_fees_owed = u64::MAX;
+        assert_eq!(
+            pool.accrue_owner_fee(0, 1).unwrap_err(),
+            TradiumError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn swap_fee_splits_into_trade_and_owner_parts() {
+        use crate::constants::{DEFAULT_OWNER_FEE, DEFAULT_TRADE_FEE, FEE_DENOMINATOR};
+
+        let fees = Fees {
+            trade_fee_numerator: DEFAULT_TRADE_FEE,
+            trade_fee_denominator: FEE_DENOMINATOR,
+            swap_fee_numerator: DEFAULT_OWNER_FEE,
+            swap_fee_denominator: FEE_DENOMINATOR,
+            ..Default::default()
+        };
+        assert_eq!(fees.total_swap_fee().unwrap(), (35, FEE_DENOMINATOR));
+        assert_eq!(fees.owner_fee_share(35).unwrap(), 5);
+        // The owner's part rounds down, leaving the remainder to LPs
+        assert_eq!(fees.owner_fee_share(100).unwrap(), 14);
+
+        // The same 0.3% + 0.05% over mixed denominators
+        let mixed = Fees {
+            trade_fee_numerator: 3,
+            trade_fee_denominator: 1_000,
+            ..fees
+        };
+        let (numerator, denominator) = mixed.total_swap_fee().unwrap();
+        assert_eq!(numerator * FEE_DENOMINATOR, 35 * denominator);
+        assert_eq!(mixed.owner_fee_share(35).unwrap(), 5);
+
+        // Without a trade fee the whole fee is the owner's
+        let owner_only = Fees {
+            trade_fee_numerator: 0,
+            ..fees
+        };
+        assert_eq!(owner_only.total_swap_fee().unwrap(), (5, FEE_DENOMINATOR));
+        assert_eq!(owner_only.owner_fee_share(5).unwrap(), 5);
+    }
+
+    #[test]
+    fn fee_steps_up_after_bootstrap_period() {
+        let mut pool = Tradium {
+            bootstrap_swaps_remaining: 2,
+            bootstrap_fee_numerator: 0,
+            ..Default::default()
+        };
+        pool.fees.swap_fee_numerator = 5;
+
+        let mut charged = vec![];
+        for _ in 0..3 {
+            charged.push(pool.effective_swap_fee().unwrap().0);
+            pool.bootstrap_swaps_remaining = pool.bootstrap_swaps_remaining.saturating_sub(1);
+        }
+        assert_eq!(charged, vec![0, 0, 5]);
+    }
+
+    #[test]
+    fn counter_wraps_at_ceiling_without_touching_bump() {
+        let mut pool = Tradium {
+            nonce: [254],
+            counter: u64::MAX - 1,
+            ..Default::default()
+        };
+        pool.advance_counter();
+        assert_eq!(pool.counter, u64::MAX);
+        pool.advance_counter();
+        assert_eq!(pool.counter, 0);
+        assert_eq!(pool.nonce, [254]);
+    }
+
+    #[test]
+    fn activity_timestamp_advances_and_never_rewinds() {
+        let mut pool = Tradium {
+            created_at: 1_000,
+            last_activity_ts: 1_000,
+            ..Default::default()
+        };
+        // A swap at a later time advances the timestamp
+        pool.record_activity(1_060);
+        assert_eq!(pool.last_activity_ts, 1_060);
+        // Clock skew between validators does not rewind it
+        pool.record_activity(1_059);
+        assert_eq!(pool.last_activity_ts, 1_060);
+        assert_eq!(pool.created_at, 1_000);
+    }
+
+    #[test]
+    fn signer_seeds_derive_the_pool_address() {
+        let (coin_mint, pc_mint) =
+            crate::pda::canonical_mint_order(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let (pool_key, bump) = crate::pda::pool_pda(&coin_mint, &pc_mint);
+        let pool = Tradium {
+            coin_vault_mint: coin_mint,
+            pc_vault_mint: pc_mint,
+            bump,
+            ..Default::default()
+        };
+        assert_eq!(
+            Pubkey::create_program_address(&pool.signer_seeds(), &crate::ID).unwrap(),
+            pool_key
+        );
+    }
+
+    #[test]
+    fn drifted_bump_is_a_bump_mismatch() {
+        let (coin_mint, pc_mint) =
+            crate::pda::canonical_mint_order(&Pubkey::new_unique(), &Pubkey::new_unique());
+        let (pool_key, bump) = crate::pda::pool_pda(&coin_mint, &pc_mint);
+        let mut pool = Tradium {
+            coin_vault_mint: coin_mint,
+            pc_vault_mint: pc_mint,
+            bump,
+            ..Default::default()
+        };
+        assert!(pool.check_bump(&pool_key).is_ok());
+
+        pool.bump = bump.wrapping_sub(1);
+        assert_eq!(
+            pool.check_bump(&pool_key).unwrap_err(),
+            TradiumError::BumpMismatch.into()
+        );
+        // Nor is a pool account at some other address accepted
+        pool.bump = bump;
+        assert_eq!(
+            pool.check_bump(&Pubkey::new_unique()).unwrap_err(),
+            TradiumError::BumpMismatch.into()
+        );
+    }
+
+    #[test]
+    fn swap_volume_totals_accumulate_per_direction() {
+        let mut state = StateData::default();
+        state.record_swap_volume(0, 1_000, 990, 3);
+        state.record_swap_volume(0, 2_000, 1_970, 6);
+        state.record_swap_volume(1, 500, 495, 1);
+
+        // The last coin-to-pc swap, and both of them in the totals
+        assert_eq!(
+            (
+                state.swap_coin_in_amount,
+                state.swap_pc_out_amount,
+                state.swap_coin_to_pc_fee
+            ),
+            (2_000, 1_970, 6)
+        );
+        assert_eq!(
+            (
+                state.swap_coin_in_amount_total,
+                state.swap_pc_out_amount_total,
+                state.swap_coin_to_pc_fee_total
+            ),
+            (3_000, 2_960, 9)
+        );
+        assert_eq!(
+            (
+                state.swap_pc_in_amount_total,
+                state.swap_coin_out_amount_total,
+                state.swap_pc_to_coin_fee_total
+            ),
+            (500, 495, 1)
+        );
+
+        // Totals saturate instead of failing the swap
+        state.swap_coin_in_amount_total = u64::MAX - 1;
+        state.record_swap_volume(0, 1_000, 990, 3);
+        assert_eq!(state.swap_coin_in_amount_total, u64::MAX);
+    }
+
+    #[test]
+    fn deposit_totals_are_net_of_withdrawals() {
+        let mut state = StateData::default();
+        state.record_deposit(1_000, 4_000);
+        state.record_deposit(500, 2_000);
+        assert_eq!(
+            (state.pool_total_deposit_coin, state.pool_total_deposit_pc),
+            (1_500, 6_000)
+        );
+
+        state.record_withdrawal(300, 1_200);
+        assert_eq!(
+            (state.pool_total_deposit_coin, state.pool_total_deposit_pc),
+            (1_200, 4_800)
+        );
+
+        // Withdrawing swap gains past the deposits floors the totals at zero
+        state.record_withdrawal(2_000, 1_000);
+        assert_eq!(
+            (state.pool_total_deposit_coin, state.pool_total_deposit_pc),
+            (0, 3_800)
+        );
+    }
+}